@@ -31,26 +31,29 @@ fn main() {
 
     let mut cx = context::Context::new();
 
-    let vs = match shader::Shader::compile_vertex_shader(include_str!("gl_triangle.vert")) {
+    let vs = match shader::Shader::compile_versioned(include_str!("gl_triangle.vert"), shader::ShaderKind::Vertex) {
         Ok(r) => r,
         Err(e) => panic!("{}", e),
     };
 
-    let fs = match shader::Shader::compile_fragment_shader(include_str!("gl_triangle.frag")) {
+    let fs = match shader::Shader::compile_versioned(include_str!("gl_triangle.frag"), shader::ShaderKind::Fragment) {
         Ok(r) => r,
         Err(e) => panic!("{}", e),
     };
 
-    let mut program = match program::Program::link_new(&[Rc::new(vs), Rc::new(fs)]) {
-        Ok(r) => r,
+    let mut program = match program::Program::build()
+        .shader(Rc::new(vs))
+        .shader(Rc::new(fs))
+        .frag_data_location(0, "out_color")
+        .link()
+    {
+        Ok((r, _)) => r,
         Err(e) => panic!("{}", e),
     };
 
     let vao = cx.vertex_array.gen_one();
     let vbo = cx.buffers.gen_one();
 
-    program.bind_frag_data_location(0, "out_color");
-
     if let Ok(pos) = program.get_attrib_location("position") {
         let mut bound_vao = cx.vertex_array.bind(&vao);
         bound_vao.enable_attrib(pos);
@@ -76,10 +79,10 @@ fn main() {
         unsafe {
             gl::ClearColor(0.1, 0.1, 0.4, 1.0);
             gl::Clear(gl::COLOR_BUFFER_BIT);
-
-            gl::DrawArrays(gl::TRIANGLES, 0, 3);
         }
 
+        cx.draw_arrays(gl::TRIANGLES, 0, 3).unwrap();
+
         cx.vertex_array.unbind(bound_vao);
     });
 }
@@ -1,5 +1,3 @@
-#version 150
-
 in vec2 position;
 
 void main() {
@@ -0,0 +1,136 @@
+use gl;
+use gl::types::*;
+
+use std::fmt;
+use std::ops::{ BitOr, BitAnd };
+
+/// Set of memory barrier bits for `glMemoryBarrier`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct MemoryBarrier {
+    bits: GLbitfield,
+}
+
+pub const VERTEX_ATTRIB_ARRAY_BARRIER: MemoryBarrier = MemoryBarrier { bits: gl::VERTEX_ATTRIB_ARRAY_BARRIER_BIT };
+pub const ELEMENT_ARRAY_BARRIER: MemoryBarrier = MemoryBarrier { bits: gl::ELEMENT_ARRAY_BARRIER_BIT };
+pub const UNIFORM_BARRIER: MemoryBarrier = MemoryBarrier { bits: gl::UNIFORM_BARRIER_BIT };
+pub const TEXTURE_FETCH_BARRIER: MemoryBarrier = MemoryBarrier { bits: gl::TEXTURE_FETCH_BARRIER_BIT };
+pub const SHADER_IMAGE_ACCESS_BARRIER: MemoryBarrier = MemoryBarrier { bits: gl::SHADER_IMAGE_ACCESS_BARRIER_BIT };
+pub const COMMAND_BARRIER: MemoryBarrier = MemoryBarrier { bits: gl::COMMAND_BARRIER_BIT };
+pub const PIXEL_BUFFER_BARRIER: MemoryBarrier = MemoryBarrier { bits: gl::PIXEL_BUFFER_BARRIER_BIT };
+pub const TEXTURE_UPDATE_BARRIER: MemoryBarrier = MemoryBarrier { bits: gl::TEXTURE_UPDATE_BARRIER_BIT };
+pub const BUFFER_UPDATE_BARRIER: MemoryBarrier = MemoryBarrier { bits: gl::BUFFER_UPDATE_BARRIER_BIT };
+pub const FRAMEBUFFER_BARRIER: MemoryBarrier = MemoryBarrier { bits: gl::FRAMEBUFFER_BARRIER_BIT };
+pub const TRANSFORM_FEEDBACK_BARRIER: MemoryBarrier = MemoryBarrier { bits: gl::TRANSFORM_FEEDBACK_BARRIER_BIT };
+pub const ATOMIC_COUNTER_BARRIER: MemoryBarrier = MemoryBarrier { bits: gl::ATOMIC_COUNTER_BARRIER_BIT };
+pub const SHADER_STORAGE_BARRIER: MemoryBarrier = MemoryBarrier { bits: gl::SHADER_STORAGE_BARRIER_BIT };
+pub const ALL_BARRIERS: MemoryBarrier = MemoryBarrier { bits: gl::ALL_BARRIER_BITS };
+
+impl MemoryBarrier {
+    /// Empty barrier set.
+    pub fn empty() -> MemoryBarrier {
+        MemoryBarrier { bits: 0 }
+    }
+
+    pub fn bits(&self) -> GLbitfield {
+        self.bits
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.bits == 0
+    }
+
+    pub fn contains(&self, other: MemoryBarrier) -> bool {
+        self.bits & other.bits == other.bits
+    }
+}
+
+impl BitOr for MemoryBarrier {
+    type Output = MemoryBarrier;
+
+    fn bitor(self, other: MemoryBarrier) -> MemoryBarrier {
+        MemoryBarrier { bits: self.bits | other.bits }
+    }
+}
+
+impl BitAnd for MemoryBarrier {
+    type Output = MemoryBarrier;
+
+    fn bitand(self, other: MemoryBarrier) -> MemoryBarrier {
+        MemoryBarrier { bits: self.bits & other.bits }
+    }
+}
+
+/// Implementation limits for compute dispatch.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct ComputeLimits {
+    pub max_work_group_count: [GLuint; 3],
+    pub max_work_group_size: [GLuint; 3],
+    pub max_work_group_invocations: GLuint,
+}
+
+impl ComputeLimits {
+
+    /// Query compute limits of the current context.
+    ///
+    /// ## glGetIntegeri_v(GL_MAX_COMPUTE_WORK_GROUP_COUNT)
+    ///
+    /// - OpenGL Version 4.3
+    /// - OpenGL ES Version 3.1
+    ///
+    pub fn query() -> ComputeLimits {
+        let mut count: [GLint; 3] = [0; 3];
+        let mut size: [GLint; 3] = [0; 3];
+        let mut invocations: GLint = 0;
+
+        unsafe {
+            for i in 0..3 {
+                gl::GetIntegeri_v(gl::MAX_COMPUTE_WORK_GROUP_COUNT, i as GLuint, &mut count[i]);
+                gl::GetIntegeri_v(gl::MAX_COMPUTE_WORK_GROUP_SIZE, i as GLuint, &mut size[i]);
+            }
+            gl::GetIntegerv(gl::MAX_COMPUTE_WORK_GROUP_INVOCATIONS, &mut invocations);
+        }
+
+        let limits = ComputeLimits {
+            max_work_group_count: [count[0] as GLuint, count[1] as GLuint, count[2] as GLuint],
+            max_work_group_size: [size[0] as GLuint, size[1] as GLuint, size[2] as GLuint],
+            max_work_group_invocations: invocations as GLuint,
+        };
+
+        debug!("compute limits, {:?}", limits);
+
+        limits
+    }
+
+    /// Check that `groups` does not exceed maximum work group count.
+    pub fn check_work_group_count(&self, groups: [GLuint; 3]) -> Result<(), DispatchError> {
+        for axis in 0..3 {
+            if groups[axis] > self.max_work_group_count[axis] {
+                return Err(DispatchError::WorkGroupCountExceeded {
+                    axis: axis,
+                    count: groups[axis],
+                    max: self.max_work_group_count[axis],
+                });
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum DispatchError {
+    NoProgramInUse,
+    NoIndirectBuffer,
+    UnalignedIndirectOffset(usize),
+    WorkGroupCountExceeded { axis: usize, count: GLuint, max: GLuint },
+}
+
+impl fmt::Display for DispatchError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            &DispatchError::NoProgramInUse => "Tried to dispatch compute without program in use.".fmt(f),
+            &DispatchError::NoIndirectBuffer => "Tried to dispatch compute indirectly without bound dispatch indirect buffer.".fmt(f),
+            &DispatchError::UnalignedIndirectOffset(offset) => write!(f, "Tried to dispatch compute indirectly with offset {} not aligned to 4 bytes.", offset),
+            &DispatchError::WorkGroupCountExceeded { axis, count, max } => write!(f, "Tried to dispatch {} work groups on axis {}, but maximum is {}.", count, axis, max),
+        }
+    }
+}
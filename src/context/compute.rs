@@ -0,0 +1,128 @@
+use gl;
+use gl::types::*;
+
+use compute::{ ComputeLimits, DispatchError, MemoryBarrier };
+use compute::{ SHADER_IMAGE_ACCESS_BARRIER, TEXTURE_FETCH_BARRIER, TEXTURE_UPDATE_BARRIER };
+
+use super::Context;
+
+impl Context {
+
+    /// Get compute limits, querying them on first use.
+    pub fn compute_limits(&mut self) -> ComputeLimits {
+        match self.compute_limits {
+            Some(limits) => limits,
+            None => {
+                let limits = ComputeLimits::query();
+                self.compute_limits = Some(limits);
+                limits
+            }
+        }
+    }
+
+    /// Launch compute work groups with program in use.
+    ///
+    /// Group counts are checked against `GL_MAX_COMPUTE_WORK_GROUP_COUNT`.
+    ///
+    /// ## glDispatchCompute
+    ///
+    /// - OpenGL Version 4.3
+    /// - OpenGL ES Version 3.1
+    ///
+    pub fn dispatch_compute(&mut self, x: GLuint, y: GLuint, z: GLuint) -> Result<(), DispatchError> {
+        trace!("dispatch compute, {} x {} x {}", x, y, z);
+
+        if self.program.get_id().is_none() {
+            error!("dispatch error, {}", DispatchError::NoProgramInUse);
+            return Err(DispatchError::NoProgramInUse);
+        }
+
+        if let Err(err) = self.compute_limits().check_work_group_count([x, y, z]) {
+            error!("dispatch error, {}", err);
+            return Err(err);
+        }
+
+        unsafe { gl::DispatchCompute(x, y, z) };
+
+        self.images.mark_written();
+
+        Ok(())
+    }
+
+    /// Launch compute work groups with counts read from bound dispatch indirect buffer.
+    ///
+    /// The `offset` is in bytes and must be a multiple of 4. Group counts stored
+    /// in the buffer can not be checked against limits.
+    ///
+    /// ## glDispatchComputeIndirect
+    ///
+    /// - OpenGL Version 4.3
+    /// - OpenGL ES Version 3.1
+    ///
+    pub fn dispatch_compute_indirect(&mut self, offset: usize) -> Result<(), DispatchError> {
+        trace!("dispatch compute indirect, offset = {}", offset);
+
+        let err = if self.program.get_id().is_none() {
+            Some(DispatchError::NoProgramInUse)
+        } else if self.buffers.dispatch_indirect.get_bound_id().is_none() {
+            Some(DispatchError::NoIndirectBuffer)
+        } else if offset % 4 != 0 {
+            Some(DispatchError::UnalignedIndirectOffset(offset))
+        } else {
+            None
+        };
+
+        if let Some(err) = err {
+            error!("dispatch error, {}", err);
+            return Err(err);
+        }
+
+        unsafe { gl::DispatchComputeIndirect(offset as GLintptr) };
+
+        self.images.mark_written();
+
+        Ok(())
+    }
+
+    /// Order memory transactions issued before the barrier relative to those issued after it.
+    ///
+    /// Pending image writes are forgotten only if `barriers` includes all of
+    /// the barriers issued by `image_barrier`.
+    ///
+    /// ## glMemoryBarrier
+    ///
+    /// - OpenGL Version 4.2
+    /// - OpenGL ES Version 3.1
+    ///
+    pub fn memory_barrier(&mut self, barriers: MemoryBarrier) {
+        trace!("memory barrier, {:x}", barriers.bits());
+        unsafe { gl::MemoryBarrier(barriers.bits()) };
+
+        if barriers.contains(image_write_barriers()) {
+            self.images.clear_pending_writes();
+        }
+    }
+
+    /// Make image stores of previous dispatches and draws visible to image loads, texture fetches and texture updates.
+    ///
+    /// Does nothing if no dispatch or draw ran with an image bound for writing
+    /// since the last barrier covering image writes.
+    ///
+    /// ## glMemoryBarrier
+    ///
+    /// - OpenGL Version 4.2
+    /// - OpenGL ES Version 3.1
+    ///
+    pub fn image_barrier(&mut self) {
+        if !self.images.has_pending_writes() {
+            trace!("no pending image writes");
+            return;
+        }
+        self.memory_barrier(image_write_barriers());
+    }
+}
+
+/// Barriers that make image stores visible to image loads, texture fetches and texture updates.
+fn image_write_barriers() -> MemoryBarrier {
+    SHADER_IMAGE_ACCESS_BARRIER | TEXTURE_FETCH_BARRIER | TEXTURE_UPDATE_BARRIER
+}
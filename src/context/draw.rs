@@ -0,0 +1,218 @@
+use gl;
+use gl::types::*;
+
+use program;
+use program::{ Program, ProgramError };
+use uniform::UniformValue;
+use transform_feedback::{ TransformFeedback, TransformFeedbackError };
+use texture::TextureTarget;
+
+use super::Context;
+
+/// Validated draw states are forgotten when this many accumulate.
+const MAX_VALIDATED_DRAW_STATES: usize = 256;
+
+/// Combination of GL state that a program was validated against.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct DrawStateKey {
+    pub program: GLuint,
+    pub vertex_array: Option<GLuint>,
+    /// Bound textures as unit, target and texture name.
+    pub textures: Vec<(GLuint, TextureTarget, GLuint)>,
+    /// Bound samplers as unit and sampler name.
+    pub samplers: Vec<(GLuint, GLuint)>,
+    /// Bound images as image unit and texture name.
+    pub images: Vec<(GLuint, GLuint)>,
+}
+
+impl Context {
+
+    /// Forget draw states programs were validated against.
+    ///
+    /// Called by `link_program`, `set_uniform` of sampler uniforms and `delete`.
+    /// Call it after changing such state without going through the context.
+    pub fn invalidate_draw_states(&mut self) {
+        self.draw_state_generation += 1;
+    }
+
+    /// Link program and forget draw states it was validated against.
+    pub fn link_program(&mut self, program: &mut Program) -> Result<(), ProgramError> {
+        self.invalidate_draw_states();
+        program.link()
+    }
+
+    /// Set value of program uniform at `location`.
+    ///
+    /// Draw states are forgotten only if the uniform is a sampler, because
+    /// other uniform values do not take part in validation.
+    pub fn set_uniform(&mut self, program: &mut Program, location: GLint, value: &UniformValue) {
+        if program.is_sampler_location(location) {
+            self.invalidate_draw_states();
+        }
+        program.set_uniform(location, value);
+    }
+
+    /// Drop program, texture, sampler or vertex array handle and forget validated draw states,
+    /// because the name can be reused once the last handle is dropped.
+    pub fn delete<T>(&mut self, object: T) {
+        drop(object);
+        self.invalidate_draw_states();
+    }
+
+    /// Render primitives from array data.
+    ///
+    /// In debug builds the program in use is validated before the first draw
+    /// with each combination of bound state.
+    ///
+    /// Fails if active transform feedback can not capture the drawn primitives.
+    ///
+    /// ## glDrawArrays
+    ///
+    /// - OpenGL Version 2.0
+    /// - OpenGL ES Version 2.0
+    ///
+    pub fn draw_arrays(&mut self, mode: GLenum, first: GLint, count: GLsizei) -> Result<(), TransformFeedbackError> {
+        trace!("draw arrays, mode = {}, first = {}, count = {}", mode, first, count);
+
+        if cfg!(debug_assertions) {
+            self.validate_draw_state();
+        }
+
+        if let Err(err) = self.check_transform_feedback_mode(mode) {
+            return Err(err);
+        }
+
+        unsafe { gl::DrawArrays(mode, first, count) };
+
+        self.images.mark_written();
+
+        Ok(())
+    }
+
+    /// Render primitives from array data using indices from bound element array buffer.
+    ///
+    /// In debug builds the program in use is validated before the first draw
+    /// with each combination of bound state.
+    ///
+    /// Fails if active transform feedback can not capture the drawn primitives.
+    ///
+    /// ## glDrawElements
+    ///
+    /// - OpenGL Version 2.0
+    /// - OpenGL ES Version 2.0
+    ///
+    pub fn draw_elements(&mut self, mode: GLenum, count: GLsizei, ty: GLenum, offset: usize) -> Result<(), TransformFeedbackError> {
+        trace!("draw elements, mode = {}, count = {}, type = {}, offset = {}", mode, count, ty, offset);
+
+        if cfg!(debug_assertions) {
+            self.validate_draw_state();
+        }
+
+        if let Err(err) = self.check_transform_feedback_mode(mode) {
+            return Err(err);
+        }
+
+        unsafe { gl::DrawElements(mode, count, ty, offset as *const GLvoid) };
+
+        self.images.mark_written();
+
+        Ok(())
+    }
+
+    /// Render primitives captured by transform feedback object, without querying vertex count.
+    ///
+    /// In debug builds the program in use is validated before the first draw
+    /// with each combination of bound state.
+    ///
+    /// Fails if active transform feedback can not capture the drawn primitives.
+    ///
+    /// ## glDrawTransformFeedback
+    ///
+    /// - OpenGL Version 4.0
+    ///
+    pub fn draw_transform_feedback(&mut self, mode: GLenum, transform_feedback: &TransformFeedback) -> Result<(), TransformFeedbackError> {
+        trace!("draw transform feedback {}, mode = {}", transform_feedback.get_id(), mode);
+
+        if cfg!(debug_assertions) {
+            self.validate_draw_state();
+        }
+
+        if let Err(err) = self.check_transform_feedback_mode(mode) {
+            return Err(err);
+        }
+
+        unsafe { gl::DrawTransformFeedback(mode, transform_feedback.get_id()) };
+
+        self.images.mark_written();
+
+        Ok(())
+    }
+
+    /// Get the combination of state the next draw call will use.
+    pub fn draw_state_key(&self) -> Option<DrawStateKey> {
+        self.program.get_id().map(|program| DrawStateKey {
+            program: program,
+            vertex_array: self.vertex_array.get_bound_id(),
+            textures: self.textures.bound_ids(),
+            samplers: self.samplers.bound_ids(),
+            images: self.images.bound_ids(),
+        })
+    }
+
+    /// Validate the program in use against current state, once per state combination.
+    ///
+    /// Remembered combinations are forgotten after `invalidate_draw_states`.
+    ///
+    /// Panics if the program is not valid, because the draw call would fail.
+    pub fn validate_draw_state(&mut self) {
+        let key = match self.draw_state_key() {
+            Some(key) => key,
+            None => {
+                error!("draw without program in use");
+                panic!("Can not draw without program in use.");
+            }
+        };
+
+        let generation = self.draw_state_generation;
+        if generation != self.validated_generation || self.validated_draw_states.len() >= MAX_VALIDATED_DRAW_STATES {
+            trace!("forget {} validated draw states", self.validated_draw_states.len());
+            self.validated_draw_states.clear();
+            self.validated_generation = generation;
+        }
+
+        if self.validated_draw_states.contains(&key) {
+            return;
+        }
+
+        match program::validate_program(key.program) {
+            Ok(ref validation) if validation.valid => {
+                debug!("[{}]: valid for {:?}", key.program, key);
+            },
+            Ok(validation) => {
+                error!("[{}]: invalid for {:?}, {}", key.program, key, validation);
+                panic!("Program {} is not valid for current state: {}", key.program, validation);
+            },
+            Err(err) => {
+                error!("[{}]: failed to validate for {:?}, {}", key.program, key, err);
+                panic!("Failed to validate program {}: {}", key.program, err);
+            },
+        }
+
+        self.validated_draw_states.insert(key);
+    }
+
+    fn check_transform_feedback_mode(&self, mode: GLenum) -> Result<(), TransformFeedbackError> {
+        let program = match self.program.get_id() {
+            Some(program) => program,
+            None => return Ok(()),
+        };
+
+        match self.transform_feedback.check_draw_mode(mode, program) {
+            Ok(()) => Ok(()),
+            Err(err) => {
+                error!("[{}]: draw error, {}", program, err);
+                Err(err)
+            },
+        }
+    }
+}
@@ -1,12 +1,31 @@
+use std::collections::HashSet;
+
 use state_buffer::BufferTargets;
 use state_program::StateProgram;
 use vertex_array::VertexArrayState;
+use transform_feedback::TransformFeedbackState;
+use compute::ComputeLimits;
+use texture::{ TextureUnits, ImageUnits };
+use sampler::SamplerState;
+
+pub use self::draw::DrawStateKey;
+
+mod draw;
+mod compute;
 
 /// GL context root.
 pub struct Context {
     pub buffers: BufferTargets,
     pub program: StateProgram,
     pub vertex_array: VertexArrayState,
+    pub transform_feedback: TransformFeedbackState,
+    pub textures: TextureUnits,
+    pub samplers: SamplerState,
+    pub images: ImageUnits,
+    validated_draw_states: HashSet<DrawStateKey>,
+    draw_state_generation: usize,
+    validated_generation: usize,
+    compute_limits: Option<ComputeLimits>,
 }
 
 impl Context {
@@ -15,6 +34,14 @@ impl Context {
             buffers: BufferTargets::new(),
             program: StateProgram::new(),
             vertex_array: VertexArrayState::new(),
+            transform_feedback: TransformFeedbackState::new(),
+            textures: TextureUnits::new(),
+            samplers: SamplerState::new(),
+            images: ImageUnits::new(),
+            validated_draw_states: HashSet::new(),
+            draw_state_generation: 0,
+            validated_generation: 0,
+            compute_limits: None,
         }
     }
 }
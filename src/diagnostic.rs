@@ -0,0 +1,639 @@
+use std::fmt;
+
+use shader::ShaderKind;
+use preprocess::{ LineMap, SourceProvider };
+
+/// Severity of a diagnostic reported by the driver.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Severity {
+    Error,
+    Warning,
+    Info,
+}
+
+/// Single diagnostic parsed from a program info log.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LinkDiagnostic {
+    pub severity: Severity,
+    /// Stage the diagnostic refers to, if driver reported or mentioned it.
+    pub stage: Option<ShaderKind>,
+    /// First quoted symbol name in the message.
+    pub symbol: Option<String>,
+    pub message: String,
+}
+
+impl fmt::Display for LinkDiagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let severity = match self.severity {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+            Severity::Info => "info",
+        };
+        match self.stage {
+            Some(stage) => write!(f, "{} [{:?}]: {}", severity, stage, self.message),
+            None => write!(f, "{}: {}", severity, self.message),
+        }
+    }
+}
+
+/// Program info log with diagnostics parsed from it.
+///
+/// Understands Mesa, NVIDIA and AMD log formats. Lines that could not be
+/// parsed are kept in the `raw` text.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LinkLog {
+    pub raw: String,
+    pub diagnostics: Vec<LinkDiagnostic>,
+}
+
+impl LinkLog {
+    pub fn parse(raw: &str) -> LinkLog {
+        let mut diagnostics: Vec<LinkDiagnostic> = Vec::new();
+        let mut section_stage = None;
+
+        for line in raw.lines() {
+            let line = line.trim();
+
+            if line.len() == 0 || line.chars().all(|c| c == '-') {
+                continue;
+            }
+
+            // NVIDIA groups messages under "Vertex info", "Link info" and similar headers.
+            if line.ends_with(" info") {
+                section_stage = stage_from_text(line);
+                continue;
+            }
+
+            match split_severity(line) {
+                Some((severity, message)) => {
+                    let message = strip_error_code(message);
+                    diagnostics.push(LinkDiagnostic {
+                        severity: severity,
+                        stage: section_stage.or(stage_from_text(message)),
+                        symbol: first_quoted(message),
+                        message: message.to_string(),
+                    });
+                },
+                None => {
+                    // AMD prints a per-stage summary before messages.
+                    if line.contains("shader(s) linked") || line.contains("shader(s) failed to link") {
+                        continue;
+                    }
+                    if let Some(last) = diagnostics.last_mut() {
+                        last.message.push('\n');
+                        last.message.push_str(line);
+                    }
+                },
+            }
+        }
+
+        LinkLog {
+            raw: raw.to_string(),
+            diagnostics: diagnostics,
+        }
+    }
+
+    /// Diagnostics with error severity.
+    pub fn errors(&self) -> Vec<&LinkDiagnostic> {
+        self.diagnostics.iter()
+            .filter(|d| d.severity == Severity::Error)
+            .collect()
+    }
+}
+
+impl fmt::Display for LinkLog {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.diagnostics.len() == 0 {
+            return self.raw.fmt(f);
+        }
+
+        let lines: Vec<String> = self.diagnostics.iter()
+            .map(|d| d.to_string())
+            .collect();
+        lines.connect("\n").fmt(f)
+    }
+}
+
+/// Split "error: message", "ERROR: message" or "0(12) : error C1234: message" into parts.
+///
+/// Prefixes are tried before infixes, and the earliest infix wins, so a
+/// message quoting another severity keeps its own.
+fn split_severity(line: &str) -> Option<(Severity, &str)> {
+    // ASCII lowercasing keeps byte offsets valid for slicing `line`.
+    let lower = line.to_ascii_lowercase();
+
+    let severities = [
+        ("error", Severity::Error),
+        ("warning", Severity::Warning),
+        ("info", Severity::Info),
+    ];
+
+    for &(prefix, severity) in severities.iter() {
+        if lower.starts_with(prefix) {
+            let rest = &line[prefix.len()..];
+            return Some((severity, rest.trim_left_matches(|c: char| c == ':' || c == ' ')));
+        }
+    }
+
+    let infix = severities.iter()
+        .filter_map(|&(name, severity)| {
+            let infix = format!(": {}", name);
+            lower.find(&infix[..]).map(|pos| (pos, pos + infix.len(), severity))
+        })
+        .min_by_key(|&(pos, _, _)| pos);
+
+    infix.map(|(_, end, severity)| {
+        (severity, line[end..].trim_left_matches(|c: char| c == ':' || c == ' '))
+    })
+}
+
+/// Strip driver specific error codes such as "C5145:" or "(#277)".
+fn strip_error_code(message: &str) -> &str {
+    let message = message.trim();
+
+    if message.starts_with("(#") || message.starts_with("error(#") {
+        if let Some(end) = message.find(')') {
+            return message[end + 1..].trim_left_matches(|c: char| c == ':' || c == ' ');
+        }
+    }
+
+    if let Some(colon) = message.find(':') {
+        let code = &message[..colon];
+        if code.len() > 1
+            && code.starts_with("C")
+            && code[1..].chars().all(|c| c.is_digit(10))
+        {
+            return message[colon + 1..].trim();
+        }
+    }
+
+    message
+}
+
+/// Stage mentioned first in `text`, such as "fragment" in "fragment shader reads vertex output".
+fn stage_from_text(text: &str) -> Option<ShaderKind> {
+    let lower = text.to_lowercase();
+
+    let names = [
+        ("vertex", ShaderKind::Vertex),
+        ("tessellation control", ShaderKind::TessControl),
+        ("tess control", ShaderKind::TessControl),
+        ("tess_control", ShaderKind::TessControl),
+        ("tessellation evaluation", ShaderKind::TessEvaluation),
+        ("tess evaluation", ShaderKind::TessEvaluation),
+        ("tess_eval", ShaderKind::TessEvaluation),
+        ("geometry", ShaderKind::Geometry),
+        ("fragment", ShaderKind::Fragment),
+        ("compute", ShaderKind::Compute),
+    ];
+
+    names.iter()
+        .filter_map(|&(name, kind)| lower.find(name).map(|position| (position, kind)))
+        .min_by_key(|&(position, _)| position)
+        .map(|(_, kind)| kind)
+}
+
+/// Find first symbol quoted as `name' (Mesa), "name" (NVIDIA) or 'name' (AMD).
+///
+/// Opening quote must start the message or follow whitespace or punctuation,
+/// so apostrophes inside words such as "can't" are skipped.
+fn first_quoted(message: &str) -> Option<String> {
+    let mut previous: Option<char> = None;
+
+    for (start, open) in message.char_indices() {
+        let after_word = match previous {
+            Some(c) => c.is_alphanumeric() || c == '_',
+            None => false,
+        };
+        previous = Some(open);
+
+        let close = match open {
+            '`' => '\'',
+            '"' => '"',
+            '\'' => '\'',
+            _ => continue,
+        };
+        if after_word {
+            continue;
+        }
+
+        let rest = &message[start + 1..];
+        if let Some(end) = rest.find(close) {
+            if end > 0 {
+                return Some(rest[..end].to_string());
+            }
+        }
+    }
+
+    None
+}
+
+/// Single diagnostic parsed from a shader info log.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CompileDiagnostic {
+    pub severity: Severity,
+    /// Index of the source string the driver reported.
+    pub source: u32,
+    /// One-based line in the compiled source.
+    pub line: Option<u32>,
+    /// One-based column, reported only by some drivers.
+    pub column: Option<u32>,
+    pub message: String,
+    /// Original file and line, filled in by `CompileLog::map_lines`.
+    pub location: Option<(String, u32)>,
+}
+
+/// Shader info log with diagnostics parsed from it.
+///
+/// Understands Mesa `0:12(5): error: ...`, NVIDIA `0(12) : error C1234: ...`
+/// and AMD `ERROR: 0:12: ...` formats.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CompileLog {
+    pub raw: String,
+    pub diagnostics: Vec<CompileDiagnostic>,
+}
+
+impl CompileLog {
+    pub fn parse(raw: &str) -> CompileLog {
+        let mut diagnostics: Vec<CompileDiagnostic> = Vec::new();
+
+        for line in raw.lines() {
+            let line = line.trim();
+
+            if line.len() == 0 {
+                continue;
+            }
+
+            // AMD ends the log with a summary such as "1 compilation errors.  No code generated."
+            if line.contains("compilation errors") {
+                continue;
+            }
+
+            match parse_compile_line(line) {
+                Some(diagnostic) => diagnostics.push(diagnostic),
+                None => {
+                    if let Some(last) = diagnostics.last_mut() {
+                        last.message.push('\n');
+                        last.message.push_str(line);
+                    }
+                },
+            }
+        }
+
+        CompileLog {
+            raw: raw.to_string(),
+            diagnostics: diagnostics,
+        }
+    }
+
+    /// Diagnostics with error severity.
+    pub fn errors(&self) -> Vec<&CompileDiagnostic> {
+        self.diagnostics.iter()
+            .filter(|d| d.severity == Severity::Error)
+            .collect()
+    }
+
+    /// Map compiled source lines back to original files.
+    pub fn map_lines(&mut self, line_map: &LineMap) {
+        for diagnostic in self.diagnostics.iter_mut() {
+            diagnostic.location = diagnostic.line
+                .and_then(|line| line_map.map(line))
+                .map(|(file, line)| (file.to_string(), line));
+        }
+    }
+
+    /// Render diagnostics with offending source lines and carets.
+    ///
+    /// The `compiled` text is the source passed to the driver. Lines of
+    /// diagnostics mapped to original files are loaded from `provider` when
+    /// given, and from the compiled text otherwise.
+    pub fn render(&self, compiled: &str, provider: Option<&dyn SourceProvider>) -> String {
+        if self.diagnostics.len() == 0 {
+            return self.raw.clone();
+        }
+
+        let compiled_lines: Vec<&str> = compiled.lines().collect();
+        let mut loaded: Vec<(String, Option<String>)> = Vec::new();
+        let mut output = String::new();
+
+        for diagnostic in &self.diagnostics {
+            let severity = match diagnostic.severity {
+                Severity::Error => "error",
+                Severity::Warning => "warning",
+                Severity::Info => "info",
+            };
+            output.push_str(&format!("{}: {}\n", severity, diagnostic.message));
+
+            let compiled_line = diagnostic.line
+                .and_then(|line| compiled_lines.get((line as usize).wrapping_sub(1)))
+                .map(|text| text.to_string());
+
+            let (name, line, text) = match (&diagnostic.location, diagnostic.line) {
+                (&Some((ref file, line)), _) => {
+                    let original = provider.and_then(|provider| {
+                        if !loaded.iter().any(|&(ref name, _)| name == file) {
+                            loaded.push((file.clone(), provider.load(file)));
+                        }
+                        loaded.iter()
+                            .find(|&&(ref name, _)| name == file)
+                            .and_then(|&(_, ref source)| source.as_ref())
+                            .and_then(|source| source.lines().nth((line as usize).wrapping_sub(1)))
+                            .map(|text| text.to_string())
+                    });
+                    (file.clone(), line, original.or(compiled_line))
+                },
+                (&None, Some(line)) => (format!("<source {}>", diagnostic.source), line, compiled_line),
+                (&None, None) => {
+                    output.push('\n');
+                    continue;
+                },
+            };
+
+            let gutter = line.to_string().len();
+            let pad: String = (0..gutter).map(|_| ' ').collect();
+
+            match diagnostic.column {
+                Some(column) => output.push_str(&format!("{}--> {}:{}:{}\n", pad, name, line, column)),
+                None => output.push_str(&format!("{}--> {}:{}\n", pad, name, line)),
+            }
+
+            if let Some(text) = text {
+                let (start, len) = match diagnostic.column {
+                    Some(column) if column > 0 => ((column - 1) as usize, 1),
+                    _ => {
+                        let start = text.len() - text.trim_left().len();
+                        (start, text.trim().len())
+                    },
+                };
+                let indent: String = text.chars().take(start).map(|c| if c == '\t' { '\t' } else { ' ' }).collect();
+                let carets: String = (0..if len > 0 { len } else { 1 }).map(|_| '^').collect();
+
+                output.push_str(&format!("{} |\n", pad));
+                output.push_str(&format!("{} | {}\n", line, text));
+                output.push_str(&format!("{} | {}{}\n", pad, indent, carets));
+            }
+
+            output.push('\n');
+        }
+
+        output
+    }
+}
+
+impl fmt::Display for CompileLog {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.raw.fmt(f)
+    }
+}
+
+/// Parse single diagnostic line of Mesa, NVIDIA or AMD compile log.
+fn parse_compile_line(line: &str) -> Option<CompileDiagnostic> {
+    // AMD: "ERROR: 0:12: message"
+    if let Some((severity, rest)) = split_severity(line) {
+        if let Some((source, line_number, rest)) = parse_colon_location(rest) {
+            return Some(new_compile_diagnostic(severity, source, Some(line_number), None, rest));
+        }
+        // Plain "error: message" without location.
+        if line.to_lowercase().starts_with(match severity {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+            Severity::Info => "info",
+        }) {
+            return Some(new_compile_diagnostic(severity, 0, None, None, rest));
+        }
+    }
+
+    let (source, rest) = match parse_number(line) {
+        Some(result) => result,
+        None => return None,
+    };
+
+    let (line_number, column, rest) = if rest.starts_with(":") {
+        // Mesa: "0:12(5): error: message"
+        let (line_number, rest) = match parse_number(&rest[1..]) {
+            Some(result) => result,
+            None => return None,
+        };
+        let (column, rest) = if rest.starts_with("(") {
+            match parse_number(&rest[1..]) {
+                Some((column, rest)) if rest.starts_with(")") => (Some(column), &rest[1..]),
+                _ => return None,
+            }
+        } else {
+            (None, rest)
+        };
+        (line_number, column, rest)
+    } else if rest.starts_with("(") {
+        // NVIDIA: "0(12) : error C1234: message"
+        match parse_number(&rest[1..]) {
+            Some((line_number, rest)) if rest.starts_with(")") => (line_number, None, &rest[1..]),
+            _ => return None,
+        }
+    } else {
+        return None;
+    };
+
+    let rest = rest.trim_left_matches(|c: char| c == ':' || c == ' ');
+
+    match split_severity(rest) {
+        Some((severity, message)) => Some(new_compile_diagnostic(severity, source, Some(line_number), column, message)),
+        None => {
+            // Mesa reports "preprocessor error" and similar qualified severities.
+            let lower = rest.to_lowercase();
+            let severity = if lower.contains("error") {
+                Severity::Error
+            } else if lower.contains("warning") {
+                Severity::Warning
+            } else {
+                return None;
+            };
+            let message = match rest.find(": ") {
+                Some(pos) => &rest[pos + 2..],
+                None => rest,
+            };
+            Some(new_compile_diagnostic(severity, source, Some(line_number), column, message))
+        },
+    }
+}
+
+fn new_compile_diagnostic(severity: Severity, source: u32, line: Option<u32>, column: Option<u32>, message: &str) -> CompileDiagnostic {
+    CompileDiagnostic {
+        severity: severity,
+        source: source,
+        line: line,
+        column: column,
+        message: strip_error_code(message).to_string(),
+        location: None,
+    }
+}
+
+/// Parse "0:12: rest" location used by AMD.
+fn parse_colon_location(text: &str) -> Option<(u32, u32, &str)> {
+    let (source, rest) = match parse_number(text) {
+        Some(result) => result,
+        None => return None,
+    };
+    if !rest.starts_with(":") {
+        return None;
+    }
+    match parse_number(&rest[1..]) {
+        Some((line, rest)) if rest.starts_with(":") => Some((source, line, rest[1..].trim())),
+        _ => None,
+    }
+}
+
+/// Parse leading decimal number.
+fn parse_number(text: &str) -> Option<(u32, &str)> {
+    let end = text.find(|c: char| !c.is_digit(10)).unwrap_or(text.len());
+    if end == 0 {
+        return None;
+    }
+    text[..end].parse::<u32>().ok().map(|number| (number, &text[end..]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use shader::ShaderKind;
+    use preprocess::{ LineMap, MemorySource };
+
+    #[test]
+    fn link_log_uses_nvidia_section_stage() {
+        let log = LinkLog::parse("Vertex info\n-----------\n0(3) : error C1008: undefined variable \"foo\"\n\nFragment info\n-------------\n0(5) : warning C7050: \"bar\" might be used before being initialized\n");
+
+        assert_eq!(log.diagnostics.len(), 2);
+        assert_eq!(log.diagnostics[0].severity, Severity::Error);
+        assert_eq!(log.diagnostics[0].stage, Some(ShaderKind::Vertex));
+        assert_eq!(log.diagnostics[0].symbol, Some("foo".to_string()));
+        assert_eq!(log.diagnostics[0].message, "undefined variable \"foo\"");
+        assert_eq!(log.diagnostics[1].severity, Severity::Warning);
+        assert_eq!(log.diagnostics[1].stage, Some(ShaderKind::Fragment));
+        assert_eq!(log.diagnostics[1].symbol, Some("bar".to_string()));
+    }
+
+    #[test]
+    fn link_log_prefers_first_mentioned_stage() {
+        let log = LinkLog::parse("error: fragment shader input `v_color' has no matching vertex shader output\n");
+
+        assert_eq!(log.diagnostics.len(), 1);
+        assert_eq!(log.diagnostics[0].stage, Some(ShaderKind::Fragment));
+        assert_eq!(log.diagnostics[0].symbol, Some("v_color".to_string()));
+    }
+
+    #[test]
+    fn link_log_skips_apostrophes_inside_words() {
+        let log = LinkLog::parse("error: can't link, geometry shader uses undefined 'emit_vertex'\n");
+
+        assert_eq!(log.diagnostics[0].stage, Some(ShaderKind::Geometry));
+        assert_eq!(log.diagnostics[0].symbol, Some("emit_vertex".to_string()));
+
+        let log = LinkLog::parse("error: program doesn't have a main function\n");
+
+        assert_eq!(log.diagnostics[0].symbol, None);
+    }
+
+    #[test]
+    fn link_log_skips_amd_summary_and_joins_continuation_lines() {
+        let log = LinkLog::parse("Vertex shader(s) failed to link.\nERROR: Implicit version number 110 not supported\nby this driver\n");
+
+        assert_eq!(log.diagnostics.len(), 1);
+        assert_eq!(log.diagnostics[0].stage, None);
+        assert_eq!(log.diagnostics[0].message, "Implicit version number 110 not supported\nby this driver");
+        assert_eq!(log.errors().len(), 1);
+    }
+
+    #[test]
+    fn link_log_without_diagnostics_displays_raw_text() {
+        let log = LinkLog::parse("Link successful.\n");
+
+        assert_eq!(log.diagnostics.len(), 0);
+        assert_eq!(log.to_string(), "Link successful.\n");
+    }
+
+    #[test]
+    fn compile_log_parses_mesa_line_and_column() {
+        let log = CompileLog::parse("0:12(5): error: `x' undeclared\n0:14(1): warning: unused variable\n");
+
+        assert_eq!(log.diagnostics.len(), 2);
+        assert_eq!(log.diagnostics[0].severity, Severity::Error);
+        assert_eq!(log.diagnostics[0].source, 0);
+        assert_eq!(log.diagnostics[0].line, Some(12));
+        assert_eq!(log.diagnostics[0].column, Some(5));
+        assert_eq!(log.diagnostics[0].message, "`x' undeclared");
+        assert_eq!(log.diagnostics[1].severity, Severity::Warning);
+        assert_eq!(log.errors().len(), 1);
+    }
+
+    #[test]
+    fn compile_log_parses_nvidia_line_and_strips_code() {
+        let log = CompileLog::parse("1(7) : error C0000: syntax error, unexpected '}'\n");
+
+        assert_eq!(log.diagnostics.len(), 1);
+        assert_eq!(log.diagnostics[0].source, 1);
+        assert_eq!(log.diagnostics[0].line, Some(7));
+        assert_eq!(log.diagnostics[0].column, None);
+        assert_eq!(log.diagnostics[0].message, "syntax error, unexpected '}'");
+    }
+
+    #[test]
+    fn compile_log_parses_amd_line_and_skips_summary() {
+        let log = CompileLog::parse("ERROR: 0:3: 'foo' : undeclared identifier\nERROR: 1 compilation errors.  No code generated.\n");
+
+        assert_eq!(log.diagnostics.len(), 1);
+        assert_eq!(log.diagnostics[0].line, Some(3));
+        assert_eq!(log.diagnostics[0].message, "'foo' : undeclared identifier");
+    }
+
+    #[test]
+    fn compile_log_keeps_diagnostic_without_location() {
+        let log = CompileLog::parse("warning: extension not supported\n");
+
+        assert_eq!(log.diagnostics.len(), 1);
+        assert_eq!(log.diagnostics[0].severity, Severity::Warning);
+        assert_eq!(log.diagnostics[0].line, None);
+        assert_eq!(log.diagnostics[0].message, "extension not supported");
+    }
+
+    #[test]
+    fn map_lines_skips_inserted_lines() {
+        let mut line_map = LineMap::identity("main.glsl", "void main() {\n    bad;\n}\n");
+        line_map.insert_lines(0, 1);
+
+        let mut log = CompileLog::parse("0:3(5): error: bad\n0:9(1): error: past the end\n");
+        log.map_lines(&line_map);
+
+        assert_eq!(log.diagnostics[0].location, Some(("main.glsl".to_string(), 2)));
+        assert_eq!(log.diagnostics[1].location, None);
+    }
+
+    #[test]
+    fn render_points_at_column_of_compiled_line() {
+        let log = CompileLog::parse("0:2(5): error: bad thing\n");
+
+        assert_eq!(
+            log.render("void main() {\n    foo;\n}\n", None),
+            "error: bad thing\n --> <source 0>:2:5\n  |\n2 |     foo;\n  |     ^\n\n"
+        );
+    }
+
+    #[test]
+    fn render_loads_original_line_from_provider() {
+        let mut provider = MemorySource::new();
+        provider.insert("lib.glsl", "float a;\n  b bad;\n");
+
+        let mut log = CompileLog::parse("0:1: error: bad include\n");
+        log.diagnostics[0].location = Some(("lib.glsl".to_string(), 2));
+
+        assert_eq!(
+            log.render("compiled line\n", Some(&provider)),
+            "error: bad include\n --> lib.glsl:2\n  |\n2 |   b bad;\n  |   ^^^^^^\n\n"
+        );
+    }
+
+    #[test]
+    fn render_without_diagnostics_returns_raw_log() {
+        let log = CompileLog::parse("No errors.\n");
+
+        assert_eq!(log.render("", None), "No errors.\n");
+    }
+}
@@ -0,0 +1,291 @@
+use version::{ Api, GLVersion };
+
+/// Profile suffix of `#version` directive.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum GlslProfile {
+    Core,
+    Compatibility,
+    Es,
+}
+
+/// `#version` header written at the start of GLSL source.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct GlslHeader {
+    /// GLSL version number, such as `330` or `300`.
+    pub version: u32,
+    pub profile: Option<GlslProfile>,
+    /// Add `precision` defaults for float and int, needed by OpenGL ES fragment shaders.
+    pub precision: bool,
+}
+
+impl GlslHeader {
+
+    /// Select header matching context version.
+    ///
+    /// Desktop contexts from 3.2 get `core` profile, OpenGL ES 3.x contexts get
+    /// `es` profile and precision defaults.
+    pub fn for_context(version: &GLVersion) -> GlslHeader {
+        match version.api {
+            Api::Gl => {
+                let number = match (version.major, version.minor) {
+                    (2, 0) => 110,
+                    (2, _) => 120,
+                    (3, 0) => 130,
+                    (3, 1) => 140,
+                    (3, 2) => 150,
+                    (major, minor) => major * 100 + minor * 10,
+                };
+                GlslHeader {
+                    version: number,
+                    profile: if number >= 150 { Some(GlslProfile::Core) } else { None },
+                    precision: false,
+                }
+            },
+            Api::GlEs => {
+                let number = match version.major {
+                    2 => 100,
+                    major => major * 100 + version.minor * 10,
+                };
+                GlslHeader {
+                    version: number,
+                    profile: if number >= 300 { Some(GlslProfile::Es) } else { None },
+                    precision: true,
+                }
+            },
+        }
+    }
+
+    /// Select header matching the current context version.
+    pub fn current() -> Option<GlslHeader> {
+        GLVersion::current().map(|version| GlslHeader::for_context(&version))
+    }
+
+    /// Header lines, each terminated with a newline.
+    pub fn lines(&self) -> Vec<String> {
+        let mut lines = Vec::with_capacity(3);
+        lines.push(match self.profile {
+            Some(GlslProfile::Core) => format!("#version {} core\n", self.version),
+            Some(GlslProfile::Compatibility) => format!("#version {} compatibility\n", self.version),
+            Some(GlslProfile::Es) => format!("#version {} es\n", self.version),
+            None => format!("#version {}\n", self.version),
+        });
+        if self.precision {
+            lines.push("precision highp float;\n".to_string());
+            lines.push("precision highp int;\n".to_string());
+        }
+        lines
+    }
+
+    /// Rewrite existing `#version` directive of `source`, or prepend one if there is none.
+    ///
+    /// Precision defaults are placed after the last `#extension` directive,
+    /// since they are statements and extensions must be enabled before any.
+    /// Returns new source and the inserted lines as pairs of the original line
+    /// after which they were inserted and their count, for adjusting line maps.
+    pub fn apply(&self, source: &str) -> (String, Vec<(u32, u32)>) {
+        let lines = self.lines();
+        let prologue = Prologue::scan(source);
+
+        let mut result = String::with_capacity(source.len() + lines.concat().len());
+        let mut insertions = Vec::new();
+        let mut offset = 0;
+
+        match prologue.version {
+            Some(line) => {
+                debug!("rewrite {} as {}", source[line.start..line.end].trim(), lines[0].trim());
+                result.push_str(&source[..line.start]);
+                result.push_str(&lines[0]);
+                offset = line.end;
+            },
+            None => {
+                debug!("prepend {}", lines[0].trim());
+                result.push_str(&lines[0]);
+                insertions.push((0, 1));
+            },
+        }
+
+        if lines.len() > 1 {
+            let (after, end) = match prologue.last_extension.or(prologue.version) {
+                Some(line) => (line.index + 1, line.end),
+                None => (0, 0),
+            };
+            if end > offset {
+                result.push_str(&source[offset..end]);
+                if !result.ends_with("\n") {
+                    result.push('\n');
+                }
+                offset = end;
+            }
+            for line in lines[1..].iter() {
+                result.push_str(line);
+            }
+            insertions.push((after, (lines.len() - 1) as u32));
+        }
+
+        result.push_str(&source[offset..]);
+        (result, insertions)
+    }
+}
+
+/// Line of source, with zero-based index and byte range including its newline.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct SourceLine {
+    pub index: u32,
+    pub start: usize,
+    pub end: usize,
+}
+
+/// Directives at the start of GLSL source, before the first line of code.
+///
+/// Blank lines, line and block comments and `#line` directives are skipped.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Prologue {
+    pub version: Option<SourceLine>,
+    /// Last `#extension` directive.
+    pub last_extension: Option<SourceLine>,
+}
+
+impl Prologue {
+    pub fn scan(source: &str) -> Prologue {
+        let mut prologue = Prologue { version: None, last_extension: None };
+        let mut in_comment = false;
+        let mut start = 0;
+
+        for (index, line) in source.lines().enumerate() {
+            let end = start + line.len();
+            let end = if source[end..].starts_with("\r\n") {
+                end + 2
+            } else if source[end..].starts_with("\n") {
+                end + 1
+            } else {
+                end
+            };
+            let current = SourceLine { index: index as u32, start: start, end: end };
+            start = end;
+
+            let code = strip_comments(line, &mut in_comment);
+            let code = code.trim();
+            if code.len() == 0 || code.starts_with("#line") {
+                continue;
+            }
+            if code.starts_with("#version") && prologue.version.is_none() && prologue.last_extension.is_none() {
+                prologue.version = Some(current);
+            } else if code.starts_with("#extension") {
+                prologue.last_extension = Some(current);
+            } else {
+                break;
+            }
+        }
+
+        prologue
+    }
+}
+
+/// Remove comments from `line`, tracking block comments that continue past its end.
+fn strip_comments(line: &str, in_comment: &mut bool) -> String {
+    let mut code = String::new();
+    let mut rest = line;
+    loop {
+        if *in_comment {
+            match rest.find("*/") {
+                Some(end) => {
+                    *in_comment = false;
+                    rest = &rest[end + 2..];
+                },
+                None => return code,
+            }
+        } else {
+            match (rest.find("/*"), rest.find("//")) {
+                (Some(block), Some(comment)) if comment < block => {
+                    code.push_str(&rest[..comment]);
+                    return code;
+                },
+                (Some(block), _) => {
+                    code.push_str(&rest[..block]);
+                    code.push(' ');
+                    *in_comment = true;
+                    rest = &rest[block + 2..];
+                },
+                (None, Some(comment)) => {
+                    code.push_str(&rest[..comment]);
+                    return code;
+                },
+                (None, None) => {
+                    code.push_str(rest);
+                    return code;
+                },
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use version::{ Api, GLVersion };
+
+    #[test]
+    fn header_matches_context_version() {
+        let header = GlslHeader::for_context(&GLVersion::new(Api::Gl, 3, 3));
+        assert_eq!(header, GlslHeader { version: 330, profile: Some(GlslProfile::Core), precision: false });
+        let header = GlslHeader::for_context(&GLVersion::new(Api::Gl, 2, 1));
+        assert_eq!(header, GlslHeader { version: 120, profile: None, precision: false });
+        let header = GlslHeader::for_context(&GLVersion::new(Api::GlEs, 3, 0));
+        assert_eq!(header, GlslHeader { version: 300, profile: Some(GlslProfile::Es), precision: true });
+    }
+
+    #[test]
+    fn existing_version_is_rewritten_without_insertions() {
+        let header = GlslHeader { version: 330, profile: Some(GlslProfile::Core), precision: false };
+        let (source, insertions) = header.apply("// comment\n#version 150\nvoid main() {}\n");
+        assert_eq!(source, "// comment\n#version 330 core\nvoid main() {}\n");
+        assert_eq!(insertions, vec![]);
+    }
+
+    #[test]
+    fn missing_version_is_prepended_as_insertion() {
+        let header = GlslHeader { version: 330, profile: None, precision: false };
+        let (source, insertions) = header.apply("void main() {}\n");
+        assert_eq!(source, "#version 330\nvoid main() {}\n");
+        assert_eq!(insertions, vec![(0, 1)]);
+    }
+
+    #[test]
+    fn precision_is_inserted_after_last_extension() {
+        let header = GlslHeader { version: 300, profile: Some(GlslProfile::Es), precision: true };
+        let (source, insertions) = header.apply(
+            "#version 300 es\n#extension GL_A : enable\n#extension GL_B : enable\nvoid main() {}\n");
+        assert_eq!(source, "#version 300 es\n#extension GL_A : enable\n#extension GL_B : enable\n\
+                            precision highp float;\nprecision highp int;\nvoid main() {}\n");
+        assert_eq!(insertions, vec![(3, 2)]);
+    }
+
+    #[test]
+    fn precision_is_inserted_after_version() {
+        let header = GlslHeader { version: 300, profile: Some(GlslProfile::Es), precision: true };
+        let (source, insertions) = header.apply("#version 300 es\nvoid main() {}\n");
+        assert_eq!(source, "#version 300 es\nprecision highp float;\nprecision highp int;\nvoid main() {}\n");
+        assert_eq!(insertions, vec![(1, 2)]);
+    }
+
+    #[test]
+    fn precision_follows_prepended_version() {
+        let header = GlslHeader { version: 100, profile: None, precision: true };
+        let (source, insertions) = header.apply("void main() {}");
+        assert_eq!(source, "#version 100\nprecision highp float;\nprecision highp int;\nvoid main() {}");
+        assert_eq!(insertions, vec![(0, 1), (0, 2)]);
+    }
+
+    #[test]
+    fn prologue_skips_comments_and_line_directives() {
+        let prologue = Prologue::scan("/* a\n b */\n#line 1\n#version 330\n#extension GL_A : enable\nint x;\n#extension GL_B : enable\n");
+        assert_eq!(prologue.version.map(|line| line.index), Some(3));
+        assert_eq!(prologue.last_extension.map(|line| line.index), Some(4));
+    }
+
+    #[test]
+    fn prologue_lines_include_crlf() {
+        let prologue = Prologue::scan("#version 330\r\nvoid main() {}\r\n");
+        assert_eq!(prologue.version, Some(SourceLine { index: 0, start: 0, end: 14 }));
+    }
+}
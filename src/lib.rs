@@ -1,17 +1,31 @@
 extern crate gl;
+#[cfg(feature = "image")] extern crate image;
 #[macro_use] extern crate log;
 
 pub use role::{ Generator };
 
 mod role;
 pub mod gli;
+pub mod version;
 
 pub mod shader;
+pub mod preprocess;
+pub mod permutation;
+pub mod spirv;
+pub mod glsl;
+pub mod parallel;
+pub mod diagnostic;
 pub mod context;
 
 pub mod program;
+pub mod uniform;
+pub mod reload;
 pub mod buffer;
 pub mod vertex_array;
+pub mod transform_feedback;
+pub mod compute;
+pub mod texture;
+pub mod sampler;
 
 pub mod state_program;
 pub mod state_buffer;
@@ -0,0 +1,84 @@
+//! Non-blocking shader compilation with `GL_KHR_parallel_shader_compile`.
+//!
+//! `glMaxShaderCompilerThreadsKHR` is not part of the generated GL bindings,
+//! so it has to be loaded with `load_with` using the same loader passed to
+//! `gl::load_with`.
+
+use gl::types::*;
+
+use std::mem;
+use std::os::raw::c_void;
+use std::sync::atomic::{ AtomicUsize, Ordering, ATOMIC_USIZE_INIT };
+
+use version;
+
+pub const MAX_SHADER_COMPILER_THREADS_KHR: GLenum = 0x91B0;
+pub const COMPLETION_STATUS_KHR: GLenum = 0x91B1;
+
+type MaxShaderCompilerThreadsFn = extern "system" fn(GLuint);
+
+static MAX_SHADER_COMPILER_THREADS: AtomicUsize = ATOMIC_USIZE_INIT;
+
+const SUPPORT_UNKNOWN: usize = 0;
+const SUPPORT_MISSING: usize = 1;
+const SUPPORT_PRESENT: usize = 2;
+
+static SUPPORT: AtomicUsize = ATOMIC_USIZE_INIT;
+
+/// Load `glMaxShaderCompilerThreadsKHR` (or `glMaxShaderCompilerThreadsARB`) entry point.
+pub fn load_with<F>(mut loadfn: F) where F: FnMut(&str) -> *const c_void {
+    let mut ptr = loadfn("glMaxShaderCompilerThreadsKHR");
+    if ptr.is_null() {
+        ptr = loadfn("glMaxShaderCompilerThreadsARB");
+    }
+    debug!("load glMaxShaderCompilerThreadsKHR, found = {}", !ptr.is_null());
+    MAX_SHADER_COMPILER_THREADS.store(ptr as usize, Ordering::SeqCst);
+}
+
+/// Returns true if the context supports parallel shader compile.
+///
+/// Extension list is queried once and the answer is cached.
+pub fn is_supported() -> bool {
+    match SUPPORT.load(Ordering::SeqCst) {
+        SUPPORT_PRESENT => true,
+        SUPPORT_MISSING => false,
+        _ => {
+            let supported = version::has_extension("GL_KHR_parallel_shader_compile")
+                || version::has_extension("GL_ARB_parallel_shader_compile");
+            info!("parallel shader compile supported = {}", supported);
+            SUPPORT.store(if supported { SUPPORT_PRESENT } else { SUPPORT_MISSING }, Ordering::SeqCst);
+            supported
+        },
+    }
+}
+
+/// Forget cached extension support, for example after switching context.
+pub fn reset_support() {
+    SUPPORT.store(SUPPORT_UNKNOWN, Ordering::SeqCst);
+}
+
+/// Set the number of threads the driver may use for shader compilation.
+///
+/// Returns false if the extension or its entry point is missing.
+///
+/// ## glMaxShaderCompilerThreadsKHR
+///
+/// - GL_KHR_parallel_shader_compile
+///
+pub fn set_max_compiler_threads(count: GLuint) -> bool {
+    let ptr = MAX_SHADER_COMPILER_THREADS.load(Ordering::SeqCst);
+
+    if ptr == 0 || !is_supported() {
+        warn!("can not set max shader compiler threads, parallel shader compile is not available");
+        return false;
+    }
+
+    debug!("set max shader compiler threads, {}", count);
+
+    unsafe {
+        let max_shader_compiler_threads: MaxShaderCompilerThreadsFn = mem::transmute(ptr);
+        max_shader_compiler_threads(count);
+    }
+
+    true
+}
@@ -0,0 +1,273 @@
+use std::collections::{ BTreeMap, HashMap };
+use std::rc::Rc;
+
+use shader::{ Shader, ShaderKind, ShaderError };
+use program::{ Program, ProgramError };
+use preprocess::{ Preprocessed, LineMap };
+use glsl::Prologue;
+
+/// Set of `#define`s that selects a shader permutation.
+///
+/// Defines are kept sorted, so sets built in different order are equal and
+/// hash the same. Used as the key for compiled shaders and linked programs.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Defines {
+    defines: BTreeMap<String, String>,
+}
+
+impl Defines {
+    pub fn new() -> Defines {
+        Defines { defines: BTreeMap::new() }
+    }
+
+    /// Define flag without value.
+    pub fn define(self, name: &str) -> Defines {
+        self.define_value(name, "")
+    }
+
+    /// Define macro with value.
+    ///
+    /// Panics if name or value spans multiple lines.
+    pub fn define_value(mut self, name: &str, value: &str) -> Defines {
+        if name.contains(|c: char| c == '\n' || c == '\r') || value.contains(|c: char| c == '\n' || c == '\r') {
+            error!("invalid define {:?} = {:?}", name, value);
+            panic!("invalid define string");
+        }
+        self.defines.insert(name.to_string(), value.to_string());
+        self
+    }
+
+    pub fn is_defined(&self, name: &str) -> bool {
+        self.defines.contains_key(name)
+    }
+
+    pub fn len(&self) -> usize {
+        self.defines.len()
+    }
+
+    /// Insert defines after `#version` line, or at the start if there is none.
+    ///
+    /// Comments and blank lines before `#version` are skipped, see `Prologue`.
+    ///
+    /// Returns new source and the line after which defines were inserted.
+    pub fn inject(&self, source: &str) -> (String, u32) {
+        let mut directives = String::new();
+        for (name, value) in self.defines.iter() {
+            if value.len() == 0 {
+                directives.push_str(&format!("#define {}\n", name));
+            } else {
+                directives.push_str(&format!("#define {} {}\n", name, value));
+            }
+        }
+
+        if let Some(line) = Prologue::scan(source).version {
+            let mut result = String::with_capacity(source.len() + directives.len() + 1);
+            result.push_str(&source[..line.end]);
+            if !result.ends_with("\n") {
+                result.push('\n');
+            }
+            result.push_str(&directives);
+            result.push_str(&source[line.end..]);
+            return (result, line.index + 1);
+        }
+
+        (format!("{}{}", directives, source), 0)
+    }
+}
+
+/// Compiles permutations of a single shader source and caches them by define set.
+pub struct ShaderVariants {
+    kind: ShaderKind,
+    source: String,
+    line_map: LineMap,
+    cache: HashMap<Defines, Rc<Shader>>,
+}
+
+impl ShaderVariants {
+    /// Create from plain source, whose lines are reported as lines of `<source>`.
+    pub fn new(kind: ShaderKind, source: &str) -> ShaderVariants {
+        ShaderVariants {
+            kind: kind,
+            source: source.to_string(),
+            line_map: LineMap::identity("<source>", source),
+            cache: HashMap::new(),
+        }
+    }
+
+    /// Create from preprocessed source, keeping its line map.
+    pub fn from_preprocessed(kind: ShaderKind, preprocessed: Preprocessed) -> ShaderVariants {
+        ShaderVariants {
+            kind: kind,
+            source: preprocessed.source,
+            line_map: preprocessed.line_map,
+            cache: HashMap::new(),
+        }
+    }
+
+    pub fn get_kind(&self) -> ShaderKind {
+        self.kind
+    }
+
+    /// Get source and line map of the permutation selected by `defines`.
+    pub fn variant_source(&self, defines: &Defines) -> (String, LineMap) {
+        let (source, line) = defines.inject(&self.source);
+        let mut line_map = self.line_map.clone();
+        line_map.insert_lines(line, defines.len() as u32);
+        (source, line_map)
+    }
+
+    /// Get compiled permutation, compiling it on first request.
+    pub fn get(&mut self, defines: &Defines) -> Result<Rc<Shader>, ShaderError> {
+        if let Some(shader) = self.cache.get(defines) {
+            trace!("[{}]: cached variant {:?}", shader.get_id(), defines);
+            return Ok(shader.clone());
+        }
+
+        debug!("compile {:?} variant {:?}", self.kind, defines);
+
+        let (source, line_map) = self.variant_source(defines);
+        let shader = match Shader::compile_new(&source, self.kind) {
+            Ok(shader) => Rc::new(shader),
+            Err(ShaderError::CompileFailed(mut log)) => {
+                log.map_lines(&line_map);
+                return Err(ShaderError::CompileFailed(log));
+            },
+            Err(err) => return Err(err),
+        };
+
+        self.cache.insert(defines.clone(), shader.clone());
+
+        Ok(shader)
+    }
+
+    /// Drop all compiled permutations.
+    pub fn clear(&mut self) {
+        self.cache.clear();
+    }
+}
+
+/// Links permutations of a program from shader variants and caches them by define set.
+pub struct ProgramVariants {
+    stages: Vec<ShaderVariants>,
+    programs: HashMap<Defines, Program>,
+}
+
+impl ProgramVariants {
+    pub fn new(stages: Vec<ShaderVariants>) -> ProgramVariants {
+        ProgramVariants {
+            stages: stages,
+            programs: HashMap::new(),
+        }
+    }
+
+    /// Get already linked permutation.
+    pub fn get(&self, defines: &Defines) -> Option<&Program> {
+        self.programs.get(defines)
+    }
+
+    /// Get linked permutation, compiling and linking it on first request.
+    pub fn get_or_link(&mut self, defines: &Defines) -> Result<&mut Program, ProgramError> {
+        if !self.programs.contains_key(defines) {
+            debug!("link variant {:?}", defines);
+
+            let mut shaders = Vec::with_capacity(self.stages.len());
+            for stage in self.stages.iter_mut() {
+                match stage.get(defines) {
+                    Ok(shader) => shaders.push(shader),
+                    Err(err) => return Err(ProgramError::Shader(err)),
+                }
+            }
+
+            let program = match Program::link_new(&shaders) {
+                Ok(program) => program,
+                Err(err) => return Err(err),
+            };
+
+            self.programs.insert(defines.clone(), program);
+        }
+
+        Ok(self.programs.get_mut(defines).unwrap())
+    }
+
+    /// Drop all linked programs and compiled shaders.
+    pub fn clear(&mut self) {
+        self.programs.clear();
+        for stage in self.stages.iter_mut() {
+            stage.clear();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use diagnostic::CompileLog;
+    use preprocess::{ MemorySource, Preprocessor };
+
+    #[test]
+    fn defines_are_injected_after_version_in_sorted_order() {
+        let defines = Defines::new().define_value("B", "2").define("A");
+        let (source, after) = defines.inject("#version 330\nvoid main() {}\n");
+        assert_eq!(source, "#version 330\n#define A\n#define B 2\nvoid main() {}\n");
+        assert_eq!(after, 1);
+    }
+
+    #[test]
+    fn defines_are_prepended_without_version() {
+        let (source, after) = Defines::new().define("A").inject("void main() {}\n");
+        assert_eq!(source, "#define A\nvoid main() {}\n");
+        assert_eq!(after, 0);
+    }
+
+    #[test]
+    fn comments_before_version_are_skipped() {
+        let (source, after) = Defines::new().define("A").inject("// header\n/* a\n b */\n#version 330\nvoid main() {}\n");
+        assert_eq!(source, "// header\n/* a\n b */\n#version 330\n#define A\nvoid main() {}\n");
+        assert_eq!(after, 4);
+    }
+
+    #[test]
+    fn version_without_newline_is_terminated() {
+        let (source, after) = Defines::new().define("A").inject("#version 330");
+        assert_eq!(source, "#version 330\n#define A\n");
+        assert_eq!(after, 1);
+    }
+
+    #[test]
+    fn define_sets_are_equal_regardless_of_order() {
+        assert_eq!(Defines::new().define("A").define("B"), Defines::new().define("B").define("A"));
+    }
+
+    #[test]
+    fn plain_variant_maps_compile_errors_around_defines() {
+        let variants = ShaderVariants::new(ShaderKind::Fragment, "#version 330\nvoid main() {\n    bad;\n}\n");
+        let (source, line_map) = variants.variant_source(&Defines::new().define("A").define("B"));
+        assert_eq!(source.lines().nth(4), Some("    bad;"));
+
+        let mut log = CompileLog::parse("0:5(5): error: bad\n0:2(1): error: in define\n");
+        log.map_lines(&line_map);
+        assert_eq!(log.diagnostics[0].location, Some(("<source>".to_string(), 3)));
+        assert_eq!(log.diagnostics[1].location, Some(("<source>".to_string(), 1)));
+    }
+
+    #[test]
+    fn preprocessed_variant_keeps_include_files() {
+        let mut provider = MemorySource::new();
+        provider
+            .insert("main.glsl", "#version 330\n#include \"lib.glsl\"\nvoid main() {}\n")
+            .insert("lib.glsl", "float x;\nbad;\n");
+        let preprocessed = Preprocessor::new(&provider).process("main.glsl").unwrap();
+        let variants = ShaderVariants::from_preprocessed(ShaderKind::Vertex, preprocessed);
+        let (_, line_map) = variants.variant_source(&Defines::new().define("A"));
+
+        assert_eq!(line_map.map(2), Some(("main.glsl", 1)));
+        assert_eq!(line_map.map(4), Some(("lib.glsl", 2)));
+        assert_eq!(line_map.map(5), Some(("main.glsl", 3)));
+    }
+
+    #[test]
+    #[should_panic]
+    fn multiline_value_panics() {
+        Defines::new().define_value("A", "1\n2");
+    }
+}
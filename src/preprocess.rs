@@ -0,0 +1,448 @@
+use std::collections::{ HashMap, HashSet };
+use std::fmt;
+use std::fs::File;
+use std::io::Read;
+use std::path::PathBuf;
+
+/// Source of GLSL files for `#include` resolution.
+pub trait SourceProvider {
+    /// Load file contents by normalized path, such as `lib/lighting.glsl`.
+    fn load(&self, path: &str) -> Option<String>;
+}
+
+/// Loads sources from a directory on disk.
+pub struct DirectorySource {
+    root: PathBuf,
+}
+
+impl DirectorySource {
+    pub fn new<P: Into<PathBuf>>(root: P) -> DirectorySource {
+        DirectorySource { root: root.into() }
+    }
+}
+
+impl SourceProvider for DirectorySource {
+    fn load(&self, path: &str) -> Option<String> {
+        let full_path = self.root.join(path);
+        trace!("load {}", full_path.display());
+
+        let mut contents = String::new();
+        match File::open(&full_path).and_then(|mut file| file.read_to_string(&mut contents)) {
+            Ok(_) => Some(contents),
+            Err(err) => {
+                debug!("failed to load {}, {}", full_path.display(), err);
+                None
+            },
+        }
+    }
+}
+
+/// Loads sources from an in-memory map.
+pub struct MemorySource {
+    files: HashMap<String, String>,
+}
+
+impl MemorySource {
+    pub fn new() -> MemorySource {
+        MemorySource { files: HashMap::new() }
+    }
+
+    pub fn insert(&mut self, path: &str, source: &str) -> &mut MemorySource {
+        self.files.insert(normalize_path(path), source.to_string());
+        self
+    }
+}
+
+impl SourceProvider for MemorySource {
+    fn load(&self, path: &str) -> Option<String> {
+        self.files.get(path).cloned()
+    }
+}
+
+/// Loads sources from a static bundle, usually built with `include_str!`.
+pub struct EmbeddedSource {
+    files: &'static [(&'static str, &'static str)],
+}
+
+impl EmbeddedSource {
+    pub fn new(files: &'static [(&'static str, &'static str)]) -> EmbeddedSource {
+        EmbeddedSource { files: files }
+    }
+}
+
+impl SourceProvider for EmbeddedSource {
+    fn load(&self, path: &str) -> Option<String> {
+        self.files.iter()
+            .find(|&&(name, _)| normalize_path(name) == path)
+            .map(|&(_, source)| source.to_string())
+    }
+}
+
+/// Position in an original source file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SourceLocation {
+    /// Index into `LineMap::files`.
+    pub file: usize,
+    /// One-based line number.
+    pub line: u32,
+}
+
+/// Maps lines of preprocessed output back to original files.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LineMap {
+    pub files: Vec<String>,
+    lines: Vec<SourceLocation>,
+}
+
+impl LineMap {
+    pub fn new() -> LineMap {
+        LineMap { files: Vec::new(), lines: Vec::new() }
+    }
+
+    /// Map each line of `source` to itself, as line of file `path`.
+    pub fn identity(path: &str, source: &str) -> LineMap {
+        let mut line_map = LineMap::new();
+        let file = line_map.file_index(path);
+        line_map.lines = (0..source.lines().count())
+            .map(|index| SourceLocation { file: file, line: (index + 1) as u32 })
+            .collect();
+        line_map
+    }
+
+    fn file_index(&mut self, path: &str) -> usize {
+        match self.files.iter().position(|file| file == path) {
+            Some(index) => index,
+            None => {
+                self.files.push(path.to_string());
+                self.files.len() - 1
+            },
+        }
+    }
+
+    /// Number of mapped output lines.
+    pub fn len(&self) -> usize {
+        self.lines.len()
+    }
+
+    /// Get original file and line of one-based output `line`.
+    pub fn map(&self, line: u32) -> Option<(&str, u32)> {
+        if line == 0 {
+            return None;
+        }
+        self.lines.get((line - 1) as usize)
+            .map(|location| (&self.files[location.file][..], location.line))
+    }
+
+    /// Insert `count` lines that do not originate from any file, after output `line`.
+    ///
+    /// Inserted lines map to the location of the line they follow.
+    pub fn insert_lines(&mut self, line: u32, count: u32) {
+        let location = match line {
+            0 => self.lines.first().cloned(),
+            line => self.lines.get((line - 1) as usize).cloned(),
+        };
+        if let Some(location) = location {
+            for _ in 0..count {
+                self.lines.insert(line as usize, location.clone());
+            }
+        }
+    }
+}
+
+/// Preprocessed source with includes resolved.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Preprocessed {
+    pub source: String,
+    pub line_map: LineMap,
+}
+
+/// Resolves `#include "..."` directives against a source provider.
+///
+/// Included paths are relative to the including file. Files marked with
+/// `#pragma once` or wrapped in an include guard are only included once.
+pub struct Preprocessor<'a> {
+    provider: &'a dyn SourceProvider,
+}
+
+struct State {
+    output: String,
+    line_map: LineMap,
+    stack: Vec<String>,
+    once: HashSet<String>,
+}
+
+impl<'a> Preprocessor<'a> {
+    pub fn new(provider: &'a dyn SourceProvider) -> Preprocessor<'a> {
+        Preprocessor { provider: provider }
+    }
+
+    /// Load file at `path` and resolve its includes.
+    pub fn process(&self, path: &str) -> Result<Preprocessed, PreprocessError> {
+        debug!("preprocess {}", path);
+
+        let path = normalize_path(path);
+        let source = match self.provider.load(&path) {
+            Some(source) => source,
+            None => return Err(PreprocessError::NotFound { path: path, included_from: None }),
+        };
+
+        self.process_source(&path, &source)
+    }
+
+    /// Resolve includes of `source` which is known as `path`.
+    pub fn process_source(&self, path: &str, source: &str) -> Result<Preprocessed, PreprocessError> {
+        let mut state = State {
+            output: String::with_capacity(source.len()),
+            line_map: LineMap::new(),
+            stack: Vec::new(),
+            once: HashSet::new(),
+        };
+
+        if let Err(err) = self.process_file(&mut state, &normalize_path(path), source) {
+            error!("preprocess error, {}", err);
+            return Err(err);
+        }
+
+        Ok(Preprocessed {
+            source: state.output,
+            line_map: state.line_map,
+        })
+    }
+
+    fn process_file(&self, state: &mut State, path: &str, source: &str) -> Result<(), PreprocessError> {
+        if state.stack.iter().any(|included| included == path) {
+            let mut chain = state.stack.clone();
+            chain.push(path.to_string());
+            return Err(PreprocessError::IncludeCycle(chain));
+        }
+
+        if state.once.contains(path) {
+            trace!("skip {}, already included", path);
+            return Ok(());
+        }
+
+        if is_include_guarded(source) {
+            state.once.insert(path.to_string());
+        }
+
+        state.stack.push(path.to_string());
+        let file = state.line_map.file_index(path);
+
+        for (index, line) in source.lines().enumerate() {
+            let line_number = (index + 1) as u32;
+            let trimmed = line.trim();
+
+            if trimmed.starts_with("#") {
+                let directive = trimmed[1..].trim_left();
+
+                if directive.starts_with("pragma") && directive["pragma".len()..].trim() == "once" {
+                    state.once.insert(path.to_string());
+                    continue;
+                }
+
+                if directive.starts_with("include") {
+                    let target = match parse_include(&directive["include".len()..]) {
+                        Some(target) => target,
+                        None => return Err(PreprocessError::Malformed {
+                            path: path.to_string(),
+                            line: line_number,
+                            text: line.to_string(),
+                        }),
+                    };
+
+                    let target = resolve_path(path, target);
+                    trace!("{}:{} include {}", path, line_number, target);
+
+                    let included = match self.provider.load(&target) {
+                        Some(included) => included,
+                        None => return Err(PreprocessError::NotFound {
+                            path: target,
+                            included_from: Some((path.to_string(), line_number)),
+                        }),
+                    };
+
+                    if let Err(err) = self.process_file(state, &target, &included) {
+                        return Err(err);
+                    }
+                    continue;
+                }
+            }
+
+            state.output.push_str(line);
+            state.output.push('\n');
+            state.line_map.lines.push(SourceLocation { file: file, line: line_number });
+        }
+
+        state.stack.pop();
+
+        Ok(())
+    }
+}
+
+/// Normalize path by removing `.` and resolving `..` components.
+pub fn normalize_path(path: &str) -> String {
+    let mut parts: Vec<&str> = Vec::new();
+    for part in path.split('/') {
+        match part {
+            "" | "." => (),
+            ".." => { parts.pop(); },
+            part => parts.push(part),
+        }
+    }
+    parts.connect("/")
+}
+
+/// Resolve `target` relative to directory of `from`.
+fn resolve_path(from: &str, target: &str) -> String {
+    if target.starts_with("/") {
+        return normalize_path(target);
+    }
+    match from.rfind('/') {
+        Some(pos) => normalize_path(&format!("{}/{}", &from[..pos], target)),
+        None => normalize_path(target),
+    }
+}
+
+/// Parse `"path"` or `<path>` argument of include directive.
+fn parse_include(argument: &str) -> Option<&str> {
+    let argument = argument.trim();
+    let close = match argument.chars().next() {
+        Some('"') => '"',
+        Some('<') => '>',
+        _ => return None,
+    };
+    let rest = &argument[1..];
+    match rest.find(close) {
+        Some(end) if end > 0 && rest[end + 1..].trim().len() == 0 => Some(&rest[..end]),
+        _ => None,
+    }
+}
+
+/// Returns true if file is wrapped in `#ifndef X` / `#define X` ... `#endif`.
+fn is_include_guarded(source: &str) -> bool {
+    let mut directives = source.lines()
+        .map(|line| line.trim())
+        .filter(|line| line.len() > 0 && !line.starts_with("//"));
+
+    let guard = match directives.next() {
+        Some(line) if line.starts_with("#ifndef") => line["#ifndef".len()..].trim(),
+        _ => return false,
+    };
+
+    let defined = match directives.next() {
+        Some(line) if line.starts_with("#define") => line["#define".len()..].trim(),
+        _ => return false,
+    };
+
+    defined.split(' ').next() == Some(guard)
+        && source.lines().rev().map(|line| line.trim()).find(|line| line.len() > 0)
+            .map(|line| line.starts_with("#endif"))
+            .unwrap_or(false)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum PreprocessError {
+    NotFound { path: String, included_from: Option<(String, u32)> },
+    IncludeCycle(Vec<String>),
+    Malformed { path: String, line: u32, text: String },
+}
+
+impl fmt::Display for PreprocessError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            &PreprocessError::NotFound { ref path, included_from: None } => write!(f, "Shader source {} was not found.", path),
+            &PreprocessError::NotFound { ref path, included_from: Some((ref from, line)) } => write!(f, "Shader source {} included from {}:{} was not found.", path, from, line),
+            &PreprocessError::IncludeCycle(ref chain) => write!(f, "Shader sources include each other in a cycle: {}.", chain.connect(" -> ")),
+            &PreprocessError::Malformed { ref path, line, ref text } => write!(f, "Malformed include directive at {}:{}: {}", path, line, text),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn includes_are_resolved_relative_to_including_file() {
+        let mut provider = MemorySource::new();
+        provider
+            .insert("main.glsl", "#version 330\n#include \"lib/light.glsl\"\nvoid main() {}\n")
+            .insert("lib/light.glsl", "#include \"../common.glsl\"\nfloat light;\n")
+            .insert("common.glsl", "float common;\n");
+
+        let preprocessed = Preprocessor::new(&provider).process("main.glsl").unwrap();
+
+        assert_eq!(preprocessed.source, "#version 330\nfloat common;\nfloat light;\nvoid main() {}\n");
+        assert_eq!(preprocessed.line_map.len(), 4);
+        assert_eq!(preprocessed.line_map.map(1), Some(("main.glsl", 1)));
+        assert_eq!(preprocessed.line_map.map(2), Some(("common.glsl", 1)));
+        assert_eq!(preprocessed.line_map.map(3), Some(("lib/light.glsl", 2)));
+        assert_eq!(preprocessed.line_map.map(4), Some(("main.glsl", 3)));
+        assert_eq!(preprocessed.line_map.map(0), None);
+        assert_eq!(preprocessed.line_map.map(5), None);
+    }
+
+    #[test]
+    fn include_cycle_is_reported_with_chain() {
+        let mut provider = MemorySource::new();
+        provider
+            .insert("a.glsl", "#include \"b.glsl\"\n")
+            .insert("b.glsl", "#include \"a.glsl\"\n");
+
+        assert_eq!(
+            Preprocessor::new(&provider).process("a.glsl"),
+            Err(PreprocessError::IncludeCycle(vec!["a.glsl".to_string(), "b.glsl".to_string(), "a.glsl".to_string()]))
+        );
+    }
+
+    #[test]
+    fn pragma_once_and_include_guard_include_file_once() {
+        let mut provider = MemorySource::new();
+        provider
+            .insert("main.glsl", "#include \"once.glsl\"\n#include \"guarded.glsl\"\n#include \"once.glsl\"\n#include \"guarded.glsl\"\n")
+            .insert("once.glsl", "#pragma once\nfloat once;\n")
+            .insert("guarded.glsl", "#ifndef GUARDED\n#define GUARDED\nfloat guarded;\n#endif\n");
+
+        let preprocessed = Preprocessor::new(&provider).process("main.glsl").unwrap();
+
+        assert_eq!(preprocessed.source, "float once;\n#ifndef GUARDED\n#define GUARDED\nfloat guarded;\n#endif\n");
+        assert_eq!(preprocessed.line_map.map(1), Some(("once.glsl", 2)));
+    }
+
+    #[test]
+    fn missing_and_malformed_includes_are_reported() {
+        let mut provider = MemorySource::new();
+        provider
+            .insert("missing.glsl", "\n#include \"nope.glsl\"\n")
+            .insert("malformed.glsl", "#include nope.glsl\n");
+
+        assert_eq!(
+            Preprocessor::new(&provider).process("missing.glsl"),
+            Err(PreprocessError::NotFound { path: "nope.glsl".to_string(), included_from: Some(("missing.glsl".to_string(), 2)) })
+        );
+        assert_eq!(
+            Preprocessor::new(&provider).process("malformed.glsl"),
+            Err(PreprocessError::Malformed { path: "malformed.glsl".to_string(), line: 1, text: "#include nope.glsl".to_string() })
+        );
+    }
+
+    #[test]
+    fn line_map_identity_and_inserted_lines() {
+        let mut line_map = LineMap::identity("shader.glsl", "a\nb\nc\n");
+        line_map.insert_lines(1, 2);
+
+        assert_eq!(line_map.len(), 5);
+        assert_eq!(line_map.map(1), Some(("shader.glsl", 1)));
+        assert_eq!(line_map.map(2), Some(("shader.glsl", 1)));
+        assert_eq!(line_map.map(3), Some(("shader.glsl", 1)));
+        assert_eq!(line_map.map(4), Some(("shader.glsl", 2)));
+        assert_eq!(line_map.map(5), Some(("shader.glsl", 3)));
+    }
+
+    #[test]
+    fn paths_are_normalized() {
+        assert_eq!(normalize_path("./lib/../shaders//main.glsl"), "shaders/main.glsl");
+        assert_eq!(resolve_path("lib/light.glsl", "../common.glsl"), "common.glsl");
+        assert_eq!(resolve_path("lib/light.glsl", "/root.glsl"), "root.glsl");
+    }
+}
@@ -2,16 +2,26 @@ use gl;
 use gl::types::*;
 
 use std::rc::Rc;
+use std::collections::HashMap;
 use std::fmt;
 use std::ops::Deref;
 use std::ptr;
 use std::ffi::CString;
+use std::error::Error;
 
-use shader::Shader;
+use shader::{ Shader, ShaderKind, ShaderError, PendingShader };
+use transform_feedback::BufferMode;
+use diagnostic::LinkLog;
+use uniform::UniformValue;
+use parallel;
 
 pub struct Program {
     id: GLuint,
     shaders: Vec<Rc<Shader>>,
+    /// Selected subroutine indices of each stage, indexed by uniform location.
+    subroutines: HashMap<ShaderKind, Vec<GLuint>>,
+    /// Locations of sampler uniforms, queried after successful link.
+    sampler_locations: Vec<GLint>,
 }
 
 impl Eq for Program {}
@@ -28,6 +38,8 @@ impl Program {
         Program {
             id: unsafe { gl::CreateProgram() },
             shaders: Vec::with_capacity(2),
+            subroutines: HashMap::new(),
+            sampler_locations: Vec::new(),
         }
     }
 
@@ -48,7 +60,7 @@ impl Program {
 
         for shader in shaders {
             if let Err(obj) = program.attach_shader(shader.clone()) {
-                return Err(ProgramError::LinkFailed(obj.to_string()));
+                return Err(ProgramError::LinkFailed(LinkLog::parse(&obj.to_string())));
             }
         }
 
@@ -61,10 +73,164 @@ impl Program {
         Ok(program)
     }
 
+    /// Issue linking without waiting for it to finish.
+    ///
+    /// Shaders may still be compiling when passed here, so compilation and
+    /// linking overlap. Compile status is checked when the program is resolved.
+    /// Falls back to synchronous linking when `GL_KHR_parallel_shader_compile`
+    /// is missing, in which case returned handle is already complete.
+    pub fn link_async(shaders: Vec<PendingShader>) -> Result<PendingProgram, ProgramError> {
+        debug!("link async");
+
+        let program = Program::internal_new();
+
+        for shader in &shaders {
+            match shader.get_id() {
+                Ok(id) => unsafe { gl::AttachShader(program.id, id) },
+                Err(err) => return Err(ProgramError::Shader(err)),
+            }
+        }
+
+        if !parallel::is_supported() {
+            let result = finish_async_link(program, shaders, true);
+            return Ok(PendingProgram { state: PendingState::Done(result) });
+        }
+
+        unsafe { gl::LinkProgram(program.id) };
+
+        Ok(PendingProgram { state: PendingState::Linking(program, shaders) })
+    }
+
+    /// Link program from a single compute shader.
+    ///
+    /// - OpenGL Version 4.3
+    /// - OpenGL ES Version 3.1
+    ///
+    pub fn link_compute_new(shader: Rc<Shader>) -> Result<Program, ProgramError> {
+        debug!("link compute new, {}", shader.get_id());
+
+        if shader.get_kind() != ShaderKind::Compute {
+            error!("[{}]: not a compute shader, kind = {:?}", shader.get_id(), shader.get_kind());
+            return Err(ProgramError::Other(format!("Shader {} is not a compute shader.", shader.get_id())));
+        }
+
+        Program::link_new(&[shader])
+    }
+
+    /// Start building a program that is linked once all pre-link bindings are set.
+    pub fn build() -> ProgramBuilder {
+        ProgramBuilder::new()
+    }
+
     pub fn get_id(&self) -> GLuint {
         self.id
     }
 
+    /// Associate a generic vertex attribute index with a named attribute variable.
+    ///
+    /// Takes effect on the next link.
+    ///
+    /// ## glBindAttribLocation
+    ///
+    /// - OpenGL Version 2.0
+    /// - OpenGL ES Version 2.0
+    ///
+    pub fn bind_attrib_location(&mut self, index: GLuint, name: &str) {
+        debug!(
+            "[{}]: bind attrib location, index = {}, name = {}",
+            self.id,
+            index,
+            name
+        );
+        if let Ok(cname) = CString::new(name) {
+            unsafe {
+                gl::BindAttribLocation(self.id, index, cname.as_ptr());
+            }
+        } else {
+            error!("[{}]: invalid name string", self.id);
+            panic!("invalid name string");
+        }
+    }
+
+    /// Bind a user-defined varying out variable to a fragment shader color number and index.
+    ///
+    /// Takes effect on the next link.
+    ///
+    /// ## glBindFragDataLocationIndexed
+    ///
+    /// - OpenGL Version 3.3
+    ///
+    pub fn bind_frag_data_location_indexed(&mut self, color_number: GLuint, index: GLuint, name: &str) {
+        debug!(
+            "[{}]: bind frag data location indexed, color_number = {}, index = {}, name = {}",
+            self.id,
+            color_number,
+            index,
+            name
+        );
+        if let Ok(cname) = CString::new(name) {
+            unsafe {
+                gl::BindFragDataLocationIndexed(self.id, color_number, index, cname.as_ptr());
+            }
+        } else {
+            error!("[{}]: invalid name string", self.id);
+            panic!("invalid name string");
+        }
+    }
+
+    /// Specify values to record in transform feedback buffers.
+    ///
+    /// Takes effect on the next link.
+    ///
+    /// ## glTransformFeedbackVaryings
+    ///
+    /// - OpenGL Version 3.0
+    /// - OpenGL ES Version 3.0
+    ///
+    pub fn set_transform_feedback_varyings(&mut self, varyings: &[&str], buffer_mode: BufferMode) {
+        debug!(
+            "[{}]: set transform feedback varyings, {}, buffer_mode = {:?}",
+            self.id,
+            varyings.connect(", "),
+            buffer_mode
+        );
+
+        let cnames: Vec<CString> = varyings.iter()
+            .map(|name| match CString::new(*name) {
+                Ok(cname) => cname,
+                Err(_) => {
+                    error!("[{}]: invalid name string", self.id);
+                    panic!("invalid name string");
+                }
+            })
+            .collect();
+        let pointers: Vec<*const GLchar> = cnames.iter()
+            .map(|cname| cname.as_ptr())
+            .collect();
+
+        unsafe {
+            gl::TransformFeedbackVaryings(
+                self.id,
+                pointers.len() as GLsizei,
+                pointers.as_ptr(),
+                buffer_mode.to_gl()
+            );
+        }
+    }
+
+    /// Set a program parameter such as `gl::PROGRAM_SEPARABLE` or
+    /// `gl::PROGRAM_BINARY_RETRIEVABLE_HINT`.
+    ///
+    /// ## glProgramParameteri
+    ///
+    /// - OpenGL Version 4.1
+    /// - OpenGL ES Version 3.0
+    ///
+    pub fn set_param(&mut self, pname: GLenum, value: GLint) {
+        debug!("[{}]: set param, {} = {}", self.id, pname, value);
+        unsafe { gl::ProgramParameteri(self.id, pname, value) };
+    }
+
     pub fn bind_frag_data_location(&mut self, color_number: GLuint, name: &str) {
         debug!(
             "[{}]: bind frag data location, color_number = {}, name = {}",
@@ -122,23 +288,33 @@ impl Program {
         }
     }
 
+    /// Link attached shaders, forgetting subroutine selections made for the previous link.
     pub fn link(&mut self) -> Result<(), ProgramError> {
         debug!("[{}]: link", self.id);
 
         unsafe { gl::LinkProgram(self.id) };
 
+        self.subroutines.clear();
+        self.sampler_locations.clear();
+
+        self.check_link_status()
+    }
+
+    fn check_link_status(&mut self) -> Result<(), ProgramError> {
         match self.get_param::<GLint>(gl::LINK_STATUS) {
             Ok(link_status) => {
                 if gl::TRUE as GLint == link_status {
                     trace!("[{}]: linked", self.id);
 
+                    self.sampler_locations = get_sampler_locations(self.id);
+
                     Ok(())
                 } else {
                     match self.get_info_log() {
                         Ok(log) => {
                             error!("[{}]: link error, {}", self.id, log);
 
-                            Err(ProgramError::LinkFailed(log))
+                            Err(ProgramError::LinkFailed(LinkLog::parse(&log)))
                         },
                         Err(err) => {
                             error!("[{}]: link error, failed to retrieve log", self.id);
@@ -176,6 +352,278 @@ impl Program {
         }
     }
 
+    /// Get location of uniform variable.
+    ///
+    /// ## glGetUniformLocation
+    ///
+    /// - OpenGL Version 2.0
+    /// - OpenGL ES Version 2.0
+    ///
+    pub fn get_uniform_location(&self, name: &str) -> Result<GLint, GetUniformLocationError> {
+        debug!("[{}]: get uniform location, {}", self.id, name);
+
+        match CString::new(name) {
+            Ok(cname) => {
+                match unsafe { gl::GetUniformLocation(self.id, cname.as_ptr()) } {
+                    -1 => {
+                        debug!("[{}]: name {} has no uniform location", self.id, name);
+                        Err(GetUniformLocationError::Missing(name.to_string()))
+                    },
+                    location => Ok(location),
+                }
+            },
+            Err(_) => {
+                error!("[{}]: invalid name string", self.id);
+                Err(GetUniformLocationError::InvalidNameString)
+            }
+        }
+    }
+
+    /// Set value of uniform at `location` without putting program in use.
+    ///
+    /// ## glProgramUniform*
+    ///
+    /// - OpenGL Version 4.1
+    /// - OpenGL ES Version 3.1
+    ///
+    pub fn set_uniform(&mut self, location: GLint, value: &UniformValue) {
+        value.upload(self.id, location);
+    }
+
+    /// Returns true if uniform at `location` is a sampler of the linked program.
+    ///
+    /// Sampler uniform values select texture units, so they take part in validation.
+    pub fn is_sampler_location(&self, location: GLint) -> bool {
+        self.sampler_locations.contains(&location)
+    }
+
+    /// Query the bindings of a user-defined varying out variable.
+    ///
+    /// Returns the color number and index of the variable, or `None` if
+    /// the variable is not an active output of the linked program.
+    ///
+    /// ## glGetFragDataLocation, glGetFragDataIndex
+    ///
+    /// - OpenGL Version 3.3
+    ///
+    pub fn get_frag_data_location(&self, name: &str) -> Option<(GLuint, GLuint)> {
+        debug!("[{}]: get frag data location, {}", self.id, name);
+
+        let cname = match CString::new(name) {
+            Ok(cname) => cname,
+            Err(_) => {
+                error!("[{}]: invalid name string", self.id);
+                return None;
+            }
+        };
+
+        match unsafe { gl::GetFragDataLocation(self.id, cname.as_ptr()) } {
+            -1 => None,
+            location => match unsafe { gl::GetFragDataIndex(self.id, cname.as_ptr()) } {
+                -1 => None,
+                index => Some((location as GLuint, index as GLuint)),
+            },
+        }
+    }
+
+    /// Get local work group size of linked compute program.
+    ///
+    /// ## glGetProgramiv(GL_COMPUTE_WORK_GROUP_SIZE)
+    ///
+    /// - OpenGL Version 4.3
+    /// - OpenGL ES Version 3.1
+    ///
+    pub fn get_compute_work_group_size(&self) -> Result<[GLuint; 3], GLintFromProgramError> {
+        self.get_param::<[GLint; 3]>(gl::COMPUTE_WORK_GROUP_SIZE)
+            .map(|size| [size[0] as GLuint, size[1] as GLuint, size[2] as GLuint])
+    }
+
+    /// Get a program parameter of specific shader stage.
+    ///
+    /// ## glGetProgramStageiv
+    ///
+    /// - OpenGL Version 4.0
+    ///
+    pub fn get_stage_param(&self, stage: ShaderKind, pname: GLenum) -> Result<GLint, GLintFromProgramError> {
+        trace!("[{}]: get stage param, {:?}, {}", self.id, stage, pname);
+
+        let mut result: GLint = 0;
+        unsafe { gl::GetProgramStageiv(self.id, stage.to_gl(), pname, &mut result) };
+        match unsafe { gl::GetError() } {
+            gl::NO_ERROR => Ok(result),
+            error => Err(GLintFromProgramError { info: error }),
+        }
+    }
+
+    /// Reflect active subroutine uniforms and their compatible subroutines of shader stage.
+    ///
+    /// ## glGetActiveSubroutineUniformName, glGetActiveSubroutineUniformiv, glGetActiveSubroutineName
+    ///
+    /// - OpenGL Version 4.0
+    ///
+    pub fn get_subroutine_uniforms(&self, stage: ShaderKind) -> Result<StageSubroutines, GLintFromProgramError> {
+        debug!("[{}]: get subroutine uniforms, {:?}", self.id, stage);
+
+        let uniform_count = match self.get_stage_param(stage, gl::ACTIVE_SUBROUTINE_UNIFORMS) {
+            Ok(value) => value,
+            Err(err) => return Err(err),
+        };
+        let location_count = match self.get_stage_param(stage, gl::ACTIVE_SUBROUTINE_UNIFORM_LOCATIONS) {
+            Ok(value) => value,
+            Err(err) => return Err(err),
+        };
+        let uniform_max_len = match self.get_stage_param(stage, gl::ACTIVE_SUBROUTINE_UNIFORM_MAX_LENGTH) {
+            Ok(value) => value,
+            Err(err) => return Err(err),
+        };
+        let subroutine_max_len = match self.get_stage_param(stage, gl::ACTIVE_SUBROUTINE_MAX_LENGTH) {
+            Ok(value) => value,
+            Err(err) => return Err(err),
+        };
+
+        let mut uniforms = Vec::with_capacity(uniform_count as usize);
+
+        for uniform_index in 0..uniform_count as GLuint {
+            let name = read_name(uniform_max_len, |len, written, buf| unsafe {
+                gl::GetActiveSubroutineUniformName(self.id, stage.to_gl(), uniform_index, len, written, buf)
+            });
+
+            let location = match CString::new(&name[..]) {
+                Ok(cname) => unsafe { gl::GetSubroutineUniformLocation(self.id, stage.to_gl(), cname.as_ptr()) },
+                Err(_) => -1,
+            };
+
+            let mut array_size: GLint = 0;
+            let mut compatible_count: GLint = 0;
+            unsafe {
+                gl::GetActiveSubroutineUniformiv(self.id, stage.to_gl(), uniform_index, gl::UNIFORM_SIZE, &mut array_size);
+                gl::GetActiveSubroutineUniformiv(self.id, stage.to_gl(), uniform_index, gl::NUM_COMPATIBLE_SUBROUTINES, &mut compatible_count);
+            }
+
+            let mut compatible_indices: Vec<GLint> = vec![0; compatible_count as usize];
+            if compatible_count > 0 {
+                unsafe {
+                    gl::GetActiveSubroutineUniformiv(
+                        self.id, stage.to_gl(), uniform_index, gl::COMPATIBLE_SUBROUTINES,
+                        compatible_indices.as_mut_ptr()
+                    );
+                }
+            }
+
+            match unsafe { gl::GetError() } {
+                gl::NO_ERROR => (),
+                error => {
+                    let error = GLintFromProgramError { info: error };
+                    error!("[{}]: error reflecting subroutine uniform {}, {}", self.id, uniform_index, error);
+                    return Err(error);
+                },
+            }
+
+            let compatible = compatible_indices.into_iter()
+                .map(|index| Subroutine {
+                    name: read_name(subroutine_max_len, |len, written, buf| unsafe {
+                        gl::GetActiveSubroutineName(self.id, stage.to_gl(), index as GLuint, len, written, buf)
+                    }),
+                    index: index as GLuint,
+                })
+                .collect();
+
+            trace!("[{}]: subroutine uniform {} at {}", self.id, name, location);
+
+            uniforms.push(SubroutineUniform {
+                name: name,
+                location: location as GLuint,
+                array_size: array_size as GLuint,
+                compatible: compatible,
+            });
+        }
+
+        Ok(StageSubroutines {
+            stage: stage,
+            location_count: location_count as GLuint,
+            uniforms: uniforms,
+        })
+    }
+
+    /// Select subroutines for subroutine uniforms of program stage by name.
+    ///
+    /// Each selection is a pair of uniform name and subroutine name. Selections
+    /// are merged with ones previously made for the same stage, and every active
+    /// location must end up with a subroutine. Selections are kept until the
+    /// program is linked again, and written by `StateProgram` when the program
+    /// is put in use.
+    pub fn select_subroutines(&mut self, stage: ShaderKind, selections: &[(&str, &str)]) -> Result<(), SubroutineError> {
+        debug!("[{}]: select subroutines, {:?}", self.id, stage);
+
+        let reflection = match self.get_subroutine_uniforms(stage) {
+            Ok(reflection) => reflection,
+            Err(err) => return Err(SubroutineError::Query(err)),
+        };
+
+        let mut assigned: Vec<Option<GLuint>> = match self.subroutines.get(&stage) {
+            Some(indices) if indices.len() == reflection.location_count as usize => {
+                indices.iter().map(|index| Some(*index)).collect()
+            },
+            _ => vec![None; reflection.location_count as usize],
+        };
+
+        for &(uniform, subroutine) in selections {
+            match reflection.resolve(uniform, subroutine) {
+                Ok((location, index)) => {
+                    trace!("[{}]: select {} = {} ({} = {})", self.id, uniform, subroutine, location, index);
+                    assigned[location as usize] = Some(index);
+                },
+                Err(err) => {
+                    error!("[{}]: select subroutines error, {}", self.id, err);
+                    return Err(err);
+                },
+            }
+        }
+
+        let mut indices = Vec::with_capacity(assigned.len());
+        for (location, index) in assigned.into_iter().enumerate() {
+            match index {
+                Some(index) => indices.push(index),
+                None => {
+                    let err = SubroutineError::Unassigned { stage: stage, location: location as GLuint };
+                    error!("[{}]: select subroutines error, {}", self.id, err);
+                    return Err(err);
+                },
+            }
+        }
+
+        self.subroutines.insert(stage, indices);
+
+        Ok(())
+    }
+
+    /// Get selected subroutine indices of stage, indexed by uniform location.
+    pub fn get_subroutines(&self, stage: ShaderKind) -> Option<&[GLuint]> {
+        self.subroutines.get(&stage).map(|indices| &indices[..])
+    }
+
+    /// Get stages with selected subroutines.
+    pub fn get_subroutine_stages(&self) -> Vec<ShaderKind> {
+        self.subroutines.keys().cloned().collect()
+    }
+
+    /// Forget subroutine selections of all stages.
+    pub fn clear_subroutines(&mut self) {
+        debug!("[{}]: clear subroutines", self.id);
+        self.subroutines.clear();
+    }
+
+    /// Check whether the program can execute given the current GL state.
+    ///
+    /// ## glValidateProgram
+    ///
+    /// - OpenGL Version 2.0
+    /// - OpenGL ES Version 2.0
+    ///
+    pub fn validate(&self) -> Result<ProgramValidation, ProgramError> {
+        validate_program(self.id)
+    }
+
     pub fn get_info_log(&self) -> Result<String, ProgramError> {
         trace!("[{}]: get info log", self.id);
 
@@ -239,6 +687,548 @@ impl Drop for Program {
     }
 }
 
+enum PendingState {
+    Linking(Program, Vec<PendingShader>),
+    Done(Result<Program, ProgramError>),
+}
+
+/// Check compile status of shaders attached by `link_async`, then link status.
+fn finish_async_link(mut program: Program, shaders: Vec<PendingShader>, link: bool) -> Result<Program, ProgramError> {
+    for shader in shaders {
+        match shader.resolve() {
+            Ok(shader) => program.shaders.push(Rc::new(shader)),
+            Err(err) => return Err(ProgramError::Shader(err)),
+        }
+    }
+
+    let result = if link { program.link() } else { program.check_link_status() };
+
+    match result {
+        Ok(()) => {
+            info!("[{}]: linked async", program.id);
+            Ok(program)
+        },
+        Err(err) => Err(err),
+    }
+}
+
+/// Program linking issued with `Program::link_async`.
+pub struct PendingProgram {
+    state: PendingState,
+}
+
+impl PendingProgram {
+
+    /// Returns true if linking finished and `resolve` will not block.
+    ///
+    /// ## glGetProgramiv(GL_COMPLETION_STATUS_KHR)
+    ///
+    /// - GL_KHR_parallel_shader_compile
+    ///
+    pub fn is_ready(&self) -> Result<bool, BoolFromProgramError> {
+        match self.state {
+            PendingState::Done(_) => Ok(true),
+            PendingState::Linking(ref program, _) => {
+                program.get_param::<bool>(parallel::COMPLETION_STATUS_KHR)
+            },
+        }
+    }
+
+    /// Get linking result, waiting for it if it is not ready.
+    ///
+    /// Compile errors of attached shaders are reported before link errors.
+    pub fn resolve(self) -> Result<Program, ProgramError> {
+        match self.state {
+            PendingState::Done(result) => result,
+            PendingState::Linking(program, shaders) => finish_async_link(program, shaders, false),
+        }
+    }
+
+    /// Get result if linking finished, or give back the pending handle.
+    ///
+    /// Resolves if completion status can not be queried.
+    pub fn try_resolve(self) -> Result<Result<Program, ProgramError>, PendingProgram> {
+        match self.is_ready() {
+            Ok(false) => Err(self),
+            _ => Ok(self.resolve()),
+        }
+    }
+}
+
+/// Collects shaders and pre-link bindings, then links the program once.
+///
+/// Bindings such as attribute locations only take effect on link, so the
+/// builder applies all of them to a fresh program object before calling
+/// `glLinkProgram` and then checks which of them the driver honored.
+#[derive(Clone)]
+pub struct ProgramBuilder {
+    shaders: Vec<Rc<Shader>>,
+    attrib_locations: Vec<(GLuint, String)>,
+    frag_data_locations: Vec<(GLuint, GLuint, String)>,
+    feedback_varyings: Vec<String>,
+    feedback_buffer_mode: BufferMode,
+    separable: Option<bool>,
+    retrievable_binary: Option<bool>,
+}
+
+impl ProgramBuilder {
+    pub fn new() -> ProgramBuilder {
+        ProgramBuilder {
+            shaders: Vec::with_capacity(2),
+            attrib_locations: Vec::new(),
+            frag_data_locations: Vec::new(),
+            feedback_varyings: Vec::new(),
+            feedback_buffer_mode: BufferMode::Interleaved,
+            separable: None,
+            retrievable_binary: None,
+        }
+    }
+
+    /// Attach a shader on link.
+    pub fn shader(mut self, shader: Rc<Shader>) -> ProgramBuilder {
+        self.shaders.push(shader);
+        self
+    }
+
+    /// Attach multiple shaders on link.
+    pub fn shaders(mut self, shaders: &[Rc<Shader>]) -> ProgramBuilder {
+        self.shaders.extend(shaders.iter().cloned());
+        self
+    }
+
+    /// Bind named vertex attribute to generic attribute `index` before link.
+    pub fn attrib_location(mut self, index: GLuint, name: &str) -> ProgramBuilder {
+        self.attrib_locations.push((index, name.to_string()));
+        self
+    }
+
+    /// Bind named fragment output to `color_number` before link.
+    pub fn frag_data_location(self, color_number: GLuint, name: &str) -> ProgramBuilder {
+        self.frag_data_location_indexed(color_number, 0, name)
+    }
+
+    /// Bind named fragment output to `color_number` and blend `index` before link.
+    pub fn frag_data_location_indexed(mut self, color_number: GLuint, index: GLuint, name: &str) -> ProgramBuilder {
+        self.frag_data_locations.push((color_number, index, name.to_string()));
+        self
+    }
+
+    /// Record named varyings into transform feedback buffers.
+    pub fn feedback_varyings(mut self, varyings: &[&str], buffer_mode: BufferMode) -> ProgramBuilder {
+        self.feedback_varyings = varyings.iter().map(|name| name.to_string()).collect();
+        self.feedback_buffer_mode = buffer_mode;
+        self
+    }
+
+    /// Mark program as usable in separate program pipelines.
+    pub fn separable(mut self, separable: bool) -> ProgramBuilder {
+        self.separable = Some(separable);
+        self
+    }
+
+    /// Hint that program binary will be retrieved after link.
+    pub fn retrievable_binary(mut self, retrievable: bool) -> ProgramBuilder {
+        self.retrievable_binary = Some(retrievable);
+        self
+    }
+
+    /// Create program object, apply all bindings and link it.
+    ///
+    /// Returns linked program together with the bindings that the driver
+    /// ignored, for example because the named variable is not active.
+    pub fn link(self) -> Result<(Program, Vec<IgnoredBinding>), ProgramError> {
+        debug!("build");
+
+        let mut program = Program::internal_new();
+
+        for shader in &self.shaders {
+            if let Err(obj) = program.attach_shader(shader.clone()) {
+                return Err(ProgramError::LinkFailed(LinkLog::parse(&obj.to_string())));
+            }
+        }
+
+        for &(index, ref name) in &self.attrib_locations {
+            program.bind_attrib_location(index, name);
+        }
+
+        for &(color_number, index, ref name) in &self.frag_data_locations {
+            program.bind_frag_data_location_indexed(color_number, index, name);
+        }
+
+        if self.feedback_varyings.len() > 0 {
+            let varyings: Vec<&str> = self.feedback_varyings.iter().map(|name| &name[..]).collect();
+            program.set_transform_feedback_varyings(&varyings, self.feedback_buffer_mode);
+        }
+
+        if let Some(separable) = self.separable {
+            program.set_param(gl::PROGRAM_SEPARABLE, bool_to_glint(separable));
+        }
+
+        if let Some(retrievable) = self.retrievable_binary {
+            program.set_param(gl::PROGRAM_BINARY_RETRIEVABLE_HINT, bool_to_glint(retrievable));
+        }
+
+        if let Err(err) = program.link() {
+            return Err(err);
+        }
+
+        let ignored = self.find_ignored(&program);
+
+        for binding in &ignored {
+            warn!("[{}]: ignored binding, {}", program.id, binding);
+        }
+
+        info!("[{}]: built new", program.id);
+
+        Ok((program, ignored))
+    }
+
+    fn find_ignored(&self, program: &Program) -> Vec<IgnoredBinding> {
+        let mut ignored = Vec::new();
+
+        for &(index, ref name) in &self.attrib_locations {
+            let actual = program.get_attrib_location(name).ok();
+            if actual != Some(index) {
+                ignored.push(IgnoredBinding::AttribLocation {
+                    name: name.clone(),
+                    requested: index,
+                    actual: actual,
+                });
+            }
+        }
+
+        for &(color_number, index, ref name) in &self.frag_data_locations {
+            let actual = program.get_frag_data_location(name);
+            if actual != Some((color_number, index)) {
+                ignored.push(IgnoredBinding::FragDataLocation {
+                    name: name.clone(),
+                    requested: (color_number, index),
+                    actual: actual,
+                });
+            }
+        }
+
+        if self.feedback_varyings.len() > 0 {
+            let captured = get_transform_feedback_varying_names(program);
+            for name in &self.feedback_varyings {
+                if !captured.contains(name) {
+                    ignored.push(IgnoredBinding::FeedbackVarying(name.clone()));
+                }
+            }
+        }
+
+        if let Some(separable) = self.separable {
+            if program.get_param::<bool>(gl::PROGRAM_SEPARABLE).ok() != Some(separable) {
+                ignored.push(IgnoredBinding::Separable(separable));
+            }
+        }
+
+        if let Some(retrievable) = self.retrievable_binary {
+            if program.get_param::<bool>(gl::PROGRAM_BINARY_RETRIEVABLE_HINT).ok() != Some(retrievable) {
+                ignored.push(IgnoredBinding::RetrievableBinary(retrievable));
+            }
+        }
+
+        ignored
+    }
+}
+
+#[inline]
+fn bool_to_glint(value: bool) -> GLint {
+    if value { gl::TRUE as GLint } else { gl::FALSE as GLint }
+}
+
+fn get_transform_feedback_varying_names(program: &Program) -> Vec<String> {
+    let count = program.get_param::<GLint>(gl::TRANSFORM_FEEDBACK_VARYINGS).unwrap_or(0);
+    let max_len = program.get_param::<GLint>(gl::TRANSFORM_FEEDBACK_VARYING_MAX_LENGTH).unwrap_or(0);
+
+    (0..count as GLuint)
+        .filter_map(|index| {
+            let mut buf: Vec<u8> = vec![0; max_len as usize];
+            let mut len: GLsizei = 0;
+            let mut size: GLsizei = 0;
+            let mut ty: GLenum = 0;
+            unsafe {
+                gl::GetTransformFeedbackVarying(
+                    program.id, index, max_len, &mut len, &mut size, &mut ty,
+                    buf.as_mut_ptr() as *mut GLchar
+                );
+            }
+            buf.truncate(len as usize);
+            String::from_utf8(buf).ok()
+        })
+        .collect()
+}
+
+/// Pre-link binding that was not honored by the linked program.
+#[derive(Debug, Clone, PartialEq)]
+pub enum IgnoredBinding {
+    AttribLocation { name: String, requested: GLuint, actual: Option<GLuint> },
+    FragDataLocation { name: String, requested: (GLuint, GLuint), actual: Option<(GLuint, GLuint)> },
+    FeedbackVarying(String),
+    Separable(bool),
+    RetrievableBinary(bool),
+}
+
+impl fmt::Display for IgnoredBinding {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            &IgnoredBinding::AttribLocation { ref name, requested, actual } => match actual {
+                Some(actual) => write!(f, "Attribute {} requested at location {} was linked at {}.", name, requested, actual),
+                None => write!(f, "Attribute {} requested at location {} is not active in program.", name, requested),
+            },
+            &IgnoredBinding::FragDataLocation { ref name, requested, actual } => match actual {
+                Some(actual) => write!(f, "Fragment output {} requested at location {:?} was linked at {:?}.", name, requested, actual),
+                None => write!(f, "Fragment output {} requested at location {:?} is not active in program.", name, requested),
+            },
+            &IgnoredBinding::FeedbackVarying(ref name) => write!(f, "Transform feedback varying {} is not captured by program.", name),
+            &IgnoredBinding::Separable(value) => write!(f, "Program separable flag {} was not applied.", value),
+            &IgnoredBinding::RetrievableBinary(value) => write!(f, "Program retrievable binary hint {} was not applied.", value),
+        }
+    }
+}
+
+/// Subroutine function that can be selected for a subroutine uniform.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Subroutine {
+    pub name: String,
+    pub index: GLuint,
+}
+
+/// Active subroutine uniform of a shader stage.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SubroutineUniform {
+    pub name: String,
+    /// First location of the uniform, array elements follow it.
+    pub location: GLuint,
+    pub array_size: GLuint,
+    pub compatible: Vec<Subroutine>,
+}
+
+/// Subroutine uniforms of a single shader stage.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StageSubroutines {
+    pub stage: ShaderKind,
+    /// Length of the index array expected by `glUniformSubroutinesuiv`.
+    pub location_count: GLuint,
+    pub uniforms: Vec<SubroutineUniform>,
+}
+
+impl StageSubroutines {
+
+    /// Resolve uniform name, optionally with array element suffix such as
+    /// `lights[2]`, and subroutine name into location and subroutine index.
+    pub fn resolve(&self, uniform: &str, subroutine: &str) -> Result<(GLuint, GLuint), SubroutineError> {
+        let (base, element) = match (uniform.find('['), uniform.ends_with("]")) {
+            (Some(open), true) => match uniform[open + 1..uniform.len() - 1].parse::<GLuint>() {
+                Ok(element) => (&uniform[..open], element),
+                Err(_) => return Err(SubroutineError::UnknownUniform(uniform.to_string())),
+            },
+            _ => (uniform, 0),
+        };
+
+        let found = self.uniforms.iter().find(|u| {
+            u.name == base || u.name == uniform || u.name == format!("{}[0]", base)
+        });
+
+        match found {
+            Some(u) if element < u.array_size => {
+                match u.compatible.iter().find(|s| s.name == subroutine) {
+                    Some(s) => Ok((u.location + element, s.index)),
+                    None => Err(SubroutineError::IncompatibleSubroutine {
+                        uniform: uniform.to_string(),
+                        subroutine: subroutine.to_string(),
+                    }),
+                }
+            },
+            _ => Err(SubroutineError::UnknownUniform(uniform.to_string())),
+        }
+    }
+}
+
+/// Get locations of all elements of active sampler uniforms of linked program `id`.
+///
+/// ## glGetActiveUniform
+///
+/// - OpenGL Version 2.0
+/// - OpenGL ES Version 2.0
+///
+fn get_sampler_locations(id: GLuint) -> Vec<GLint> {
+    let mut count: GLint = 0;
+    let mut max_len: GLint = 0;
+    unsafe {
+        gl::GetProgramiv(id, gl::ACTIVE_UNIFORMS, &mut count);
+        gl::GetProgramiv(id, gl::ACTIVE_UNIFORM_MAX_LENGTH, &mut max_len);
+    }
+
+    let mut locations = Vec::new();
+    for index in 0..count as GLuint {
+        let mut size: GLint = 0;
+        let mut ty: GLenum = 0;
+        let name = read_name(max_len, |len, written, buf| unsafe {
+            gl::GetActiveUniform(id, index, len, written, &mut size, &mut ty, buf)
+        });
+
+        if !is_sampler_type(ty) {
+            continue;
+        }
+
+        let location = match CString::new(name) {
+            Ok(name) => unsafe { gl::GetUniformLocation(id, name.as_ptr()) },
+            Err(_) => -1,
+        };
+        if location < 0 {
+            continue;
+        }
+
+        for element in 0..size {
+            locations.push(location + element);
+        }
+    }
+
+    trace!("[{}]: sampler uniform locations, {:?}", id, locations);
+
+    locations
+}
+
+fn is_sampler_type(ty: GLenum) -> bool {
+    match ty {
+        gl::SAMPLER_1D | gl::SAMPLER_2D | gl::SAMPLER_3D | gl::SAMPLER_CUBE
+        | gl::SAMPLER_1D_SHADOW | gl::SAMPLER_2D_SHADOW | gl::SAMPLER_CUBE_SHADOW
+        | gl::SAMPLER_1D_ARRAY | gl::SAMPLER_2D_ARRAY
+        | gl::SAMPLER_1D_ARRAY_SHADOW | gl::SAMPLER_2D_ARRAY_SHADOW
+        | gl::SAMPLER_2D_MULTISAMPLE | gl::SAMPLER_2D_MULTISAMPLE_ARRAY
+        | gl::SAMPLER_BUFFER | gl::SAMPLER_2D_RECT | gl::SAMPLER_2D_RECT_SHADOW
+        | gl::SAMPLER_CUBE_MAP_ARRAY | gl::SAMPLER_CUBE_MAP_ARRAY_SHADOW
+        | gl::INT_SAMPLER_1D | gl::INT_SAMPLER_2D | gl::INT_SAMPLER_3D | gl::INT_SAMPLER_CUBE
+        | gl::INT_SAMPLER_1D_ARRAY | gl::INT_SAMPLER_2D_ARRAY
+        | gl::INT_SAMPLER_2D_MULTISAMPLE | gl::INT_SAMPLER_2D_MULTISAMPLE_ARRAY
+        | gl::INT_SAMPLER_BUFFER | gl::INT_SAMPLER_2D_RECT | gl::INT_SAMPLER_CUBE_MAP_ARRAY
+        | gl::UNSIGNED_INT_SAMPLER_1D | gl::UNSIGNED_INT_SAMPLER_2D | gl::UNSIGNED_INT_SAMPLER_3D
+        | gl::UNSIGNED_INT_SAMPLER_CUBE | gl::UNSIGNED_INT_SAMPLER_1D_ARRAY | gl::UNSIGNED_INT_SAMPLER_2D_ARRAY
+        | gl::UNSIGNED_INT_SAMPLER_2D_MULTISAMPLE | gl::UNSIGNED_INT_SAMPLER_2D_MULTISAMPLE_ARRAY
+        | gl::UNSIGNED_INT_SAMPLER_BUFFER | gl::UNSIGNED_INT_SAMPLER_2D_RECT
+        | gl::UNSIGNED_INT_SAMPLER_CUBE_MAP_ARRAY => true,
+        _ => false,
+    }
+}
+
+/// Read name of at most `max_len` bytes including null terminator.
+fn read_name<F>(max_len: GLint, mut read: F) -> String
+    where
+        F: FnMut(GLsizei, *mut GLsizei, *mut GLchar)
+{
+    if max_len <= 0 {
+        return String::new();
+    }
+
+    let mut buf: Vec<u8> = vec![0; max_len as usize];
+    let mut written: GLsizei = 0;
+    read(max_len, &mut written, buf.as_mut_ptr() as *mut GLchar);
+    buf.truncate(written as usize);
+
+    String::from_utf8(buf).unwrap_or(String::new())
+}
+
+/// Outcome of program validation against current GL state.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProgramValidation {
+    /// Value of `GL_VALIDATE_STATUS`.
+    pub valid: bool,
+    /// Non-empty lines of the program info log.
+    pub messages: Vec<String>,
+}
+
+impl fmt::Display for ProgramValidation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let status = if self.valid { "[valid]" } else { "[invalid]" };
+        write!(f, "{}\n{}", status, self.messages.connect("\n"))
+    }
+}
+
+/// Validate program by raw name.
+///
+/// Used by state trackers that only hold the name of the program in use.
+pub fn validate_program(id: GLuint) -> Result<ProgramValidation, ProgramError> {
+    debug!("[{}]: validate", id);
+
+    let mut status = gl::FALSE as GLint;
+    let mut len: GLint = 0;
+
+    unsafe {
+        gl::ValidateProgram(id);
+        gl::GetProgramiv(id, gl::VALIDATE_STATUS, &mut status);
+        gl::GetProgramiv(id, gl::INFO_LOG_LENGTH, &mut len);
+    }
+
+    match unsafe { gl::GetError() } {
+        gl::NO_ERROR => (),
+        error => {
+            let error = GLintFromProgramError { info: error };
+            error!("[{}]: validate error, {}", id, error);
+            return Err(ProgramError::Other(error.to_string()));
+        },
+    }
+
+    let log = if len > 1 {
+        let mut buf: Vec<u8> = vec![0; len as usize];
+        let mut written: GLsizei = 0;
+        unsafe { gl::GetProgramInfoLog(id, len, &mut written, buf.as_mut_ptr() as *mut GLchar) };
+        buf.truncate(written as usize);
+
+        match String::from_utf8(buf) {
+            Ok(log) => log,
+            _ => {
+                let error = ProgramError::Other("ProgramInfoLog not valid utf8".to_string());
+
+                error!("[{}]: error getting info log, {}", id, error);
+
+                return Err(error);
+            }
+        }
+    } else {
+        String::new()
+    };
+
+    let validation = ProgramValidation {
+        valid: status == gl::TRUE as GLint,
+        messages: log.lines()
+            .map(|line| line.trim())
+            .filter(|line| line.len() > 0)
+            .map(|line| line.to_string())
+            .collect(),
+    };
+
+    trace!("[{}]: validated, {}", id, validation);
+
+    Ok(validation)
+}
+
+/// Get stages of shaders attached to program by raw name.
+///
+/// ## glGetAttachedShaders
+///
+/// - OpenGL Version 2.0
+/// - OpenGL ES Version 2.0
+///
+pub fn get_attached_stages(id: GLuint) -> Vec<ShaderKind> {
+    let mut count: GLint = 0;
+    unsafe { gl::GetProgramiv(id, gl::ATTACHED_SHADERS, &mut count) };
+
+    let mut shaders: Vec<GLuint> = vec![0; count as usize];
+    let mut written: GLsizei = 0;
+    unsafe { gl::GetAttachedShaders(id, count, &mut written, shaders.as_mut_ptr()) };
+    shaders.truncate(written as usize);
+
+    shaders.iter()
+        .filter_map(|&shader| {
+            let mut kind: GLint = 0;
+            unsafe { gl::GetShaderiv(shader, gl::SHADER_TYPE, &mut kind) };
+            ShaderKind::from_gl(kind as GLenum)
+        })
+        .collect()
+}
+
 pub trait ParamFromProgram {
     /// Output error type.
     type Err;
@@ -261,20 +1251,168 @@ impl ParamFromProgram for GLint {
     }
 }
 
+impl ParamFromProgram for [GLint; 3] {
+    type Err = GLintFromProgramError;
+
+    /// Gets `gl::COMPUTE_WORK_GROUP_SIZE`, the only three component parameter.
+    ///
+    /// Other parameters fail with `GL_INVALID_ENUM` without querying, because
+    /// they would write a single value.
+    #[inline]
+    fn param_from_program(program: &Program, pname: GLenum) -> Result<[GLint; 3], GLintFromProgramError> {
+        if pname != gl::COMPUTE_WORK_GROUP_SIZE {
+            error!("[{}]: {} is not a three component parameter", program.id, pname);
+            return Err(GLintFromProgramError { info: gl::INVALID_ENUM });
+        }
+
+        let mut result: [GLint; 3] = [0; 3];
+        unsafe { gl::GetProgramiv(program.id, pname, result.as_mut_ptr()) };
+        match unsafe { gl::GetError() } {
+            gl::NO_ERROR => Ok(result),
+            error => Err(GLintFromProgramError { info: error }),
+        }
+    }
+}
+
+impl ParamFromProgram for bool {
+    type Err = BoolFromProgramError;
+
+    /// Gets boolean parameter such as `gl::LINK_STATUS` or `gl::PROGRAM_SEPARABLE`.
+    fn param_from_program(program: &Program, pname: GLenum) -> Result<bool, BoolFromProgramError> {
+        match <GLint as ParamFromProgram>::param_from_program(program, pname) {
+            Ok(value) if value == gl::TRUE as GLint => Ok(true),
+            Ok(value) if value == gl::FALSE as GLint => Ok(false),
+            Ok(value) => Err(BoolFromProgramError::NotBool(value)),
+            Err(err) => Err(BoolFromProgramError::Query(err)),
+        }
+    }
+}
+
+impl ParamFromProgram for String {
+    type Err = StringFromProgramError;
+
+    /// Gets string by its length parameter.
+    ///
+    /// Pass `gl::INFO_LOG_LENGTH` for info log.
+    fn param_from_program(program: &Program, pname: GLenum) -> Result<String, StringFromProgramError> {
+        if pname != gl::INFO_LOG_LENGTH {
+            return Err(StringFromProgramError::UnsupportedParam(pname));
+        }
+
+        let len = match <GLint as ParamFromProgram>::param_from_program(program, pname) {
+            Ok(len) => len,
+            Err(err) => return Err(StringFromProgramError::Query(err)),
+        };
+
+        if len <= 1 {
+            return Ok(String::new());
+        }
+
+        let mut buf: Vec<u8> = vec![0; len as usize];
+        let mut written: GLsizei = 0;
+        unsafe { gl::GetProgramInfoLog(program.id, len, &mut written, buf.as_mut_ptr() as *mut GLchar) };
+        buf.truncate(written as usize);
+
+        match String::from_utf8(buf) {
+            Ok(value) => Ok(value),
+            Err(_) => Err(StringFromProgramError::InvalidUtf8),
+        }
+    }
+}
+
+/// Primitive type accepted by geometry shader.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum GeometryInputType {
+    Points,
+    Lines,
+    LinesAdjacency,
+    Triangles,
+    TrianglesAdjacency,
+}
+
+impl GeometryInputType {
+    pub fn from_gl(value: GLenum) -> Option<GeometryInputType> {
+        match value {
+            gl::POINTS => Some(GeometryInputType::Points),
+            gl::LINES => Some(GeometryInputType::Lines),
+            gl::LINES_ADJACENCY => Some(GeometryInputType::LinesAdjacency),
+            gl::TRIANGLES => Some(GeometryInputType::Triangles),
+            gl::TRIANGLES_ADJACENCY => Some(GeometryInputType::TrianglesAdjacency),
+            _ => None,
+        }
+    }
+}
+
+/// Primitive type emitted by geometry shader.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum GeometryOutputType {
+    Points,
+    LineStrip,
+    TriangleStrip,
+}
+
+impl GeometryOutputType {
+    pub fn from_gl(value: GLenum) -> Option<GeometryOutputType> {
+        match value {
+            gl::POINTS => Some(GeometryOutputType::Points),
+            gl::LINE_STRIP => Some(GeometryOutputType::LineStrip),
+            gl::TRIANGLE_STRIP => Some(GeometryOutputType::TriangleStrip),
+            _ => None,
+        }
+    }
+}
+
+macro_rules! impl_enum_param_from_program {
+    ( $Type:ty, $doc:expr ) => {
+        impl ParamFromProgram for $Type {
+            type Err = EnumFromProgramError;
+
+            #[doc = $doc]
+            fn param_from_program(program: &Program, pname: GLenum) -> Result<$Type, EnumFromProgramError> {
+                match <GLint as ParamFromProgram>::param_from_program(program, pname) {
+                    Ok(value) => match <$Type>::from_gl(value as GLenum) {
+                        Some(result) => Ok(result),
+                        None => Err(EnumFromProgramError::Unrecognised(value)),
+                    },
+                    Err(err) => Err(EnumFromProgramError::Query(err)),
+                }
+            }
+        }
+    };
+}
+
+impl_enum_param_from_program!(GeometryInputType, "Gets `gl::GEOMETRY_INPUT_TYPE` parameter.");
+impl_enum_param_from_program!(GeometryOutputType, "Gets `gl::GEOMETRY_OUTPUT_TYPE` parameter.");
+impl_enum_param_from_program!(BufferMode, "Gets `gl::TRANSFORM_FEEDBACK_BUFFER_MODE` parameter.");
+
+#[derive(Debug, Clone, PartialEq)]
 pub enum ProgramError {
-    LinkFailed(String),
+    LinkFailed(LinkLog),
+    /// Compiling a shader for the program failed.
+    Shader(ShaderError),
     Other(String),
 }
 
 impl fmt::Display for ProgramError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
-            &ProgramError::LinkFailed(ref log) => write!(f, "[link failed]\n{}", log),
+            &ProgramError::LinkFailed(ref log) => write!(f, "[link failed]\n{}", log.raw),
+            &ProgramError::Shader(ref err) => err.fmt(f),
             &ProgramError::Other(ref err) => write!(f, "[other error]\n{}", err),
         }
     }
 }
 
+impl Error for ProgramError {
+    fn description(&self) -> &str {
+        match self {
+            &ProgramError::LinkFailed(_) => "program link failed",
+            &ProgramError::Shader(_) => "program shader error",
+            &ProgramError::Other(_) => "program error",
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct AttachShaderError;
 
@@ -308,6 +1446,21 @@ impl fmt::Display for GetAttribLocationError {
     }
 }
 
+#[derive(Debug, Clone, PartialEq)]
+pub enum GetUniformLocationError {
+    InvalidNameString,
+    Missing(String),
+}
+
+impl fmt::Display for GetUniformLocationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            &GetUniformLocationError::InvalidNameString => "Tried to get program uniform location with bad name string that could not be converted to c-string.".fmt(f),
+            &GetUniformLocationError::Missing(ref name) => write!(f, "Tried to get uniform location of {} which is not active in program.", name),
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct GLintFromProgramError {
     info: GLenum
@@ -323,3 +1476,69 @@ impl fmt::Display for GLintFromProgramError {
         }
     }
 }
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum BoolFromProgramError {
+    Query(GLintFromProgramError),
+    NotBool(GLint),
+}
+
+impl fmt::Display for BoolFromProgramError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            &BoolFromProgramError::Query(ref err) => err.fmt(f),
+            &BoolFromProgramError::NotBool(value) => write!(f, "Program parameter value {} is not a boolean.", value),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum EnumFromProgramError {
+    Query(GLintFromProgramError),
+    Unrecognised(GLint),
+}
+
+impl fmt::Display for EnumFromProgramError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            &EnumFromProgramError::Query(ref err) => err.fmt(f),
+            &EnumFromProgramError::Unrecognised(value) => write!(f, "Program parameter value {} is not recognised for requested type.", value),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum StringFromProgramError {
+    Query(GLintFromProgramError),
+    UnsupportedParam(GLenum),
+    InvalidUtf8,
+}
+
+impl fmt::Display for StringFromProgramError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            &StringFromProgramError::Query(ref err) => err.fmt(f),
+            &StringFromProgramError::UnsupportedParam(pname) => write!(f, "Program parameter {} can not be retrieved as string.", pname),
+            &StringFromProgramError::InvalidUtf8 => "Program string parameter is not valid utf8.".fmt(f),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SubroutineError {
+    Query(GLintFromProgramError),
+    UnknownUniform(String),
+    IncompatibleSubroutine { uniform: String, subroutine: String },
+    Unassigned { stage: ShaderKind, location: GLuint },
+}
+
+impl fmt::Display for SubroutineError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            &SubroutineError::Query(ref err) => err.fmt(f),
+            &SubroutineError::UnknownUniform(ref name) => write!(f, "Tried to select subroutine for uniform {} which is not active in program.", name),
+            &SubroutineError::IncompatibleSubroutine { ref uniform, ref subroutine } => write!(f, "Subroutine {} is not compatible with subroutine uniform {}.", subroutine, uniform),
+            &SubroutineError::Unassigned { stage, location } => write!(f, "Subroutine uniform location {} of {:?} stage has no subroutine selected.", location, stage),
+        }
+    }
+}
@@ -0,0 +1,286 @@
+use gl::types::*;
+
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::path::{ Path, PathBuf };
+use std::rc::Rc;
+use std::time::SystemTime;
+
+use shader::{ Shader, ShaderKind, ShaderError };
+use program::{ Program, ProgramBuilder, ProgramError };
+use preprocess::{ DirectorySource, Preprocessor, PreprocessError };
+use uniform::UniformValue;
+
+/// Shader stage loaded from a file.
+struct Stage {
+    kind: ShaderKind,
+    path: PathBuf,
+}
+
+/// Result of polling source files for changes.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ReloadStatus {
+    /// No source file changed.
+    Unchanged,
+    /// Sources changed and program was relinked.
+    Reloaded,
+    /// Sources changed but new program failed to build, old program is kept.
+    Failed,
+}
+
+/// Program that is rebuilt when its source files change on disk.
+///
+/// Includes are resolved relative to each stage file and are watched too.
+/// When rebuilding fails the previous program stays in place and the error
+/// is available from `get_error`.
+///
+/// The new program is linked with bindings of the builder passed to
+/// `load_with`. Uniform locations requested through this handle, uniform
+/// values set through it and subroutine selections of the old program are
+/// carried over to the new program, as long as the names still resolve.
+/// Uniform values set directly on the program from `get_mut` are lost. The
+/// new program has a different name, so it must be put in use again after
+/// reload.
+pub struct ReloadableProgram {
+    stages: Vec<Stage>,
+    builder: ProgramBuilder,
+    watched: Vec<(PathBuf, Option<SystemTime>)>,
+    program: Program,
+    error: Option<ReloadError>,
+    locations: HashMap<String, Option<GLint>>,
+    values: HashMap<String, UniformValue>,
+}
+
+impl ReloadableProgram {
+
+    /// Build program from stage source files.
+    pub fn load(stages: &[(ShaderKind, &Path)]) -> Result<ReloadableProgram, ReloadError> {
+        ReloadableProgram::load_with(stages, ProgramBuilder::new())
+    }
+
+    /// Build program from stage source files, applying pre-link bindings of
+    /// `builder` on every build. Shaders of `builder` are linked too.
+    pub fn load_with(stages: &[(ShaderKind, &Path)], builder: ProgramBuilder) -> Result<ReloadableProgram, ReloadError> {
+        debug!("load reloadable program");
+
+        let stages: Vec<Stage> = stages.iter()
+            .map(|&(kind, path)| Stage { kind: kind, path: path.to_path_buf() })
+            .collect();
+
+        let (program, files) = match build(&stages, &builder) {
+            Ok(result) => result,
+            Err(err) => {
+                error!("failed to load reloadable program, {}", err);
+                return Err(err);
+            },
+        };
+
+        info!("[{}]: loaded reloadable program", program.get_id());
+
+        Ok(ReloadableProgram {
+            stages: stages,
+            builder: builder,
+            watched: watch(files),
+            program: program,
+            error: None,
+            locations: HashMap::new(),
+            values: HashMap::new(),
+        })
+    }
+
+    /// Get current program.
+    pub fn get(&self) -> &Program {
+        &self.program
+    }
+
+    /// Get current program.
+    pub fn get_mut(&mut self) -> &mut Program {
+        &mut self.program
+    }
+
+    /// Get error of the last failed rebuild, cleared by successful rebuild.
+    pub fn get_error(&self) -> Option<&ReloadError> {
+        self.error.as_ref()
+    }
+
+    /// Check modification times of source files and rebuild program if any changed.
+    pub fn poll(&mut self) -> ReloadStatus {
+        let changed = self.watched.iter()
+            .any(|&(ref path, modified)| modified_time(path) != modified);
+
+        if !changed {
+            return ReloadStatus::Unchanged;
+        }
+
+        self.reload()
+    }
+
+    /// Rebuild program from source files.
+    pub fn reload(&mut self) -> ReloadStatus {
+        debug!("[{}]: reload", self.program.get_id());
+
+        let (program, files) = match build(&self.stages, &self.builder) {
+            Ok(result) => result,
+            Err(err) => {
+                error!("[{}]: reload failed, keeping old program, {}", self.program.get_id(), err);
+                // Watch current file times so the same failure is not retried until next edit.
+                let paths = self.watched.iter().map(|&(ref path, _)| path.clone()).collect();
+                self.watched = watch(paths);
+                self.error = Some(err);
+                return ReloadStatus::Failed;
+            },
+        };
+
+        info!("[{}]: reloaded as {}", self.program.get_id(), program.get_id());
+
+        let subroutines = subroutine_names(&self.program);
+
+        self.program = program;
+        self.watched = watch(files);
+        self.error = None;
+
+        let names: Vec<String> = self.locations.keys().cloned().collect();
+        for name in names {
+            let location = self.program.get_uniform_location(&name).ok();
+            self.locations.insert(name, location);
+        }
+
+        for (name, value) in self.values.iter() {
+            if let Some(&Some(location)) = self.locations.get(name) {
+                self.program.set_uniform(location, value);
+            }
+        }
+
+        for (stage, selections) in subroutines {
+            let selections: Vec<(&str, &str)> = selections.iter()
+                .map(|&(ref uniform, ref subroutine)| (&uniform[..], &subroutine[..]))
+                .collect();
+            if let Err(err) = self.program.select_subroutines(stage, &selections) {
+                warn!("[{}]: subroutines of {:?} not carried over, {}", self.program.get_id(), stage, err);
+            }
+        }
+
+        ReloadStatus::Reloaded
+    }
+
+    /// Get uniform location, cached across reloads.
+    pub fn get_uniform_location(&mut self, name: &str) -> Option<GLint> {
+        if let Some(&location) = self.locations.get(name) {
+            return location;
+        }
+
+        let location = self.program.get_uniform_location(name).ok();
+        self.locations.insert(name.to_string(), location);
+        location
+    }
+
+    /// Set uniform value by name and remember it for reapplying after reload.
+    pub fn set_uniform(&mut self, name: &str, value: UniformValue) {
+        if let Some(location) = self.get_uniform_location(name) {
+            self.program.set_uniform(location, &value);
+        }
+        self.values.insert(name.to_string(), value);
+    }
+}
+
+/// Get subroutine selections of program as uniform and subroutine names for each stage.
+fn subroutine_names(program: &Program) -> Vec<(ShaderKind, Vec<(String, String)>)> {
+    let mut stages = Vec::new();
+
+    for stage in program.get_subroutine_stages() {
+        let indices = match program.get_subroutines(stage) {
+            Some(indices) => indices,
+            None => continue,
+        };
+        let reflection = match program.get_subroutine_uniforms(stage) {
+            Ok(reflection) => reflection,
+            Err(_) => continue,
+        };
+
+        let mut selections = Vec::new();
+        for uniform in &reflection.uniforms {
+            let base = if uniform.name.ends_with("[0]") { &uniform.name[..uniform.name.len() - 3] } else { &uniform.name[..] };
+            for element in 0..uniform.array_size {
+                let index = match indices.get((uniform.location + element) as usize) {
+                    Some(&index) => index,
+                    None => continue,
+                };
+                let name = if uniform.array_size > 1 { format!("{}[{}]", base, element) } else { uniform.name.clone() };
+                if let Some(subroutine) = uniform.compatible.iter().find(|s| s.index == index) {
+                    selections.push((name, subroutine.name.clone()));
+                }
+            }
+        }
+
+        stages.push((stage, selections));
+    }
+
+    stages
+}
+
+/// Compile and link stages with bindings of `builder`, returning program and all files it was built from.
+fn build(stages: &[Stage], builder: &ProgramBuilder) -> Result<(Program, Vec<PathBuf>), ReloadError> {
+    let mut shaders = Vec::with_capacity(stages.len());
+    let mut files = Vec::new();
+
+    for stage in stages {
+        let dir = stage.path.parent().unwrap_or(Path::new("")).to_path_buf();
+        let name = match stage.path.file_name().and_then(|name| name.to_str()) {
+            Some(name) => name.to_string(),
+            None => return Err(ReloadError::InvalidPath(stage.path.clone())),
+        };
+
+        let provider = DirectorySource::new(dir.clone());
+        let preprocessed = match Preprocessor::new(&provider).process(&name) {
+            Ok(preprocessed) => preprocessed,
+            Err(err) => return Err(ReloadError::Preprocess(err)),
+        };
+
+        for file in &preprocessed.line_map.files {
+            files.push(dir.join(file));
+        }
+
+        match Shader::compile_preprocessed(&preprocessed, stage.kind) {
+            Ok(shader) => shaders.push(Rc::new(shader)),
+            Err(err) => return Err(ReloadError::Compile(stage.path.clone(), err)),
+        }
+    }
+
+    match builder.clone().shaders(&shaders).link() {
+        Ok((program, _)) => Ok((program, files)),
+        Err(err) => Err(ReloadError::Link(err)),
+    }
+}
+
+fn watch(files: Vec<PathBuf>) -> Vec<(PathBuf, Option<SystemTime>)> {
+    files.into_iter()
+        .map(|path| {
+            let modified = modified_time(&path);
+            (path, modified)
+        })
+        .collect()
+}
+
+fn modified_time(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|metadata| metadata.modified()).ok()
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ReloadError {
+    InvalidPath(PathBuf),
+    Preprocess(PreprocessError),
+    Compile(PathBuf, ShaderError),
+    Link(ProgramError),
+}
+
+impl fmt::Display for ReloadError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            &ReloadError::InvalidPath(ref path) => write!(f, "Shader path {} is not a valid file name.", path.display()),
+            &ReloadError::Preprocess(ref err) => err.fmt(f),
+            &ReloadError::Compile(ref path, ref err) => write!(f, "{}: {}", path.display(), err),
+            &ReloadError::Link(ref err) => err.fmt(f),
+        }
+    }
+}
@@ -0,0 +1,614 @@
+use gl;
+use gl::types::*;
+use std::collections::HashMap;
+use std::hash::{ Hash, Hasher };
+use std::rc::Rc;
+use std::fmt;
+
+use gli;
+use role;
+use role::Generator;
+use version::{ Api, GLVersion, has_extension };
+
+/// `GL_TEXTURE_MAX_ANISOTROPY`, same value as the `EXT` variant.
+pub const TEXTURE_MAX_ANISOTROPY: GLenum = 0x84FE;
+/// `GL_MAX_TEXTURE_MAX_ANISOTROPY`, same value as the `EXT` variant.
+pub const MAX_TEXTURE_MAX_ANISOTROPY: GLenum = 0x84FF;
+
+impl role::Generator for SamplerState {
+    type Object = Sampler;
+}
+
+impl gli::Generate for SamplerState {
+    fn gl_gen(size: usize) -> Vec<GLuint> {
+        let mut ids: Vec<GLuint> = vec![0; size];
+        unsafe { gl::GenSamplers(size as GLsizei, ids.as_mut_ptr()) };
+        ids
+    }
+}
+
+impl gli::IntoObject<Sampler> for Sampler {
+    fn new_object(id: GLuint) -> Sampler {
+        Sampler::from_raw(id)
+    }
+}
+
+/// Texel filter for magnification and minification.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Filter {
+    Nearest,
+    Linear,
+}
+
+/// Filter between mip levels.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum MipmapFilter {
+    /// Sample base level only.
+    None,
+    Nearest,
+    Linear,
+}
+
+/// Texture coordinate wrap mode.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Wrap {
+    Repeat,
+    MirroredRepeat,
+    ClampToEdge,
+    ClampToBorder,
+    MirrorClampToEdge,
+}
+
+impl Wrap {
+    pub fn to_gl(&self) -> GLenum {
+        match *self {
+            Wrap::Repeat => gl::REPEAT,
+            Wrap::MirroredRepeat => gl::MIRRORED_REPEAT,
+            Wrap::ClampToEdge => gl::CLAMP_TO_EDGE,
+            Wrap::ClampToBorder => gl::CLAMP_TO_BORDER,
+            Wrap::MirrorClampToEdge => gl::MIRROR_CLAMP_TO_EDGE,
+        }
+    }
+}
+
+/// Sampler parameters and wrap modes that are not available in every context.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct SamplerSupport {
+    /// `GL_TEXTURE_LOD_BIAS`, missing on OpenGL ES.
+    pub lod_bias: bool,
+    /// `GL_CLAMP_TO_BORDER` and `GL_TEXTURE_BORDER_COLOR`, on OpenGL ES only from
+    /// 3.2 or with `EXT_texture_border_clamp`.
+    pub border_clamp: bool,
+    /// `GL_MIRROR_CLAMP_TO_EDGE`, from OpenGL 4.4 or with `ARB_texture_mirror_clamp_to_edge`.
+    pub mirror_clamp: bool,
+}
+
+impl SamplerSupport {
+    /// Query support in the current context.
+    pub fn query() -> SamplerSupport {
+        let support = match GLVersion::current() {
+            Some(version) if version.api == Api::GlEs => SamplerSupport {
+                lod_bias: false,
+                border_clamp: version.is_at_least(Api::GlEs, 3, 2)
+                    || has_extension("GL_EXT_texture_border_clamp")
+                    || has_extension("GL_OES_texture_border_clamp"),
+                mirror_clamp: has_extension("GL_EXT_texture_mirror_clamp_to_edge"),
+            },
+            version => SamplerSupport {
+                lod_bias: true,
+                border_clamp: true,
+                mirror_clamp: version.map_or(false, |version| version.is_at_least(Api::Gl, 4, 4))
+                    || has_extension("GL_ARB_texture_mirror_clamp_to_edge"),
+            },
+        };
+
+        debug!("sampler support, {:?}", support);
+
+        support
+    }
+
+    /// Returns true if `wrap` mode can be used.
+    pub fn has_wrap(&self, wrap: Wrap) -> bool {
+        match wrap {
+            Wrap::ClampToBorder => self.border_clamp,
+            Wrap::MirrorClampToEdge => self.mirror_clamp,
+            _ => true,
+        }
+    }
+}
+
+/// Comparison of depth texture samples against reference value.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum CompareFunc {
+    Never,
+    Less,
+    Equal,
+    LessEqual,
+    Greater,
+    NotEqual,
+    GreaterEqual,
+    Always,
+}
+
+impl CompareFunc {
+    pub fn to_gl(&self) -> GLenum {
+        match *self {
+            CompareFunc::Never => gl::NEVER,
+            CompareFunc::Less => gl::LESS,
+            CompareFunc::Equal => gl::EQUAL,
+            CompareFunc::LessEqual => gl::LEQUAL,
+            CompareFunc::Greater => gl::GREATER,
+            CompareFunc::NotEqual => gl::NOTEQUAL,
+            CompareFunc::GreaterEqual => gl::GEQUAL,
+            CompareFunc::Always => gl::ALWAYS,
+        }
+    }
+}
+
+/// Complete sampler state.
+///
+/// Starts with OpenGL defaults. Descriptions are compared and hashed by the
+/// bits of their float values, so equal descriptions share one sampler object.
+#[derive(Debug, Copy, Clone)]
+pub struct SamplerDescription {
+    pub mag_filter: Filter,
+    pub min_filter: Filter,
+    pub mipmap_filter: MipmapFilter,
+    pub wrap: [Wrap; 3],
+    pub max_anisotropy: f32,
+    pub lod_bias: f32,
+    pub min_lod: f32,
+    pub max_lod: f32,
+    pub border_color: [f32; 4],
+    /// Depth compare function, or `None` to return depth values.
+    pub compare: Option<CompareFunc>,
+}
+
+impl SamplerDescription {
+    pub fn new() -> SamplerDescription {
+        SamplerDescription {
+            mag_filter: Filter::Linear,
+            min_filter: Filter::Nearest,
+            mipmap_filter: MipmapFilter::Linear,
+            wrap: [Wrap::Repeat; 3],
+            max_anisotropy: 1.0,
+            lod_bias: 0.0,
+            min_lod: -1000.0,
+            max_lod: 1000.0,
+            border_color: [0.0; 4],
+            compare: None,
+        }
+    }
+
+    /// Set magnification and minification filters.
+    pub fn filter(mut self, mag: Filter, min: Filter, mipmap: MipmapFilter) -> SamplerDescription {
+        self.mag_filter = mag;
+        self.min_filter = min;
+        self.mipmap_filter = mipmap;
+        self
+    }
+
+    /// Set wrap mode of all coordinates.
+    pub fn wrap(self, wrap: Wrap) -> SamplerDescription {
+        self.wrap_str(wrap, wrap, wrap)
+    }
+
+    /// Set wrap modes of S, T and R coordinates.
+    pub fn wrap_str(mut self, s: Wrap, t: Wrap, r: Wrap) -> SamplerDescription {
+        self.wrap = [s, t, r];
+        self
+    }
+
+    pub fn anisotropy(mut self, max_anisotropy: f32) -> SamplerDescription {
+        self.max_anisotropy = max_anisotropy;
+        self
+    }
+
+    pub fn lod_bias(mut self, bias: f32) -> SamplerDescription {
+        self.lod_bias = bias;
+        self
+    }
+
+    pub fn lod_range(mut self, min: f32, max: f32) -> SamplerDescription {
+        self.min_lod = min;
+        self.max_lod = max;
+        self
+    }
+
+    pub fn border_color(mut self, color: [f32; 4]) -> SamplerDescription {
+        self.border_color = color;
+        self
+    }
+
+    pub fn compare(mut self, func: CompareFunc) -> SamplerDescription {
+        self.compare = Some(func);
+        self
+    }
+
+    /// Get `GL_TEXTURE_MIN_FILTER` value.
+    pub fn min_filter_to_gl(&self) -> GLenum {
+        match (self.min_filter, self.mipmap_filter) {
+            (Filter::Nearest, MipmapFilter::None) => gl::NEAREST,
+            (Filter::Linear, MipmapFilter::None) => gl::LINEAR,
+            (Filter::Nearest, MipmapFilter::Nearest) => gl::NEAREST_MIPMAP_NEAREST,
+            (Filter::Linear, MipmapFilter::Nearest) => gl::LINEAR_MIPMAP_NEAREST,
+            (Filter::Nearest, MipmapFilter::Linear) => gl::NEAREST_MIPMAP_LINEAR,
+            (Filter::Linear, MipmapFilter::Linear) => gl::LINEAR_MIPMAP_LINEAR,
+        }
+    }
+
+    /// Get `GL_TEXTURE_MAG_FILTER` value.
+    pub fn mag_filter_to_gl(&self) -> GLenum {
+        match self.mag_filter {
+            Filter::Nearest => gl::NEAREST,
+            Filter::Linear => gl::LINEAR,
+        }
+    }
+
+    /// Check values that OpenGL would reject.
+    pub fn check(&self) -> Result<(), SamplerError> {
+        if !(self.max_anisotropy >= 1.0) {
+            return Err(SamplerError::InvalidAnisotropy(self.max_anisotropy));
+        }
+        if !(self.min_lod <= self.max_lod) {
+            return Err(SamplerError::InvalidLodRange { min: self.min_lod, max: self.max_lod });
+        }
+        Ok(())
+    }
+
+    /// Check that parameters differing from OpenGL defaults are available.
+    ///
+    /// Border color is only used with `Wrap::ClampToBorder`, so it is not checked.
+    pub fn check_supported(&self, support: &SamplerSupport) -> Result<(), SamplerError> {
+        if self.lod_bias != 0.0 && !support.lod_bias {
+            return Err(SamplerError::LodBiasUnsupported);
+        }
+        for &wrap in self.wrap.iter() {
+            if !support.has_wrap(wrap) {
+                return Err(SamplerError::WrapUnsupported(wrap));
+            }
+        }
+        Ok(())
+    }
+
+    fn key(&self) -> ([u32; 8], Filter, Filter, MipmapFilter, [Wrap; 3], Option<CompareFunc>) {
+        (
+            [
+                self.max_anisotropy.to_bits(),
+                self.lod_bias.to_bits(),
+                self.min_lod.to_bits(),
+                self.max_lod.to_bits(),
+                self.border_color[0].to_bits(),
+                self.border_color[1].to_bits(),
+                self.border_color[2].to_bits(),
+                self.border_color[3].to_bits(),
+            ],
+            self.mag_filter,
+            self.min_filter,
+            self.mipmap_filter,
+            self.wrap,
+            self.compare,
+        )
+    }
+}
+
+impl PartialEq for SamplerDescription {
+    fn eq(&self, other: &SamplerDescription) -> bool {
+        self.key() == other.key()
+    }
+}
+
+impl Eq for SamplerDescription {}
+
+impl Hash for SamplerDescription {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.key().hash(state);
+    }
+}
+
+/// Raw sampler object wrapper to hide RAII mechanism.
+struct Raw {
+    id: GLuint,
+}
+
+impl Drop for Raw {
+
+    /// Delete sampler objects.
+    ///
+    /// ## glDeleteSamplers
+    ///
+    /// - OpenGL Version 3.3
+    /// - OpenGL ES Version 3.0
+    ///
+    fn drop(&mut self) {
+        debug!("[{}]: cleanup && delete", self.id);
+        unsafe { gl::DeleteSamplers(1, &mut self.id) };
+    }
+}
+
+/// Manipulates OpenGL sampler object.
+#[derive(Clone)]
+pub struct Sampler {
+    raw: Rc<Raw>,
+}
+
+impl Sampler {
+
+    /// Create from raw name.
+    pub fn from_raw(id: GLuint) -> Sampler {
+        Sampler { raw: Rc::new(Raw { id: id }) }
+    }
+
+    /// Get raw name.
+    pub fn get_id(&self) -> GLuint {
+        self.raw.id
+    }
+
+    /// Set all parameters from `description`.
+    ///
+    /// Anisotropy is only set when it is greater than 1. LOD bias and border
+    /// color are skipped when `support` lacks them.
+    ///
+    /// ## glSamplerParameteri, glSamplerParameterf, glSamplerParameterfv
+    ///
+    /// - OpenGL Version 3.3
+    /// - OpenGL ES Version 3.0
+    ///
+    pub fn apply(&self, description: &SamplerDescription, support: &SamplerSupport) {
+        debug!("[{}]: apply {:?}", self.get_id(), description);
+
+        let id = self.get_id();
+        unsafe {
+            gl::SamplerParameteri(id, gl::TEXTURE_MAG_FILTER, description.mag_filter_to_gl() as GLint);
+            gl::SamplerParameteri(id, gl::TEXTURE_MIN_FILTER, description.min_filter_to_gl() as GLint);
+            gl::SamplerParameteri(id, gl::TEXTURE_WRAP_S, description.wrap[0].to_gl() as GLint);
+            gl::SamplerParameteri(id, gl::TEXTURE_WRAP_T, description.wrap[1].to_gl() as GLint);
+            gl::SamplerParameteri(id, gl::TEXTURE_WRAP_R, description.wrap[2].to_gl() as GLint);
+            gl::SamplerParameterf(id, gl::TEXTURE_MIN_LOD, description.min_lod);
+            gl::SamplerParameterf(id, gl::TEXTURE_MAX_LOD, description.max_lod);
+
+            if support.lod_bias {
+                gl::SamplerParameterf(id, gl::TEXTURE_LOD_BIAS, description.lod_bias);
+            }
+            if support.border_clamp {
+                gl::SamplerParameterfv(id, gl::TEXTURE_BORDER_COLOR, description.border_color.as_ptr());
+            }
+
+            match description.compare {
+                Some(func) => {
+                    gl::SamplerParameteri(id, gl::TEXTURE_COMPARE_MODE, gl::COMPARE_REF_TO_TEXTURE as GLint);
+                    gl::SamplerParameteri(id, gl::TEXTURE_COMPARE_FUNC, func.to_gl() as GLint);
+                },
+                None => gl::SamplerParameteri(id, gl::TEXTURE_COMPARE_MODE, gl::NONE as GLint),
+            }
+
+            if description.max_anisotropy > 1.0 {
+                gl::SamplerParameterf(id, TEXTURE_MAX_ANISOTROPY, description.max_anisotropy);
+            }
+        }
+    }
+
+    /// Determine if a name corresponds to a sampler object.
+    ///
+    /// ## glIsSampler
+    ///
+    /// - OpenGL Version 3.3
+    /// - OpenGL ES Version 3.0
+    ///
+    pub fn is_sampler(&self) -> bool {
+        unsafe { gl::IsSampler(self.get_id()) == gl::TRUE }
+    }
+}
+
+/// Shares sampler objects between identical descriptions and tracks sampler bound to each texture unit.
+///
+/// Number of units is queried from `GL_MAX_COMBINED_TEXTURE_IMAGE_UNITS` on
+/// first use. Redundant `glBindSampler` calls are skipped.
+pub struct SamplerState {
+    samplers: HashMap<SamplerDescription, Sampler>,
+    units: Vec<Option<Sampler>>,
+    max_anisotropy: Option<f32>,
+    support: Option<SamplerSupport>,
+}
+
+impl SamplerState {
+    pub fn new() -> SamplerState {
+        SamplerState {
+            samplers: HashMap::new(),
+            units: Vec::new(),
+            max_anisotropy: None,
+            support: None,
+        }
+    }
+
+    fn ensure_units(&mut self) {
+        if self.units.len() > 0 {
+            return;
+        }
+
+        let mut count: GLint = 0;
+        unsafe { gl::GetIntegerv(gl::MAX_COMBINED_TEXTURE_IMAGE_UNITS, &mut count) };
+
+        debug!("sampler units, count = {}", count);
+
+        self.units = vec![None; count as usize];
+    }
+
+    fn check_unit(&mut self, unit: GLuint) -> Result<(), SamplerError> {
+        self.ensure_units();
+        let count = self.units.len() as GLuint;
+        if unit >= count {
+            error!("sampler unit {} out of range, count = {}", unit, count);
+            return Err(SamplerError::UnitOutOfRange { unit: unit, count: count });
+        }
+        Ok(())
+    }
+
+    /// Maximum supported anisotropy, 1 if anisotropic filtering is not supported.
+    ///
+    /// Requires OpenGL 4.6 or `EXT_texture_filter_anisotropic`.
+    pub fn get_max_anisotropy(&mut self) -> f32 {
+        if let Some(max) = self.max_anisotropy {
+            return max;
+        }
+
+        let supported = match GLVersion::current() {
+            Some(version) => version.is_at_least(Api::Gl, 4, 6)
+                || has_extension("GL_EXT_texture_filter_anisotropic")
+                || has_extension("GL_ARB_texture_filter_anisotropic"),
+            None => false,
+        };
+
+        let mut max: GLfloat = 1.0;
+        if supported {
+            unsafe { gl::GetFloatv(MAX_TEXTURE_MAX_ANISOTROPY, &mut max) };
+        }
+
+        debug!("max anisotropy = {}", max);
+
+        self.max_anisotropy = Some(max);
+        max
+    }
+
+    /// Get parameters and wrap modes available, querying them on first use.
+    pub fn get_support(&mut self) -> SamplerSupport {
+        match self.support {
+            Some(support) => support,
+            None => {
+                let support = SamplerSupport::query();
+                self.support = Some(support);
+                support
+            }
+        }
+    }
+
+    /// Get sampler with `description`, creating it if no identical one exists.
+    pub fn get(&mut self, description: &SamplerDescription) -> Result<Sampler, SamplerError> {
+        if let Some(sampler) = self.samplers.get(description) {
+            trace!("[{}]: shared sampler", sampler.get_id());
+            return Ok(sampler.clone());
+        }
+
+        if let Err(err) = description.check() {
+            error!("invalid sampler description, {}", err);
+            return Err(err);
+        }
+
+        let max = self.get_max_anisotropy();
+        if description.max_anisotropy > max {
+            error!("sampler anisotropy {} exceeds maximum {}", description.max_anisotropy, max);
+            return Err(SamplerError::AnisotropyExceeded { requested: description.max_anisotropy, max: max });
+        }
+
+        let support = self.get_support();
+        if let Err(err) = description.check_supported(&support) {
+            error!("unsupported sampler description, {}", err);
+            return Err(err);
+        }
+
+        let sampler = self.gen_one();
+        sampler.apply(description, &support);
+
+        self.samplers.insert(*description, sampler.clone());
+
+        Ok(sampler)
+    }
+
+    /// Number of distinct sampler objects created.
+    pub fn len(&self) -> usize {
+        self.samplers.len()
+    }
+
+    /// Bind sampler to texture unit.
+    ///
+    /// ## glBindSampler
+    ///
+    /// - OpenGL Version 3.3
+    /// - OpenGL ES Version 3.0
+    ///
+    pub fn bind(&mut self, unit: GLuint, sampler: &Sampler) -> Result<(), SamplerError> {
+        if let Err(err) = self.check_unit(unit) {
+            return Err(err);
+        }
+
+        if self.get_bound_id(unit) == Some(sampler.get_id()) {
+            trace!("[{}]: already bound to unit {}", sampler.get_id(), unit);
+            return Ok(());
+        }
+
+        debug!("[{}]: bind to unit {}", sampler.get_id(), unit);
+        unsafe { gl::BindSampler(unit, sampler.get_id()) };
+
+        self.units[unit as usize] = Some(sampler.clone());
+
+        Ok(())
+    }
+
+    /// Bind shared sampler with `description` to texture unit.
+    pub fn bind_description(&mut self, unit: GLuint, description: &SamplerDescription) -> Result<Sampler, SamplerError> {
+        let sampler = match self.get(description) {
+            Ok(sampler) => sampler,
+            Err(err) => return Err(err),
+        };
+        match self.bind(unit, &sampler) {
+            Ok(()) => Ok(sampler),
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Unbind sampler from texture unit, so texture parameters are used.
+    ///
+    /// ## glBindSampler(0)
+    ///
+    /// - OpenGL Version 3.3
+    /// - OpenGL ES Version 3.0
+    ///
+    pub fn unbind(&mut self, unit: GLuint) {
+        if self.get_bound_id(unit).is_none() {
+            return;
+        }
+
+        debug!("unbind sampler from unit {}", unit);
+        unsafe { gl::BindSampler(unit, 0) };
+
+        self.units[unit as usize] = None;
+    }
+
+    /// Get name of sampler bound to texture unit.
+    pub fn get_bound_id(&self, unit: GLuint) -> Option<GLuint> {
+        self.units.get(unit as usize)
+            .and_then(|sampler| sampler.as_ref())
+            .map(|sampler| sampler.get_id())
+    }
+
+    /// List all bindings as unit and sampler name.
+    pub fn bound_ids(&self) -> Vec<(GLuint, GLuint)> {
+        self.units.iter()
+            .enumerate()
+            .filter_map(|(unit, sampler)| sampler.as_ref().map(|sampler| (unit as GLuint, sampler.get_id())))
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SamplerError {
+    UnitOutOfRange { unit: GLuint, count: GLuint },
+    InvalidAnisotropy(f32),
+    AnisotropyExceeded { requested: f32, max: f32 },
+    InvalidLodRange { min: f32, max: f32 },
+    LodBiasUnsupported,
+    WrapUnsupported(Wrap),
+}
+
+impl fmt::Display for SamplerError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            &SamplerError::UnitOutOfRange { unit, count } => write!(f, "Tried to bind sampler to unit {}, but there are only {} units.", unit, count),
+            &SamplerError::InvalidAnisotropy(value) => write!(f, "Sampler anisotropy {} is less than 1.", value),
+            &SamplerError::AnisotropyExceeded { requested, max } => write!(f, "Sampler anisotropy {} exceeds maximum supported {}.", requested, max),
+            &SamplerError::InvalidLodRange { min, max } => write!(f, "Sampler minimum LOD {} is greater than maximum LOD {}.", min, max),
+            &SamplerError::LodBiasUnsupported => "Sampler LOD bias is not available on OpenGL ES.".fmt(f),
+            &SamplerError::WrapUnsupported(wrap) => write!(f, "Sampler wrap mode {:?} is not supported by current context.", wrap),
+        }
+    }
+}
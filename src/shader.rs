@@ -3,11 +3,136 @@ use gl::types::*;
 
 use std::ptr;
 use std::ffi::CString;
+use std::cell::Cell;
 
 use std::fmt;
 
+use version::{ Api, GLVersion, has_extension };
+use diagnostic::CompileLog;
+use preprocess::{ Preprocessed, LineMap };
+use spirv::{ self, SpecializationConstant };
+use glsl::GlslHeader;
+use parallel;
+
+thread_local! {
+    /// Context version and supported stages as bits of `ShaderKind::all` order,
+    /// cached for the context current on this thread.
+    static STAGE_SUPPORT: Cell<Option<(GLVersion, u8)>> = Cell::new(None)
+}
+
+/// Programmable pipeline stage a shader object belongs to.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum ShaderKind {
+    Vertex,
+    TessControl,
+    TessEvaluation,
+    Geometry,
+    Fragment,
+    Compute,
+}
+
+impl ShaderKind {
+    pub fn to_gl(&self) -> GLenum {
+        match *self {
+            ShaderKind::Vertex => gl::VERTEX_SHADER,
+            ShaderKind::TessControl => gl::TESS_CONTROL_SHADER,
+            ShaderKind::TessEvaluation => gl::TESS_EVALUATION_SHADER,
+            ShaderKind::Geometry => gl::GEOMETRY_SHADER,
+            ShaderKind::Fragment => gl::FRAGMENT_SHADER,
+            ShaderKind::Compute => gl::COMPUTE_SHADER,
+        }
+    }
+
+    pub fn from_gl(value: GLenum) -> Option<ShaderKind> {
+        match value {
+            gl::VERTEX_SHADER => Some(ShaderKind::Vertex),
+            gl::TESS_CONTROL_SHADER => Some(ShaderKind::TessControl),
+            gl::TESS_EVALUATION_SHADER => Some(ShaderKind::TessEvaluation),
+            gl::GEOMETRY_SHADER => Some(ShaderKind::Geometry),
+            gl::FRAGMENT_SHADER => Some(ShaderKind::Fragment),
+            gl::COMPUTE_SHADER => Some(ShaderKind::Compute),
+            _ => None,
+        }
+    }
+
+    pub fn all() -> [ShaderKind; 6] {
+        [
+            ShaderKind::Vertex, ShaderKind::TessControl, ShaderKind::TessEvaluation,
+            ShaderKind::Geometry, ShaderKind::Fragment, ShaderKind::Compute,
+        ]
+    }
+
+    /// Minimal context version that supports this stage for `api`.
+    pub fn min_version(&self, api: Api) -> (u32, u32) {
+        match (api, *self) {
+            (Api::Gl, ShaderKind::Vertex) | (Api::Gl, ShaderKind::Fragment) => (2, 0),
+            (Api::Gl, ShaderKind::Geometry) => (3, 2),
+            (Api::Gl, ShaderKind::TessControl) | (Api::Gl, ShaderKind::TessEvaluation) => (4, 0),
+            (Api::Gl, ShaderKind::Compute) => (4, 3),
+            (Api::GlEs, ShaderKind::Vertex) | (Api::GlEs, ShaderKind::Fragment) => (2, 0),
+            (Api::GlEs, ShaderKind::Compute) => (3, 1),
+            (Api::GlEs, ShaderKind::Geometry)
+            | (Api::GlEs, ShaderKind::TessControl)
+            | (Api::GlEs, ShaderKind::TessEvaluation) => (3, 2),
+        }
+    }
+
+    /// Extensions that add this stage to older contexts of `api`.
+    pub fn extensions(&self, api: Api) -> &'static [&'static str] {
+        match (api, *self) {
+            (Api::Gl, ShaderKind::TessControl) | (Api::Gl, ShaderKind::TessEvaluation) => &["GL_ARB_tessellation_shader"],
+            (Api::Gl, ShaderKind::Compute) => &["GL_ARB_compute_shader"],
+            (Api::GlEs, ShaderKind::Geometry) => &["GL_EXT_geometry_shader", "GL_OES_geometry_shader"],
+            (Api::GlEs, ShaderKind::TessControl)
+            | (Api::GlEs, ShaderKind::TessEvaluation) => &["GL_EXT_tessellation_shader", "GL_OES_tessellation_shader"],
+            _ => &[],
+        }
+    }
+
+    /// Returns true if context of `version` supports this stage.
+    ///
+    /// Below the minimal version, extensions are looked up in the current context.
+    pub fn is_supported_by(&self, version: &GLVersion) -> bool {
+        let (major, minor) = self.min_version(version.api);
+        version.is_at_least(version.api, major, minor)
+            || self.extensions(version.api).iter().any(|name| has_extension(name))
+    }
+}
+
+/// Get cached context version and supported stage bits, querying them on first use.
+fn stage_support() -> Option<(GLVersion, u8)> {
+    STAGE_SUPPORT.with(|cache| {
+        if let Some(support) = cache.get() {
+            return Some(support);
+        }
+
+        let version = match GLVersion::current() {
+            Some(version) => version,
+            None => return None,
+        };
+
+        let mut supported = 0u8;
+        for (index, kind) in ShaderKind::all().iter().enumerate() {
+            if kind.is_supported_by(&version) {
+                supported |= 1 << index;
+            }
+        }
+
+        debug!("shader stages of {}, supported = {:06b}", version, supported);
+
+        cache.set(Some((version, supported)));
+        Some((version, supported))
+    })
+}
+
+/// Forget cached shader stage support, for example after switching context.
+pub fn reset_stage_support() {
+    STAGE_SUPPORT.with(|cache| cache.set(None));
+}
+
 pub struct Shader {
     id: GLuint,
+    kind: ShaderKind,
 }
 
 impl Eq for Shader {}
@@ -20,26 +145,59 @@ impl PartialEq for Shader {
 
 impl Shader {
     #[inline]
-    fn internal_new(ty: GLenum) -> Shader {
-        Shader {
-            id: unsafe { gl::CreateShader(ty) }
+    fn internal_new(kind: ShaderKind) -> Result<Shader, ShaderError> {
+        if let Err(err) = Shader::check_supported(kind) {
+            return Err(err);
         }
+
+        Ok(Shader {
+            id: unsafe { gl::CreateShader(kind.to_gl()) },
+            kind: kind,
+        })
     }
 
-    pub fn new(ty: GLenum) -> Shader {
-        debug!("new, {}", ty);
+    /// Check that the current context supports shader stage.
+    ///
+    /// Version and extensions are queried once and cached, see `reset_stage_support`.
+    /// Passes if the context version can not be determined.
+    pub fn check_supported(kind: ShaderKind) -> Result<(), ShaderError> {
+        let (version, supported) = match stage_support() {
+            Some(support) => support,
+            None => {
+                warn!("can not check {:?} shader support, unknown context version", kind);
+                return Ok(());
+            },
+        };
+
+        let index = ShaderKind::all().iter().position(|&stage| stage == kind).unwrap_or(0);
+        if supported & (1 << index) != 0 {
+            Ok(())
+        } else {
+            error!("{:?} shader is not supported by {}", kind, version);
+            Err(ShaderError::UnsupportedKind(kind, version))
+        }
+    }
 
-        let shader = Shader::internal_new(ty);
+    pub fn new(kind: ShaderKind) -> Result<Shader, ShaderError> {
+        debug!("new, {:?}", kind);
+
+        let shader = match Shader::internal_new(kind) {
+            Ok(shader) => shader,
+            Err(err) => return Err(err),
+        };
 
         info!("[{}]: created new", shader.id);
 
-        shader
+        Ok(shader)
     }
 
-    pub fn compile_new(source: &str, ty: GLenum) -> Result<Shader, ShaderError> {
-        debug!("compile new, {}", ty);
+    pub fn compile_new(source: &str, kind: ShaderKind) -> Result<Shader, ShaderError> {
+        debug!("compile new, {:?}", kind);
 
-        let shader = Shader::internal_new(ty);
+        let shader = match Shader::internal_new(kind) {
+            Ok(shader) => shader,
+            Err(err) => return Err(err),
+        };
 
         shader.set_source(source);
 
@@ -52,20 +210,187 @@ impl Shader {
         Ok(shader)
     }
 
+    /// Issue compilation without waiting for it to finish.
+    ///
+    /// Falls back to synchronous compilation when `GL_KHR_parallel_shader_compile`
+    /// is missing, in which case returned handle is already complete.
+    pub fn compile_async(source: &str, kind: ShaderKind) -> Result<PendingShader, ShaderError> {
+        debug!("compile async, {:?}", kind);
+
+        let shader = match Shader::internal_new(kind) {
+            Ok(shader) => shader,
+            Err(err) => return Err(err),
+        };
+
+        shader.set_source(source);
+
+        if !parallel::is_supported() {
+            let result = shader.compile().map(|_| shader);
+            return Ok(PendingShader { state: PendingState::Done(result) });
+        }
+
+        unsafe { gl::CompileShader(shader.id) };
+
+        Ok(PendingShader { state: PendingState::Compiling(shader) })
+    }
+
+    /// Compile source with `#version` directive selected for the current context.
+    ///
+    /// Existing directive is rewritten, and missing one is prepended. Error
+    /// locations are reported as lines of `<source>`, excluding inserted lines.
+    /// If context version can not be determined, source is compiled unchanged.
+    pub fn compile_versioned(source: &str, kind: ShaderKind) -> Result<Shader, ShaderError> {
+        match GlslHeader::current() {
+            Some(header) => {
+                let (versioned, insertions) = header.apply(source);
+                let mut line_map = LineMap::identity("<source>", source);
+                for &(line, count) in insertions.iter().rev() {
+                    line_map.insert_lines(line, count);
+                }
+                match Shader::compile_new(&versioned, kind) {
+                    Err(ShaderError::CompileFailed(mut log)) => {
+                        log.map_lines(&line_map);
+                        Err(ShaderError::CompileFailed(log))
+                    },
+                    result => result,
+                }
+            },
+            None => {
+                warn!("can not select glsl version, unknown context version");
+                Shader::compile_new(source, kind)
+            },
+        }
+    }
+
+    /// Compile preprocessed source, mapping error locations back to original files.
+    pub fn compile_preprocessed(preprocessed: &Preprocessed, kind: ShaderKind) -> Result<Shader, ShaderError> {
+        match Shader::compile_new(&preprocessed.source, kind) {
+            Err(ShaderError::CompileFailed(mut log)) => {
+                log.map_lines(&preprocessed.line_map);
+                Err(ShaderError::CompileFailed(log))
+            },
+            result => result,
+        }
+    }
+
+    /// Load SPIR-V module and specialize it for `entry_point`.
+    ///
+    /// ## glShaderBinary(GL_SHADER_BINARY_FORMAT_SPIR_V), glSpecializeShader
+    ///
+    /// - OpenGL Version 4.6
+    ///
+    pub fn load_spirv(kind: ShaderKind, binary: &[u8], entry_point: &str, constants: &[SpecializationConstant]) -> Result<Shader, ShaderError> {
+        debug!("load spirv, {:?}, {} bytes", kind, binary.len());
+
+        if let Err(err) = spirv::check_supported() {
+            error!("load spirv error, {}", err);
+            return Err(ShaderError::SpirvUnsupported(err));
+        }
+
+        if let Err(err) = spirv::check_binary(binary) {
+            error!("load spirv error, {}", err);
+            return Err(ShaderError::InvalidBinary(err));
+        }
+
+        let shader = match Shader::internal_new(kind) {
+            Ok(shader) => shader,
+            Err(err) => return Err(err),
+        };
+
+        unsafe {
+            gl::ShaderBinary(
+                1, &shader.id, spirv::SHADER_BINARY_FORMAT_SPIR_V,
+                binary.as_ptr() as *const _, binary.len() as GLsizei
+            );
+        }
+
+        match unsafe { gl::GetError() } {
+            gl::NO_ERROR => (),
+            error => {
+                error!("[{}]: shader binary error, {}", shader.id, error);
+                return Err(ShaderError::InvalidBinary(format!("glShaderBinary failed with error {}.", error)));
+            },
+        }
+
+        if let Err(err) = spirv::specialize(shader.id, entry_point, constants) {
+            return Err(ShaderError::Other(err));
+        }
+
+        match shader.get_param::<bool>(gl::COMPILE_STATUS) {
+            Ok(true) => {
+                info!("[{}]: loaded spirv", shader.id);
+                Ok(shader)
+            },
+            Ok(false) => match shader.get_info_log() {
+                Ok(log) => {
+                    error!("[{}]: specialize error, {}", shader.id, log);
+                    Err(ShaderError::CompileFailed(CompileLog::parse(&log)))
+                },
+                Err(err) => Err(err),
+            },
+            Err(err) => Err(ShaderError::Other(err.to_string())),
+        }
+    }
+
     pub fn compile_vertex_shader(source: &str) -> Result<Shader, ShaderError>
     {
-        Shader::compile_new(source, gl::VERTEX_SHADER)
+        Shader::compile_new(source, ShaderKind::Vertex)
+    }
+
+    /// Compile tessellation control shader.
+    ///
+    /// - OpenGL Version 4.0
+    /// - OpenGL ES Version 3.2
+    ///
+    pub fn compile_tess_control_shader(source: &str) -> Result<Shader, ShaderError>
+    {
+        Shader::compile_new(source, ShaderKind::TessControl)
+    }
+
+    /// Compile tessellation evaluation shader.
+    ///
+    /// - OpenGL Version 4.0
+    /// - OpenGL ES Version 3.2
+    ///
+    pub fn compile_tess_evaluation_shader(source: &str) -> Result<Shader, ShaderError>
+    {
+        Shader::compile_new(source, ShaderKind::TessEvaluation)
+    }
+
+    /// Compile geometry shader.
+    ///
+    /// - OpenGL Version 3.2
+    /// - OpenGL ES Version 3.2
+    ///
+    pub fn compile_geometry_shader(source: &str) -> Result<Shader, ShaderError>
+    {
+        Shader::compile_new(source, ShaderKind::Geometry)
     }
 
     pub fn compile_fragment_shader(source: &str) -> Result<Shader, ShaderError>
     {
-        Shader::compile_new(source, gl::FRAGMENT_SHADER)
+        Shader::compile_new(source, ShaderKind::Fragment)
+    }
+
+    /// Compile compute shader.
+    ///
+    /// - OpenGL Version 4.3
+    /// - OpenGL ES Version 3.1
+    ///
+    pub fn compile_compute_shader(source: &str) -> Result<Shader, ShaderError>
+    {
+        Shader::compile_new(source, ShaderKind::Compute)
     }
 
     pub fn get_id(&self) -> GLuint {
         self.id
     }
 
+    /// Get pipeline stage of the shader.
+    pub fn get_kind(&self) -> ShaderKind {
+        self.kind
+    }
+
     pub fn set_source(&self, source: &str) {
         debug!("[{}]: set source", self.id);
         trace!("[{}]: source\n{}", self.id, source);
@@ -80,11 +405,44 @@ impl Shader {
         unsafe { gl::ShaderSource(self.id, 1, &c_str.as_ptr(), ptr::null()); }
     }
 
+    /// Set source from multiple strings that are concatenated by the compiler.
+    ///
+    /// ## glShaderSource
+    ///
+    /// - OpenGL Version 2.0
+    /// - OpenGL ES Version 2.0
+    ///
+    pub fn set_sources(&self, sources: &[&str]) {
+        debug!("[{}]: set sources, count = {}", self.id, sources.len());
+
+        let c_strs: Vec<CString> = sources.iter()
+            .map(|source| {
+                trace!("[{}]: source\n{}", self.id, source);
+                match CString::new(*source) {
+                    Ok(r) => r,
+                    Err(_) => {
+                        error!("[{}]: set sources error", self.id);
+                        panic!("invalid shader source");
+                    }
+                }
+            })
+            .collect();
+        let pointers: Vec<*const GLchar> = c_strs.iter()
+            .map(|c_str| c_str.as_ptr())
+            .collect();
+
+        unsafe { gl::ShaderSource(self.id, pointers.len() as GLsizei, pointers.as_ptr(), ptr::null()); }
+    }
+
     pub fn compile(&self) -> Result<(), ShaderError> {
         debug!("[{}]: compile", self.id);
 
         unsafe { gl::CompileShader(self.id) };
 
+        self.check_compile_status()
+    }
+
+    fn check_compile_status(&self) -> Result<(), ShaderError> {
         match self.get_param::<GLint>(gl::COMPILE_STATUS) {
             Ok(compile_status) => {
                 if gl::TRUE as GLint == compile_status {
@@ -96,7 +454,7 @@ impl Shader {
                         Ok(log) => {
                             error!("[{}]: compile error, {}", self.id, log);
 
-                            Err(ShaderError::CompileFailed(log))
+                            Err(ShaderError::CompileFailed(CompileLog::parse(&log)))
                         },
                         Err(err) => {
                             error!("[{}]: compile error, failed to get info log", self.id);
@@ -148,6 +506,17 @@ impl Shader {
         }
     }
 
+    /// Get concatenated source strings of the shader.
+    ///
+    /// ## glGetShaderSource
+    ///
+    /// - OpenGL Version 2.0
+    /// - OpenGL ES Version 2.0
+    ///
+    pub fn get_source(&self) -> Result<String, StringFromShaderError> {
+        self.get_param::<String>(gl::SHADER_SOURCE_LENGTH)
+    }
+
     pub fn get_param<T>(&self, pname: GLenum) -> Result<T, <T as ParamFromShader>::Err>
         where
             T : ParamFromShader
@@ -161,6 +530,71 @@ impl Shader {
     }
 }
 
+enum PendingState {
+    Compiling(Shader),
+    Done(Result<Shader, ShaderError>),
+}
+
+/// Shader compilation issued with `Shader::compile_async`.
+pub struct PendingShader {
+    state: PendingState,
+}
+
+impl PendingShader {
+
+    /// Returns true if compilation finished and `resolve` will not block.
+    ///
+    /// ## glGetShaderiv(GL_COMPLETION_STATUS_KHR)
+    ///
+    /// - GL_KHR_parallel_shader_compile
+    ///
+    pub fn is_ready(&self) -> Result<bool, BoolFromShaderError> {
+        match self.state {
+            PendingState::Done(_) => Ok(true),
+            PendingState::Compiling(ref shader) => {
+                shader.get_param::<bool>(parallel::COMPLETION_STATUS_KHR)
+            },
+        }
+    }
+
+    /// Get raw name of the shader, which can be attached before compilation finishes.
+    ///
+    /// Fails with the compile error if compilation already finished without success.
+    pub fn get_id(&self) -> Result<GLuint, ShaderError> {
+        match self.state {
+            PendingState::Compiling(ref shader) => Ok(shader.id),
+            PendingState::Done(Ok(ref shader)) => Ok(shader.id),
+            PendingState::Done(Err(ref err)) => Err(err.clone()),
+        }
+    }
+
+    /// Get compilation result, waiting for it if it is not ready.
+    pub fn resolve(self) -> Result<Shader, ShaderError> {
+        match self.state {
+            PendingState::Done(result) => result,
+            PendingState::Compiling(shader) => {
+                match shader.check_compile_status() {
+                    Ok(()) => {
+                        info!("[{}]: compiled async", shader.id);
+                        Ok(shader)
+                    },
+                    Err(err) => Err(err),
+                }
+            },
+        }
+    }
+
+    /// Get result if compilation finished, or give back the pending handle.
+    ///
+    /// Resolves if completion status can not be queried.
+    pub fn try_resolve(self) -> Result<Result<Shader, ShaderError>, PendingShader> {
+        match self.is_ready() {
+            Ok(false) => Err(self),
+            _ => Ok(self.resolve()),
+        }
+    }
+}
+
 impl Drop for Shader {
     fn drop(&mut self) {
         debug!("[{}]: cleanup", self.id);
@@ -195,8 +629,79 @@ impl ParamFromShader for GLint {
     }
 }
 
+impl ParamFromShader for bool {
+    type Err = BoolFromShaderError;
+
+    /// Gets boolean parameter such as `gl::COMPILE_STATUS` or `gl::DELETE_STATUS`.
+    fn param_from_shader(shader: &Shader, pname: GLenum) -> Result<bool, BoolFromShaderError> {
+        match <GLint as ParamFromShader>::param_from_shader(shader, pname) {
+            Ok(value) if value == gl::TRUE as GLint => Ok(true),
+            Ok(value) if value == gl::FALSE as GLint => Ok(false),
+            Ok(value) => Err(BoolFromShaderError::NotBool(value)),
+            Err(err) => Err(BoolFromShaderError::Query(err)),
+        }
+    }
+}
+
+impl ParamFromShader for ShaderKind {
+    type Err = ShaderKindFromShaderError;
+
+    /// Gets `gl::SHADER_TYPE` parameter.
+    fn param_from_shader(shader: &Shader, pname: GLenum) -> Result<ShaderKind, ShaderKindFromShaderError> {
+        match <GLint as ParamFromShader>::param_from_shader(shader, pname) {
+            Ok(value) => match ShaderKind::from_gl(value as GLenum) {
+                Some(kind) => Ok(kind),
+                None => Err(ShaderKindFromShaderError::Unrecognised(value)),
+            },
+            Err(err) => Err(ShaderKindFromShaderError::Query(err)),
+        }
+    }
+}
+
+impl ParamFromShader for String {
+    type Err = StringFromShaderError;
+
+    /// Gets string by its length parameter.
+    ///
+    /// Pass `gl::SHADER_SOURCE_LENGTH` for shader source and
+    /// `gl::INFO_LOG_LENGTH` for info log.
+    fn param_from_shader(shader: &Shader, pname: GLenum) -> Result<String, StringFromShaderError> {
+        if pname != gl::SHADER_SOURCE_LENGTH && pname != gl::INFO_LOG_LENGTH {
+            return Err(StringFromShaderError::UnsupportedParam(pname));
+        }
+
+        let len = match <GLint as ParamFromShader>::param_from_shader(shader, pname) {
+            Ok(len) => len,
+            Err(err) => return Err(StringFromShaderError::Query(err)),
+        };
+
+        if len <= 1 {
+            return Ok(String::new());
+        }
+
+        let mut buf: Vec<u8> = vec![0; len as usize];
+        let mut written: GLsizei = 0;
+        unsafe {
+            match pname {
+                gl::SHADER_SOURCE_LENGTH => gl::GetShaderSource(shader.id, len, &mut written, buf.as_mut_ptr() as *mut GLchar),
+                _ => gl::GetShaderInfoLog(shader.id, len, &mut written, buf.as_mut_ptr() as *mut GLchar),
+            }
+        }
+        buf.truncate(written as usize);
+
+        match String::from_utf8(buf) {
+            Ok(value) => Ok(value),
+            Err(_) => Err(StringFromShaderError::InvalidUtf8),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
 pub enum ShaderError {
-    CompileFailed(String),
+    CompileFailed(CompileLog),
+    UnsupportedKind(ShaderKind, GLVersion),
+    SpirvUnsupported(String),
+    InvalidBinary(String),
     Other(String),
 }
 
@@ -204,6 +709,9 @@ impl fmt::Display for ShaderError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
             &ShaderError::CompileFailed(ref log) => write!(f, "[compile failed]\n{}", log),
+            &ShaderError::SpirvUnsupported(ref err) => write!(f, "[spirv unsupported]\n{}", err),
+            &ShaderError::InvalidBinary(ref err) => write!(f, "[invalid binary]\n{}", err),
+            &ShaderError::UnsupportedKind(kind, version) => write!(f, "[unsupported kind]\n{:?} shader is not supported by {}", kind, version),
             &ShaderError::Other(ref err) => write!(f, "[other error]\n{}", err),
         }
     }
@@ -224,3 +732,50 @@ impl fmt::Display for GLintFromShaderError {
         }
     }
 }
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum BoolFromShaderError {
+    Query(GLintFromShaderError),
+    NotBool(GLint),
+}
+
+impl fmt::Display for BoolFromShaderError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            &BoolFromShaderError::Query(ref err) => err.fmt(f),
+            &BoolFromShaderError::NotBool(value) => write!(f, "Shader parameter value {} is not a boolean.", value),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ShaderKindFromShaderError {
+    Query(GLintFromShaderError),
+    Unrecognised(GLint),
+}
+
+impl fmt::Display for ShaderKindFromShaderError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            &ShaderKindFromShaderError::Query(ref err) => err.fmt(f),
+            &ShaderKindFromShaderError::Unrecognised(value) => write!(f, "Shader parameter value {} is not a recognised shader type.", value),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum StringFromShaderError {
+    Query(GLintFromShaderError),
+    UnsupportedParam(GLenum),
+    InvalidUtf8,
+}
+
+impl fmt::Display for StringFromShaderError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            &StringFromShaderError::Query(ref err) => err.fmt(f),
+            &StringFromShaderError::UnsupportedParam(pname) => write!(f, "Shader parameter {} can not be retrieved as string.", pname),
+            &StringFromShaderError::InvalidUtf8 => "Shader string parameter is not valid utf8.".fmt(f),
+        }
+    }
+}
@@ -0,0 +1,141 @@
+//! SPIR-V shader support.
+//!
+//! `glSpecializeShader` is not part of the generated GL bindings, so it has
+//! to be loaded with `load_with` using the same loader passed to `gl::load_with`.
+
+use gl::types::*;
+
+use std::ffi::CString;
+use std::mem;
+use std::os::raw::c_void;
+use std::sync::atomic::{ AtomicUsize, Ordering, ATOMIC_USIZE_INIT };
+
+use version::{ self, Api, GLVersion };
+
+pub const SHADER_BINARY_FORMAT_SPIR_V: GLenum = 0x9551;
+pub const SPIR_V_BINARY: GLenum = 0x9552;
+
+/// Magic number at the start of every SPIR-V module.
+pub const SPIRV_MAGIC: u32 = 0x07230203;
+
+type SpecializeShaderFn = extern "system" fn(GLuint, *const GLchar, GLuint, *const GLuint, *const GLuint);
+
+static SPECIALIZE_SHADER: AtomicUsize = ATOMIC_USIZE_INIT;
+
+/// Load `glSpecializeShader` (or `glSpecializeShaderARB`) entry point.
+pub fn load_with<F>(mut loadfn: F) where F: FnMut(&str) -> *const c_void {
+    let mut ptr = loadfn("glSpecializeShader");
+    if ptr.is_null() {
+        ptr = loadfn("glSpecializeShaderARB");
+    }
+    debug!("load glSpecializeShader, found = {}", !ptr.is_null());
+    SPECIALIZE_SHADER.store(ptr as usize, Ordering::SeqCst);
+}
+
+/// Returns true if `glSpecializeShader` entry point was loaded.
+pub fn is_loaded() -> bool {
+    SPECIALIZE_SHADER.load(Ordering::SeqCst) != 0
+}
+
+/// Check that the current context can load SPIR-V shaders.
+///
+/// Requires OpenGL 4.6 or `GL_ARB_gl_spirv`, and a loaded `glSpecializeShader`.
+pub fn check_supported() -> Result<(), String> {
+    let version = GLVersion::current();
+    let core = version.map(|version| version.is_at_least(Api::Gl, 4, 6)).unwrap_or(false);
+
+    if !core && !version::has_extension("GL_ARB_gl_spirv") {
+        return Err(match version {
+            Some(version) => format!("SPIR-V shaders require OpenGL 4.6 or GL_ARB_gl_spirv, but context is {} without the extension.", version),
+            None => "SPIR-V shaders require OpenGL 4.6 or GL_ARB_gl_spirv, but context version is unknown.".to_string(),
+        });
+    }
+
+    if !is_loaded() {
+        return Err("SPIR-V shaders require glSpecializeShader, load it with kugel::spirv::load_with.".to_string());
+    }
+
+    Ok(())
+}
+
+/// Check that `binary` looks like a SPIR-V module.
+pub fn check_binary(binary: &[u8]) -> Result<(), String> {
+    if binary.len() < 20 || binary.len() % 4 != 0 {
+        return Err(format!("SPIR-V binary size {} is not a multiple of 4 of at least 20 bytes.", binary.len()));
+    }
+
+    let magic = binary[0] as u32
+        | (binary[1] as u32) << 8
+        | (binary[2] as u32) << 16
+        | (binary[3] as u32) << 24;
+
+    if magic != SPIRV_MAGIC {
+        return Err(format!("SPIR-V binary has invalid magic number {:08x}.", magic));
+    }
+
+    Ok(())
+}
+
+/// Typed value of a specialization constant.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum SpecializationValue {
+    Bool(bool),
+    Int(i32),
+    UInt(u32),
+    Float(f32),
+}
+
+impl SpecializationValue {
+    /// Value as 32-bit word passed to `glSpecializeShader`.
+    pub fn to_bits(&self) -> GLuint {
+        match *self {
+            SpecializationValue::Bool(value) => if value { 1 } else { 0 },
+            SpecializationValue::Int(value) => value as GLuint,
+            SpecializationValue::UInt(value) => value,
+            SpecializationValue::Float(value) => value.to_bits(),
+        }
+    }
+}
+
+/// Specialization constant `constant_id` with its value.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct SpecializationConstant {
+    pub id: GLuint,
+    pub value: SpecializationValue,
+}
+
+impl SpecializationConstant {
+    pub fn new(id: GLuint, value: SpecializationValue) -> SpecializationConstant {
+        SpecializationConstant { id: id, value: value }
+    }
+}
+
+/// Specialize shader `id` that has SPIR-V binary attached.
+///
+/// ## glSpecializeShader
+///
+/// - OpenGL Version 4.6
+///
+pub fn specialize(id: GLuint, entry_point: &str, constants: &[SpecializationConstant]) -> Result<(), String> {
+    let ptr = SPECIALIZE_SHADER.load(Ordering::SeqCst);
+    if ptr == 0 {
+        return Err("glSpecializeShader is not loaded.".to_string());
+    }
+
+    let centry = match CString::new(entry_point) {
+        Ok(centry) => centry,
+        Err(_) => return Err(format!("Invalid entry point name {}.", entry_point)),
+    };
+
+    let indices: Vec<GLuint> = constants.iter().map(|c| c.id).collect();
+    let values: Vec<GLuint> = constants.iter().map(|c| c.value.to_bits()).collect();
+
+    debug!("[{}]: specialize, entry point = {}, constants = {:?}", id, entry_point, constants);
+
+    unsafe {
+        let specialize_shader: SpecializeShaderFn = mem::transmute(ptr);
+        specialize_shader(id, centry.as_ptr(), constants.len() as GLuint, indices.as_ptr(), values.as_ptr());
+    }
+
+    Ok(())
+}
@@ -16,6 +16,10 @@ macro_rules! impl_bindable_buffer_target {
                 Ok(self)
             }
 
+            pub fn get_bound_id(&self) -> Option<GLuint> {
+                self.buffer.as_ref().map(|buffer| buffer.get_id())
+            }
+
             pub fn unbind(&mut self) {
                 if let Some(ref buffer) = self.buffer {
                     debug!("[{}]: unbind {}", buffer.get_id(), stringify!($Type));
@@ -36,6 +40,7 @@ macro_rules! impl_bindable_buffer_target {
 
 impl_bindable_buffer_target!(ArrayBufferTarget, gl::ARRAY_BUFFER);
 impl_bindable_buffer_target!(ElementArrayBufferTarget, gl::ELEMENT_ARRAY_BUFFER);
+impl_bindable_buffer_target!(DispatchIndirectBufferTarget, gl::DISPATCH_INDIRECT_BUFFER);
 
 pub struct ArrayBufferTarget {
     buffer: Option<Rc<Buffer>>,
@@ -68,11 +73,22 @@ impl ElementArrayBufferTarget {
     }
 }
 
+pub struct DispatchIndirectBufferTarget {
+    buffer: Option<Rc<Buffer>>,
+}
+
+impl DispatchIndirectBufferTarget {
+    pub fn new() -> DispatchIndirectBufferTarget {
+        DispatchIndirectBufferTarget { buffer: None }
+    }
+}
+
 pub struct BindBufferError;
 
 pub struct BufferTargets {
     pub array:          ArrayBufferTarget,
     pub element_array:  ElementArrayBufferTarget,
+    pub dispatch_indirect: DispatchIndirectBufferTarget,
 }
 
 impl BufferTargets {
@@ -80,6 +96,7 @@ impl BufferTargets {
         BufferTargets {
             array:          ArrayBufferTarget::new(),
             element_array:  ElementArrayBufferTarget::new(),
+            dispatch_indirect: DispatchIndirectBufferTarget::new(),
         }
     }
 
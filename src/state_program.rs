@@ -1,7 +1,8 @@
 use gl;
 use gl::types::*;
 
-use program::Program;
+use program::{ Program, SubroutineError };
+use shader::ShaderKind;
 
 pub struct StateProgram {
     id: Option<GLuint>,
@@ -14,6 +15,11 @@ impl StateProgram {
         }
     }
 
+    /// Get name of the program in use.
+    pub fn get_id(&self) -> Option<GLuint> {
+        self.id
+    }
+
     pub fn with_use(&mut self, program: &mut Program) -> &mut StateProgram {
         let previous_id = self.id;
         let new_id = program.get_id();
@@ -25,6 +31,7 @@ impl StateProgram {
 
                     self.id = Some(new_id);
                     unsafe { gl::UseProgram(new_id) };
+                    apply_subroutines(program);
                 }
             },
             None => {
@@ -32,12 +39,43 @@ impl StateProgram {
 
                 self.id = Some(new_id);
                 unsafe { gl::UseProgram(new_id) };
+                apply_subroutines(program);
             }
         }
 
         self
     }
 
+    /// Select subroutines for subroutine uniforms of program stage by name.
+    ///
+    /// Selections are stored on the program, see `Program::select_subroutines`.
+    /// The full index array is written immediately if program is in use, and
+    /// again every time the program is put in use, because GL resets subroutine
+    /// state on program change.
+    ///
+    /// ## glUniformSubroutinesuiv
+    ///
+    /// - OpenGL Version 4.0
+    ///
+    pub fn set_subroutines(&mut self, program: &mut Program, stage: ShaderKind, selections: &[(&str, &str)]) -> Result<(), SubroutineError> {
+        debug!("[{}]: set subroutines, {:?}", program.get_id(), stage);
+
+        if let Err(err) = program.select_subroutines(stage, selections) {
+            return Err(err);
+        }
+
+        if self.id == Some(program.get_id()) {
+            apply_stage_subroutines(program, stage);
+        }
+
+        Ok(())
+    }
+
+    /// Forget subroutine selections of the program.
+    pub fn clear_subroutines(&mut self, program: &mut Program) {
+        program.clear_subroutines();
+    }
+
     pub fn done(&mut self) {
         if let Some(used_id) = self.id {
             debug!("[{}]: unuse", used_id);
@@ -48,6 +86,19 @@ impl StateProgram {
     }
 }
 
+fn apply_subroutines(program: &Program) {
+    for stage in program.get_subroutine_stages() {
+        apply_stage_subroutines(program, stage);
+    }
+}
+
+fn apply_stage_subroutines(program: &Program, stage: ShaderKind) {
+    if let Some(indices) = program.get_subroutines(stage) {
+        trace!("[{}]: apply subroutines, {:?}", program.get_id(), stage);
+        unsafe { gl::UniformSubroutinesuiv(stage.to_gl(), indices.len() as GLsizei, indices.as_ptr()) };
+    }
+}
+
 impl Drop for StateProgram {
     fn drop(&mut self) {
         self.done();
@@ -0,0 +1,217 @@
+use gl::types::*;
+
+use super::storage::Size;
+
+pub const COMPRESSED_RGB_S3TC_DXT1_EXT: GLenum = 0x83F0;
+pub const COMPRESSED_RGBA_S3TC_DXT1_EXT: GLenum = 0x83F1;
+pub const COMPRESSED_RGBA_S3TC_DXT3_EXT: GLenum = 0x83F2;
+pub const COMPRESSED_RGBA_S3TC_DXT5_EXT: GLenum = 0x83F3;
+pub const COMPRESSED_SRGB_S3TC_DXT1_EXT: GLenum = 0x8C4C;
+pub const COMPRESSED_SRGB_ALPHA_S3TC_DXT1_EXT: GLenum = 0x8C4D;
+pub const COMPRESSED_SRGB_ALPHA_S3TC_DXT3_EXT: GLenum = 0x8C4E;
+pub const COMPRESSED_SRGB_ALPHA_S3TC_DXT5_EXT: GLenum = 0x8C4F;
+pub const COMPRESSED_RED_RGTC1: GLenum = 0x8DBB;
+pub const COMPRESSED_SIGNED_RED_RGTC1: GLenum = 0x8DBC;
+pub const COMPRESSED_RG_RGTC2: GLenum = 0x8DBD;
+pub const COMPRESSED_SIGNED_RG_RGTC2: GLenum = 0x8DBE;
+pub const COMPRESSED_RGBA_BPTC_UNORM: GLenum = 0x8E8C;
+pub const COMPRESSED_SRGB_ALPHA_BPTC_UNORM: GLenum = 0x8E8D;
+pub const COMPRESSED_RGB_BPTC_SIGNED_FLOAT: GLenum = 0x8E8E;
+pub const COMPRESSED_RGB_BPTC_UNSIGNED_FLOAT: GLenum = 0x8E8F;
+pub const COMPRESSED_R11_EAC: GLenum = 0x9270;
+pub const COMPRESSED_SIGNED_R11_EAC: GLenum = 0x9271;
+pub const COMPRESSED_RG11_EAC: GLenum = 0x9272;
+pub const COMPRESSED_SIGNED_RG11_EAC: GLenum = 0x9273;
+pub const COMPRESSED_RGB8_ETC2: GLenum = 0x9274;
+pub const COMPRESSED_SRGB8_ETC2: GLenum = 0x9275;
+pub const COMPRESSED_RGB8_PUNCHTHROUGH_ALPHA1_ETC2: GLenum = 0x9276;
+pub const COMPRESSED_SRGB8_PUNCHTHROUGH_ALPHA1_ETC2: GLenum = 0x9277;
+pub const COMPRESSED_RGBA8_ETC2_EAC: GLenum = 0x9278;
+pub const COMPRESSED_SRGB8_ALPHA8_ETC2_EAC: GLenum = 0x9279;
+/// First of `GL_COMPRESSED_RGBA_ASTC_*_KHR` formats, for 4x4 blocks.
+pub const COMPRESSED_RGBA_ASTC_4X4_KHR: GLenum = 0x93B0;
+/// First of `GL_COMPRESSED_SRGB8_ALPHA8_ASTC_*_KHR` formats, for 4x4 blocks.
+pub const COMPRESSED_SRGB8_ALPHA8_ASTC_4X4_KHR: GLenum = 0x93D0;
+
+/// ASTC block footprints, in the order of their format enums.
+static ASTC_BLOCKS: [(u8, u8); 14] = [
+    (4, 4), (5, 4), (5, 5), (6, 5), (6, 6), (8, 5), (8, 6),
+    (8, 8), (10, 5), (10, 6), (10, 8), (10, 10), (12, 10), (12, 12),
+];
+
+/// Block-compressed internal format.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum CompressedFormat {
+    /// S3TC DXT1, optionally with 1-bit alpha.
+    Bc1 { alpha: bool, srgb: bool },
+    /// S3TC DXT3.
+    Bc2 { srgb: bool },
+    /// S3TC DXT5.
+    Bc3 { srgb: bool },
+    /// RGTC1, single channel.
+    Bc4 { signed: bool },
+    /// RGTC2, two channels.
+    Bc5 { signed: bool },
+    /// BPTC float RGB.
+    Bc6h { signed: bool },
+    /// BPTC RGBA.
+    Bc7 { srgb: bool },
+    Etc2Rgb8 { srgb: bool },
+    Etc2Rgb8A1 { srgb: bool },
+    Etc2Rgba8 { srgb: bool },
+    EacR11 { signed: bool },
+    EacRg11 { signed: bool },
+    /// ASTC LDR with `width` x `height` blocks.
+    Astc { width: u8, height: u8, srgb: bool },
+}
+
+impl CompressedFormat {
+    pub fn to_gl(&self) -> GLenum {
+        match *self {
+            CompressedFormat::Bc1 { alpha: false, srgb: false } => COMPRESSED_RGB_S3TC_DXT1_EXT,
+            CompressedFormat::Bc1 { alpha: true, srgb: false } => COMPRESSED_RGBA_S3TC_DXT1_EXT,
+            CompressedFormat::Bc1 { alpha: false, srgb: true } => COMPRESSED_SRGB_S3TC_DXT1_EXT,
+            CompressedFormat::Bc1 { alpha: true, srgb: true } => COMPRESSED_SRGB_ALPHA_S3TC_DXT1_EXT,
+            CompressedFormat::Bc2 { srgb } => if srgb { COMPRESSED_SRGB_ALPHA_S3TC_DXT3_EXT } else { COMPRESSED_RGBA_S3TC_DXT3_EXT },
+            CompressedFormat::Bc3 { srgb } => if srgb { COMPRESSED_SRGB_ALPHA_S3TC_DXT5_EXT } else { COMPRESSED_RGBA_S3TC_DXT5_EXT },
+            CompressedFormat::Bc4 { signed } => if signed { COMPRESSED_SIGNED_RED_RGTC1 } else { COMPRESSED_RED_RGTC1 },
+            CompressedFormat::Bc5 { signed } => if signed { COMPRESSED_SIGNED_RG_RGTC2 } else { COMPRESSED_RG_RGTC2 },
+            CompressedFormat::Bc6h { signed } => if signed { COMPRESSED_RGB_BPTC_SIGNED_FLOAT } else { COMPRESSED_RGB_BPTC_UNSIGNED_FLOAT },
+            CompressedFormat::Bc7 { srgb } => if srgb { COMPRESSED_SRGB_ALPHA_BPTC_UNORM } else { COMPRESSED_RGBA_BPTC_UNORM },
+            CompressedFormat::Etc2Rgb8 { srgb } => if srgb { COMPRESSED_SRGB8_ETC2 } else { COMPRESSED_RGB8_ETC2 },
+            CompressedFormat::Etc2Rgb8A1 { srgb } => if srgb { COMPRESSED_SRGB8_PUNCHTHROUGH_ALPHA1_ETC2 } else { COMPRESSED_RGB8_PUNCHTHROUGH_ALPHA1_ETC2 },
+            CompressedFormat::Etc2Rgba8 { srgb } => if srgb { COMPRESSED_SRGB8_ALPHA8_ETC2_EAC } else { COMPRESSED_RGBA8_ETC2_EAC },
+            CompressedFormat::EacR11 { signed } => if signed { COMPRESSED_SIGNED_R11_EAC } else { COMPRESSED_R11_EAC },
+            CompressedFormat::EacRg11 { signed } => if signed { COMPRESSED_SIGNED_RG11_EAC } else { COMPRESSED_RG11_EAC },
+            CompressedFormat::Astc { width, height, srgb } => {
+                match ASTC_BLOCKS.iter().position(|&block| block == (width, height)) {
+                    Some(index) if srgb => COMPRESSED_SRGB8_ALPHA8_ASTC_4X4_KHR + index as GLenum,
+                    Some(index) => COMPRESSED_RGBA_ASTC_4X4_KHR + index as GLenum,
+                    None => 0,
+                }
+            },
+        }
+    }
+
+    pub fn from_gl(value: GLenum) -> Option<CompressedFormat> {
+        let format = match value {
+            COMPRESSED_RGB_S3TC_DXT1_EXT => CompressedFormat::Bc1 { alpha: false, srgb: false },
+            COMPRESSED_RGBA_S3TC_DXT1_EXT => CompressedFormat::Bc1 { alpha: true, srgb: false },
+            COMPRESSED_SRGB_S3TC_DXT1_EXT => CompressedFormat::Bc1 { alpha: false, srgb: true },
+            COMPRESSED_SRGB_ALPHA_S3TC_DXT1_EXT => CompressedFormat::Bc1 { alpha: true, srgb: true },
+            COMPRESSED_RGBA_S3TC_DXT3_EXT => CompressedFormat::Bc2 { srgb: false },
+            COMPRESSED_SRGB_ALPHA_S3TC_DXT3_EXT => CompressedFormat::Bc2 { srgb: true },
+            COMPRESSED_RGBA_S3TC_DXT5_EXT => CompressedFormat::Bc3 { srgb: false },
+            COMPRESSED_SRGB_ALPHA_S3TC_DXT5_EXT => CompressedFormat::Bc3 { srgb: true },
+            COMPRESSED_RED_RGTC1 => CompressedFormat::Bc4 { signed: false },
+            COMPRESSED_SIGNED_RED_RGTC1 => CompressedFormat::Bc4 { signed: true },
+            COMPRESSED_RG_RGTC2 => CompressedFormat::Bc5 { signed: false },
+            COMPRESSED_SIGNED_RG_RGTC2 => CompressedFormat::Bc5 { signed: true },
+            COMPRESSED_RGB_BPTC_UNSIGNED_FLOAT => CompressedFormat::Bc6h { signed: false },
+            COMPRESSED_RGB_BPTC_SIGNED_FLOAT => CompressedFormat::Bc6h { signed: true },
+            COMPRESSED_RGBA_BPTC_UNORM => CompressedFormat::Bc7 { srgb: false },
+            COMPRESSED_SRGB_ALPHA_BPTC_UNORM => CompressedFormat::Bc7 { srgb: true },
+            COMPRESSED_RGB8_ETC2 => CompressedFormat::Etc2Rgb8 { srgb: false },
+            COMPRESSED_SRGB8_ETC2 => CompressedFormat::Etc2Rgb8 { srgb: true },
+            COMPRESSED_RGB8_PUNCHTHROUGH_ALPHA1_ETC2 => CompressedFormat::Etc2Rgb8A1 { srgb: false },
+            COMPRESSED_SRGB8_PUNCHTHROUGH_ALPHA1_ETC2 => CompressedFormat::Etc2Rgb8A1 { srgb: true },
+            COMPRESSED_RGBA8_ETC2_EAC => CompressedFormat::Etc2Rgba8 { srgb: false },
+            COMPRESSED_SRGB8_ALPHA8_ETC2_EAC => CompressedFormat::Etc2Rgba8 { srgb: true },
+            COMPRESSED_R11_EAC => CompressedFormat::EacR11 { signed: false },
+            COMPRESSED_SIGNED_R11_EAC => CompressedFormat::EacR11 { signed: true },
+            COMPRESSED_RG11_EAC => CompressedFormat::EacRg11 { signed: false },
+            COMPRESSED_SIGNED_RG11_EAC => CompressedFormat::EacRg11 { signed: true },
+            value if value >= COMPRESSED_RGBA_ASTC_4X4_KHR && value < COMPRESSED_RGBA_ASTC_4X4_KHR + 14 => {
+                let (width, height) = ASTC_BLOCKS[(value - COMPRESSED_RGBA_ASTC_4X4_KHR) as usize];
+                CompressedFormat::Astc { width: width, height: height, srgb: false }
+            },
+            value if value >= COMPRESSED_SRGB8_ALPHA8_ASTC_4X4_KHR && value < COMPRESSED_SRGB8_ALPHA8_ASTC_4X4_KHR + 14 => {
+                let (width, height) = ASTC_BLOCKS[(value - COMPRESSED_SRGB8_ALPHA8_ASTC_4X4_KHR) as usize];
+                CompressedFormat::Astc { width: width, height: height, srgb: true }
+            },
+            _ => return None,
+        };
+        Some(format)
+    }
+
+    /// ASTC format with block footprint `width` x `height`, if such footprint exists.
+    pub fn astc(width: u8, height: u8, srgb: bool) -> Option<CompressedFormat> {
+        if ASTC_BLOCKS.contains(&(width, height)) {
+            Some(CompressedFormat::Astc { width: width, height: height, srgb: srgb })
+        } else {
+            None
+        }
+    }
+
+    /// Returns false for ASTC footprints that have no format, which map to `GL_NONE`.
+    pub fn is_valid(&self) -> bool {
+        match *self {
+            CompressedFormat::Astc { width, height, .. } => ASTC_BLOCKS.contains(&(width, height)),
+            _ => true,
+        }
+    }
+
+    /// Width and height of a block in texels.
+    pub fn block_size(&self) -> (u32, u32) {
+        match *self {
+            CompressedFormat::Astc { width, height, .. } => (width as u32, height as u32),
+            _ => (4, 4),
+        }
+    }
+
+    /// Size of a block in bytes.
+    pub fn block_bytes(&self) -> usize {
+        match *self {
+            CompressedFormat::Bc1 { .. }
+            | CompressedFormat::Bc4 { .. }
+            | CompressedFormat::Etc2Rgb8 { .. }
+            | CompressedFormat::Etc2Rgb8A1 { .. }
+            | CompressedFormat::EacR11 { .. } => 8,
+            _ => 16,
+        }
+    }
+
+    pub fn is_srgb(&self) -> bool {
+        match *self {
+            CompressedFormat::Bc1 { srgb, .. }
+            | CompressedFormat::Bc2 { srgb }
+            | CompressedFormat::Bc3 { srgb }
+            | CompressedFormat::Bc7 { srgb }
+            | CompressedFormat::Etc2Rgb8 { srgb }
+            | CompressedFormat::Etc2Rgb8A1 { srgb }
+            | CompressedFormat::Etc2Rgba8 { srgb }
+            | CompressedFormat::Astc { srgb, .. } => srgb,
+            _ => false,
+        }
+    }
+
+    /// Same format with sRGB encoding and signedness cleared.
+    ///
+    /// Formats with the same base format share block layout and can be
+    /// viewed as each other.
+    pub fn base_format(&self) -> CompressedFormat {
+        match *self {
+            CompressedFormat::Bc1 { alpha, .. } => CompressedFormat::Bc1 { alpha: alpha, srgb: false },
+            CompressedFormat::Bc2 { .. } => CompressedFormat::Bc2 { srgb: false },
+            CompressedFormat::Bc3 { .. } => CompressedFormat::Bc3 { srgb: false },
+            CompressedFormat::Bc4 { .. } => CompressedFormat::Bc4 { signed: false },
+            CompressedFormat::Bc5 { .. } => CompressedFormat::Bc5 { signed: false },
+            CompressedFormat::Bc6h { .. } => CompressedFormat::Bc6h { signed: false },
+            CompressedFormat::Bc7 { .. } => CompressedFormat::Bc7 { srgb: false },
+            CompressedFormat::Etc2Rgb8 { .. } => CompressedFormat::Etc2Rgb8 { srgb: false },
+            CompressedFormat::Etc2Rgb8A1 { .. } => CompressedFormat::Etc2Rgb8A1 { srgb: false },
+            CompressedFormat::Etc2Rgba8 { .. } => CompressedFormat::Etc2Rgba8 { srgb: false },
+            CompressedFormat::EacR11 { .. } => CompressedFormat::EacR11 { signed: false },
+            CompressedFormat::EacRg11 { .. } => CompressedFormat::EacRg11 { signed: false },
+            CompressedFormat::Astc { width, height, .. } => CompressedFormat::Astc { width: width, height: height, srgb: false },
+        }
+    }
+
+    /// Number of bytes of compressed data for image of `size`, with `depth` layers or slices.
+    pub fn image_size(&self, size: Size) -> usize {
+        let (block_width, block_height) = self.block_size();
+        let blocks_x = (size.width + block_width - 1) / block_width;
+        let blocks_y = (size.height + block_height - 1) / block_height;
+        blocks_x as usize * blocks_y as usize * size.depth as usize * self.block_bytes()
+    }
+}
@@ -0,0 +1,638 @@
+//! Parsers for KTX, KTX2 and DDS files with block-compressed images.
+//!
+//! Parsed mip levels store all layers, layer-faces or slices of the level one
+//! after another, in the order `TextureBinding::compressed_sub_image`
+//! expects for a whole level.
+
+use std::fmt;
+
+use super::TextureTarget;
+use super::compressed::{ CompressedFormat, COMPRESSED_RGBA_ASTC_4X4_KHR, COMPRESSED_SRGB8_ALPHA8_ASTC_4X4_KHR };
+use super::storage::Size;
+
+const KTX_IDENTIFIER: [u8; 12] = [0xAB, 0x4B, 0x54, 0x58, 0x20, 0x31, 0x31, 0xBB, 0x0D, 0x0A, 0x1A, 0x0A];
+const KTX2_IDENTIFIER: [u8; 12] = [0xAB, 0x4B, 0x54, 0x58, 0x20, 0x32, 0x30, 0xBB, 0x0D, 0x0A, 0x1A, 0x0A];
+const DDS_MAGIC: [u8; 4] = [b'D', b'D', b'S', b' '];
+
+const KTX_ENDIANNESS: u32 = 0x04030201;
+
+const DDS_PIXEL_FORMAT_ALPHA: u32 = 0x1;
+const DDS_PIXEL_FORMAT_FOURCC: u32 = 0x4;
+const DDS_CAPS2_CUBEMAP: u32 = 0x200;
+const DDS_CAPS2_VOLUME: u32 = 0x200000;
+const DDS_RESOURCE_MISC_TEXTURECUBE: u32 = 0x4;
+const DDS_DIMENSION_TEXTURE3D: u32 = 4;
+
+/// Largest accepted width, height, depth or layer count.
+const MAX_DIMENSION: u32 = 65536;
+
+/// Compressed mip chain read from a container file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CompressedData {
+    pub target: TextureTarget,
+    pub format: CompressedFormat,
+    /// Size of the base level, with layers or layer-faces in `depth`.
+    pub size: Size,
+    /// Data of each mip level, starting with the base level.
+    pub levels: Vec<Vec<u8>>,
+}
+
+impl CompressedData {
+    /// Size of mip `level`.
+    pub fn level_size(&self, level: u32) -> Size {
+        self.target.mip_size(self.size, level)
+    }
+}
+
+/// Parse KTX, KTX2 or DDS file contents, detecting container from its identifier.
+pub fn parse(data: &[u8]) -> Result<CompressedData, ContainerError> {
+    if data.starts_with(&KTX_IDENTIFIER) {
+        parse_ktx(data)
+    } else if data.starts_with(&KTX2_IDENTIFIER) {
+        parse_ktx2(data)
+    } else if data.starts_with(&DDS_MAGIC) {
+        parse_dds(data)
+    } else {
+        Err(ContainerError::UnknownContainer)
+    }
+}
+
+/// Parse KTX 1.1 file contents.
+pub fn parse_ktx(data: &[u8]) -> Result<CompressedData, ContainerError> {
+    if !data.starts_with(&KTX_IDENTIFIER) {
+        return Err(ContainerError::InvalidHeader("missing KTX identifier"));
+    }
+
+    let big_endian = match read_u32(data, 12, false) {
+        Ok(KTX_ENDIANNESS) => false,
+        Ok(value) if value.swap_bytes() == KTX_ENDIANNESS => true,
+        Ok(_) => return Err(ContainerError::InvalidHeader("invalid KTX endianness")),
+        Err(err) => return Err(err),
+    };
+
+    let mut header = [0u32; 13];
+    for (index, value) in header.iter_mut().enumerate() {
+        *value = match read_u32(data, 16 + index * 4, big_endian) {
+            Ok(value) => value,
+            Err(err) => return Err(err),
+        };
+    }
+    let gl_type = header[0];
+    let gl_internal_format = header[3];
+    let (width, height, depth) = (header[5], header[6], header[7]);
+    let (array_elements, faces, mip_levels, key_value_bytes) = (header[8], header[9], header[10], header[11]);
+
+    if gl_type != 0 {
+        return Err(ContainerError::NotCompressed);
+    }
+
+    let format = match CompressedFormat::from_gl(gl_internal_format) {
+        Some(format) => format,
+        None => return Err(ContainerError::UnsupportedFormat { container: "KTX", format: gl_internal_format }),
+    };
+
+    let (target, size) = match choose_target(width, height, depth, array_elements, faces) {
+        Ok(layout) => layout,
+        Err(err) => return Err(err),
+    };
+    let level_count = if mip_levels == 0 { 1 } else { mip_levels };
+    if let Err(err) = check_level_count(target, size, level_count) {
+        return Err(err);
+    }
+
+    debug!("parse ktx, format = {:?}, target = {:?}, size = {:?}, levels = {}", format, target, size, level_count);
+
+    // Non-array cube maps store size of a single face and then each face.
+    let face_count = if faces == 6 && array_elements == 0 { 6 } else { 1 };
+
+    let mut offset = 64 + key_value_bytes as usize;
+    let mut levels = Vec::with_capacity(level_count as usize);
+    for level in 0..level_count {
+        let image_size = match read_u32(data, offset, big_endian) {
+            Ok(image_size) => image_size as usize,
+            Err(err) => return Err(err),
+        };
+        offset += 4;
+
+        let mut level_data = Vec::new();
+        for _ in 0..face_count {
+            match read_bytes(data, offset, image_size) {
+                Ok(bytes) => level_data.extend(bytes.iter().cloned()),
+                Err(err) => return Err(err),
+            }
+            offset += pad4(image_size);
+        }
+
+        if let Err(err) = check_level_data(format, target, size, level, level_data.len()) {
+            return Err(err);
+        }
+        levels.push(level_data);
+    }
+
+    Ok(CompressedData { target: target, format: format, size: size, levels: levels })
+}
+
+/// Parse KTX 2.0 file contents.
+///
+/// Supercompressed files and files without a block-compressed `vkFormat`,
+/// such as Basis Universal, are rejected.
+pub fn parse_ktx2(data: &[u8]) -> Result<CompressedData, ContainerError> {
+    if !data.starts_with(&KTX2_IDENTIFIER) {
+        return Err(ContainerError::InvalidHeader("missing KTX2 identifier"));
+    }
+
+    let mut header = [0u32; 9];
+    for (index, value) in header.iter_mut().enumerate() {
+        *value = match read_u32(data, 12 + index * 4, false) {
+            Ok(value) => value,
+            Err(err) => return Err(err),
+        };
+    }
+    let vk_format = header[0];
+    let (width, height, depth) = (header[2], header[3], header[4]);
+    let (layers, faces, mip_levels, supercompression) = (header[5], header[6], header[7], header[8]);
+
+    if supercompression != 0 {
+        return Err(ContainerError::Supercompressed(supercompression));
+    }
+
+    let format = match format_from_vk(vk_format) {
+        Some(format) => format,
+        None => return Err(ContainerError::UnsupportedFormat { container: "KTX2", format: vk_format }),
+    };
+
+    let (target, size) = match choose_target(width, height, depth, layers, faces) {
+        Ok(layout) => layout,
+        Err(err) => return Err(err),
+    };
+    let level_count = if mip_levels == 0 { 1 } else { mip_levels };
+    if let Err(err) = check_level_count(target, size, level_count) {
+        return Err(err);
+    }
+
+    debug!("parse ktx2, format = {:?}, target = {:?}, size = {:?}, levels = {}", format, target, size, level_count);
+
+    // Level index follows the 48 byte header and 32 byte data format, key/value and global data index.
+    let mut levels = Vec::with_capacity(level_count as usize);
+    for level in 0..level_count {
+        let entry = 80 + level as usize * 24;
+        let (offset, length) = match (read_u64(data, entry), read_u64(data, entry + 8)) {
+            (Ok(offset), Ok(length)) => (offset as usize, length as usize),
+            (Err(err), _) | (_, Err(err)) => return Err(err),
+        };
+
+        let level_data = match read_bytes(data, offset, length) {
+            Ok(bytes) => bytes.to_vec(),
+            Err(err) => return Err(err),
+        };
+
+        if let Err(err) = check_level_data(format, target, size, level, level_data.len()) {
+            return Err(err);
+        }
+        levels.push(level_data);
+    }
+
+    Ok(CompressedData { target: target, format: format, size: size, levels: levels })
+}
+
+/// Parse DDS file contents, with legacy FourCC or `DX10` header.
+pub fn parse_dds(data: &[u8]) -> Result<CompressedData, ContainerError> {
+    if !data.starts_with(&DDS_MAGIC) {
+        return Err(ContainerError::InvalidHeader("missing DDS magic"));
+    }
+
+    let mut header = [0u32; 31];
+    for (index, value) in header.iter_mut().enumerate() {
+        *value = match read_u32(data, 4 + index * 4, false) {
+            Ok(value) => value,
+            Err(err) => return Err(err),
+        };
+    }
+    if header[0] != 124 || header[18] != 32 {
+        return Err(ContainerError::InvalidHeader("invalid DDS header size"));
+    }
+
+    let (height, width, depth, mip_levels) = (header[2], header[3], header[5], header[6]);
+    let (pixel_flags, four_cc, caps2) = (header[19], header[20], header[27]);
+
+    if pixel_flags & DDS_PIXEL_FORMAT_FOURCC == 0 {
+        return Err(ContainerError::NotCompressed);
+    }
+
+    let (format, layers, cube, volume, data_offset) = if four_cc == four_cc_value(b"DX10") {
+        let mut extension = [0u32; 4];
+        for (index, value) in extension.iter_mut().enumerate() {
+            *value = match read_u32(data, 128 + index * 4, false) {
+                Ok(value) => value,
+                Err(err) => return Err(err),
+            };
+        }
+        let (dxgi_format, dimension, misc_flags, array_size) = (extension[0], extension[1], extension[2], extension[3]);
+        let format = match format_from_dxgi(dxgi_format) {
+            Some(format) => format,
+            None => return Err(ContainerError::UnsupportedFormat { container: "DDS", format: dxgi_format }),
+        };
+        let layers = if array_size > 1 { array_size } else { 0 };
+        (format, layers, misc_flags & DDS_RESOURCE_MISC_TEXTURECUBE != 0, dimension == DDS_DIMENSION_TEXTURE3D, 148)
+    } else {
+        let format = match format_from_four_cc(four_cc, pixel_flags & DDS_PIXEL_FORMAT_ALPHA != 0) {
+            Some(format) => format,
+            None => return Err(ContainerError::UnsupportedFormat { container: "DDS", format: four_cc }),
+        };
+        (format, 0, caps2 & DDS_CAPS2_CUBEMAP != 0, caps2 & DDS_CAPS2_VOLUME != 0, 128)
+    };
+
+    let depth = if volume { depth } else { 0 };
+    let faces = if cube { 6 } else { 1 };
+    let (target, size) = match choose_target(width, height, depth, layers, faces) {
+        Ok(layout) => layout,
+        Err(err) => return Err(err),
+    };
+    let level_count = if mip_levels == 0 { 1 } else { mip_levels };
+    if let Err(err) = check_level_count(target, size, level_count) {
+        return Err(err);
+    }
+
+    debug!("parse dds, format = {:?}, target = {:?}, size = {:?}, levels = {}", format, target, size, level_count);
+
+    // DDS stores the whole mip chain of each layer-face before the next one.
+    let elements = if target.is_layered() { size.depth } else { 1 };
+    let mut levels: Vec<Vec<u8>> = (0..level_count).map(|_| Vec::new()).collect();
+    let mut offset = data_offset;
+    for _ in 0..elements {
+        for level in 0..level_count {
+            let level_size = target.mip_size(size, level);
+            let element_size = Size::new(level_size.width, level_size.height, level_size.depth / elements);
+            let len = format.image_size(element_size);
+            match read_bytes(data, offset, len) {
+                Ok(bytes) => levels[level as usize].extend(bytes.iter().cloned()),
+                Err(err) => return Err(err),
+            }
+            offset += len;
+        }
+    }
+
+    Ok(CompressedData { target: target, format: format, size: size, levels: levels })
+}
+
+/// Choose texture target and base size for container dimensions.
+///
+/// Zero `depth` or `layers` mean the dimension is not used, as in KTX headers.
+fn choose_target(width: u32, height: u32, depth: u32, layers: u32, faces: u32) -> Result<(TextureTarget, Size), ContainerError> {
+    if width == 0 || height == 0 {
+        return Err(ContainerError::InvalidHeader("compressed formats require 2D images"));
+    }
+    if width > MAX_DIMENSION || height > MAX_DIMENSION || depth > MAX_DIMENSION || layers > MAX_DIMENSION {
+        return Err(ContainerError::InvalidHeader("image dimensions are too large"));
+    }
+
+    match (faces, depth > 1, layers > 0) {
+        (6, false, true) => Ok((TextureTarget::CubeMapArray, Size::new(width, height, layers * 6))),
+        (6, false, false) => Ok((TextureTarget::CubeMap, Size::new(width, height, 6))),
+        (1, true, false) => Ok((TextureTarget::Texture3D, Size::new(width, height, depth))),
+        (1, false, true) => Ok((TextureTarget::Texture2DArray, Size::new(width, height, layers))),
+        (1, false, false) => Ok((TextureTarget::Texture2D, Size::new_2d(width, height))),
+        _ => Err(ContainerError::InvalidHeader("unsupported combination of depth, layers and faces")),
+    }
+}
+
+fn check_level_count(target: TextureTarget, size: Size, levels: u32) -> Result<(), ContainerError> {
+    if levels > target.mip_count(size) {
+        return Err(ContainerError::InvalidHeader("more mip levels than the size allows"));
+    }
+    Ok(())
+}
+
+fn check_level_data(format: CompressedFormat, target: TextureTarget, size: Size, level: u32, len: usize) -> Result<(), ContainerError> {
+    let expected = format.image_size(target.mip_size(size, level));
+    if len != expected {
+        return Err(ContainerError::LevelSize { level: level, expected: expected, actual: len });
+    }
+    Ok(())
+}
+
+fn format_from_vk(value: u32) -> Option<CompressedFormat> {
+    let format = match value {
+        131 => CompressedFormat::Bc1 { alpha: false, srgb: false },
+        132 => CompressedFormat::Bc1 { alpha: false, srgb: true },
+        133 => CompressedFormat::Bc1 { alpha: true, srgb: false },
+        134 => CompressedFormat::Bc1 { alpha: true, srgb: true },
+        135 => CompressedFormat::Bc2 { srgb: false },
+        136 => CompressedFormat::Bc2 { srgb: true },
+        137 => CompressedFormat::Bc3 { srgb: false },
+        138 => CompressedFormat::Bc3 { srgb: true },
+        139 => CompressedFormat::Bc4 { signed: false },
+        140 => CompressedFormat::Bc4 { signed: true },
+        141 => CompressedFormat::Bc5 { signed: false },
+        142 => CompressedFormat::Bc5 { signed: true },
+        143 => CompressedFormat::Bc6h { signed: false },
+        144 => CompressedFormat::Bc6h { signed: true },
+        145 => CompressedFormat::Bc7 { srgb: false },
+        146 => CompressedFormat::Bc7 { srgb: true },
+        147 => CompressedFormat::Etc2Rgb8 { srgb: false },
+        148 => CompressedFormat::Etc2Rgb8 { srgb: true },
+        149 => CompressedFormat::Etc2Rgb8A1 { srgb: false },
+        150 => CompressedFormat::Etc2Rgb8A1 { srgb: true },
+        151 => CompressedFormat::Etc2Rgba8 { srgb: false },
+        152 => CompressedFormat::Etc2Rgba8 { srgb: true },
+        153 => CompressedFormat::EacR11 { signed: false },
+        154 => CompressedFormat::EacR11 { signed: true },
+        155 => CompressedFormat::EacRg11 { signed: false },
+        156 => CompressedFormat::EacRg11 { signed: true },
+        // ASTC LDR formats alternate UNORM and SRGB, in the same block order as GL enums.
+        value if value >= 157 && value <= 184 => {
+            let first = if value % 2 == 1 { COMPRESSED_RGBA_ASTC_4X4_KHR } else { COMPRESSED_SRGB8_ALPHA8_ASTC_4X4_KHR };
+            return CompressedFormat::from_gl(first + (value - 157) / 2);
+        },
+        _ => return None,
+    };
+    Some(format)
+}
+
+fn format_from_dxgi(value: u32) -> Option<CompressedFormat> {
+    let format = match value {
+        70 | 71 => CompressedFormat::Bc1 { alpha: true, srgb: false },
+        72 => CompressedFormat::Bc1 { alpha: true, srgb: true },
+        73 | 74 => CompressedFormat::Bc2 { srgb: false },
+        75 => CompressedFormat::Bc2 { srgb: true },
+        76 | 77 => CompressedFormat::Bc3 { srgb: false },
+        78 => CompressedFormat::Bc3 { srgb: true },
+        79 | 80 => CompressedFormat::Bc4 { signed: false },
+        81 => CompressedFormat::Bc4 { signed: true },
+        82 | 83 => CompressedFormat::Bc5 { signed: false },
+        84 => CompressedFormat::Bc5 { signed: true },
+        94 | 95 => CompressedFormat::Bc6h { signed: false },
+        96 => CompressedFormat::Bc6h { signed: true },
+        97 | 98 => CompressedFormat::Bc7 { srgb: false },
+        99 => CompressedFormat::Bc7 { srgb: true },
+        _ => return None,
+    };
+    Some(format)
+}
+
+fn format_from_four_cc(value: u32, alpha: bool) -> Option<CompressedFormat> {
+    let format = match &to_four_cc(value) {
+        b"DXT1" => CompressedFormat::Bc1 { alpha: alpha, srgb: false },
+        b"DXT2" | b"DXT3" => CompressedFormat::Bc2 { srgb: false },
+        b"DXT4" | b"DXT5" => CompressedFormat::Bc3 { srgb: false },
+        b"ATI1" | b"BC4U" => CompressedFormat::Bc4 { signed: false },
+        b"BC4S" => CompressedFormat::Bc4 { signed: true },
+        b"ATI2" | b"BC5U" => CompressedFormat::Bc5 { signed: false },
+        b"BC5S" => CompressedFormat::Bc5 { signed: true },
+        _ => return None,
+    };
+    Some(format)
+}
+
+fn four_cc_value(code: &[u8; 4]) -> u32 {
+    code[0] as u32 | (code[1] as u32) << 8 | (code[2] as u32) << 16 | (code[3] as u32) << 24
+}
+
+fn to_four_cc(value: u32) -> [u8; 4] {
+    [value as u8, (value >> 8) as u8, (value >> 16) as u8, (value >> 24) as u8]
+}
+
+fn pad4(len: usize) -> usize {
+    (len + 3) / 4 * 4
+}
+
+fn read_bytes(data: &[u8], offset: usize, len: usize) -> Result<&[u8], ContainerError> {
+    match offset.checked_add(len) {
+        Some(end) if end <= data.len() => Ok(&data[offset..end]),
+        _ => Err(ContainerError::Truncated { expected: offset.saturating_add(len), actual: data.len() }),
+    }
+}
+
+fn read_u32(data: &[u8], offset: usize, big_endian: bool) -> Result<u32, ContainerError> {
+    read_bytes(data, offset, 4).map(|bytes| {
+        let value = bytes.iter().rev().fold(0u32, |value, &byte| value << 8 | byte as u32);
+        if big_endian { value.swap_bytes() } else { value }
+    })
+}
+
+fn read_u64(data: &[u8], offset: usize) -> Result<u64, ContainerError> {
+    read_bytes(data, offset, 8).map(|bytes| bytes.iter().rev().fold(0u64, |value, &byte| value << 8 | byte as u64))
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ContainerError {
+    UnknownContainer,
+    Truncated { expected: usize, actual: usize },
+    InvalidHeader(&'static str),
+    NotCompressed,
+    UnsupportedFormat { container: &'static str, format: u32 },
+    Supercompressed(u32),
+    LevelSize { level: u32, expected: usize, actual: usize },
+}
+
+impl fmt::Display for ContainerError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            &ContainerError::UnknownContainer => "Data is not a KTX, KTX2 or DDS file.".fmt(f),
+            &ContainerError::Truncated { expected, actual } => write!(f, "Container is truncated, needed {} bytes but has only {}.", expected, actual),
+            &ContainerError::InvalidHeader(reason) => write!(f, "Container header is invalid, {}.", reason),
+            &ContainerError::NotCompressed => "Container stores uncompressed images.".fmt(f),
+            &ContainerError::UnsupportedFormat { container, format } => write!(f, "{} format 0x{:X} is not a supported BC, ETC2/EAC or ASTC format.", container, format),
+            &ContainerError::Supercompressed(scheme) => write!(f, "KTX2 supercompression scheme {} is not supported.", scheme),
+            &ContainerError::LevelSize { level, expected, actual } => write!(f, "Mip level {} has {} bytes of data, expected {}.", level, actual, expected),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BC1: CompressedFormat = CompressedFormat::Bc1 { alpha: false, srgb: false };
+
+    fn push_u32(data: &mut Vec<u8>, value: u32, big_endian: bool) {
+        let value = if big_endian { value.swap_bytes() } else { value };
+        data.extend([value as u8, (value >> 8) as u8, (value >> 16) as u8, (value >> 24) as u8].iter().cloned());
+    }
+
+    fn push_u64(data: &mut Vec<u8>, value: u64) {
+        push_u32(data, value as u32, false);
+        push_u32(data, (value >> 32) as u32, false);
+    }
+
+    /// KTX file with `faces` copies of each level image.
+    fn ktx(gl_type: u32, width: u32, height: u32, faces: u32, levels: &[Vec<u8>], big_endian: bool) -> Vec<u8> {
+        let mut data = KTX_IDENTIFIER.to_vec();
+        let header = [KTX_ENDIANNESS, gl_type, 1, 0, BC1.to_gl(), 0, width, height, 0, 0, faces, levels.len() as u32, 4];
+        for &value in header.iter() {
+            push_u32(&mut data, value, big_endian);
+        }
+        push_u32(&mut data, 0, big_endian);
+        for level in levels.iter() {
+            push_u32(&mut data, level.len() as u32, big_endian);
+            for _ in 0..faces {
+                data.extend(level.iter().cloned());
+            }
+        }
+        data
+    }
+
+    fn ktx2(vk_format: u32, width: u32, height: u32, supercompression: u32, levels: &[Vec<u8>]) -> Vec<u8> {
+        let mut data = KTX2_IDENTIFIER.to_vec();
+        for &value in [vk_format, 1, width, height, 0, 0, 1, levels.len() as u32, supercompression].iter() {
+            push_u32(&mut data, value, false);
+        }
+        data.extend([0u8; 32].iter().cloned());
+        let mut offset = 80 + levels.len() * 24;
+        for level in levels.iter() {
+            push_u64(&mut data, offset as u64);
+            push_u64(&mut data, level.len() as u64);
+            push_u64(&mut data, level.len() as u64);
+            offset += level.len();
+        }
+        for level in levels.iter() {
+            data.extend(level.iter().cloned());
+        }
+        data
+    }
+
+    fn dds(width: u32, height: u32, mip_levels: u32, pixel_flags: u32, four_cc: &[u8; 4]) -> Vec<u8> {
+        let mut data = DDS_MAGIC.to_vec();
+        let mut header = [0u32; 31];
+        header[0] = 124;
+        header[2] = height;
+        header[3] = width;
+        header[6] = mip_levels;
+        header[18] = 32;
+        header[19] = pixel_flags;
+        header[20] = four_cc_value(four_cc);
+        for &value in header.iter() {
+            push_u32(&mut data, value, false);
+        }
+        data
+    }
+
+    fn bytes(len: usize, value: u8) -> Vec<u8> {
+        (0..len).map(|_| value).collect()
+    }
+
+    #[test]
+    fn ktx_mip_chain_is_parsed() {
+        let data = ktx(0, 8, 8, 1, &[bytes(32, 1), bytes(8, 2)], false);
+        let parsed = parse_ktx(&data).unwrap();
+        assert_eq!(parsed.target, TextureTarget::Texture2D);
+        assert_eq!(parsed.format, BC1);
+        assert_eq!(parsed.size, Size::new_2d(8, 8));
+        assert_eq!(parsed.levels, vec![bytes(32, 1), bytes(8, 2)]);
+        assert_eq!(parsed.level_size(1), Size::new_2d(4, 4));
+        assert_eq!(parse(&data), Ok(parsed));
+    }
+
+    #[test]
+    fn big_endian_ktx_is_parsed() {
+        let data = ktx(0, 4, 4, 1, &[bytes(8, 1)], true);
+        assert_eq!(parse_ktx(&data).map(|parsed| parsed.levels), Ok(vec![bytes(8, 1)]));
+    }
+
+    #[test]
+    fn ktx_cube_map_concatenates_faces() {
+        let data = ktx(0, 4, 4, 6, &[bytes(8, 3)], false);
+        let parsed = parse_ktx(&data).unwrap();
+        assert_eq!(parsed.target, TextureTarget::CubeMap);
+        assert_eq!(parsed.size, Size::new(4, 4, 6));
+        assert_eq!(parsed.levels, vec![bytes(48, 3)]);
+    }
+
+    #[test]
+    fn truncated_ktx_is_rejected() {
+        let data = ktx(0, 8, 8, 1, &[bytes(32, 1), bytes(8, 2)], false);
+        assert_eq!(parse_ktx(&data[..data.len() - 1]), Err(ContainerError::Truncated { expected: data.len(), actual: data.len() - 1 }));
+        assert_eq!(parse_ktx(&data[..40]), Err(ContainerError::Truncated { expected: 44, actual: 40 }));
+        assert_eq!(parse_ktx(&data[..8]), Err(ContainerError::InvalidHeader("missing KTX identifier")));
+    }
+
+    #[test]
+    fn malformed_ktx_is_rejected() {
+        let mut data = ktx(0, 4, 4, 1, &[bytes(8, 1)], false);
+        data[12] = 0;
+        assert_eq!(parse_ktx(&data), Err(ContainerError::InvalidHeader("invalid KTX endianness")));
+
+        assert_eq!(parse_ktx(&ktx(gl::UNSIGNED_BYTE, 4, 4, 1, &[bytes(8, 1)], false)), Err(ContainerError::NotCompressed));
+        assert_eq!(
+            parse_ktx(&ktx(0, 4, 4, 1, &[bytes(8, 1), bytes(8, 1), bytes(8, 1), bytes(8, 1)], false)),
+            Err(ContainerError::InvalidHeader("more mip levels than the size allows"))
+        );
+        assert_eq!(
+            parse_ktx(&ktx(0, 8, 8, 1, &[bytes(16, 1)], false)),
+            Err(ContainerError::LevelSize { level: 0, expected: 32, actual: 16 })
+        );
+        assert_eq!(parse_ktx(&ktx(0, 0, 4, 1, &[bytes(8, 1)], false)), Err(ContainerError::InvalidHeader("compressed formats require 2D images")));
+        assert_eq!(
+            parse_ktx(&ktx(0, 4, 4, 3, &[bytes(8, 1)], false)),
+            Err(ContainerError::InvalidHeader("unsupported combination of depth, layers and faces"))
+        );
+    }
+
+    #[test]
+    fn ktx2_mip_chain_is_parsed() {
+        let data = ktx2(131, 8, 8, 0, &[bytes(32, 1), bytes(8, 2)]);
+        let parsed = parse_ktx2(&data).unwrap();
+        assert_eq!(parsed.target, TextureTarget::Texture2D);
+        assert_eq!(parsed.format, BC1);
+        assert_eq!(parsed.levels, vec![bytes(32, 1), bytes(8, 2)]);
+        assert_eq!(parse(&data), Ok(parsed));
+    }
+
+    #[test]
+    fn malformed_ktx2_is_rejected() {
+        assert_eq!(parse_ktx2(&ktx2(131, 4, 4, 1, &[bytes(8, 1)])), Err(ContainerError::Supercompressed(1)));
+        assert_eq!(
+            parse_ktx2(&ktx2(37, 4, 4, 0, &[bytes(4, 1)])),
+            Err(ContainerError::UnsupportedFormat { container: "KTX2", format: 37 })
+        );
+        let data = ktx2(131, 4, 4, 0, &[bytes(8, 1)]);
+        assert_eq!(parse_ktx2(&data[..90]), Err(ContainerError::Truncated { expected: 96, actual: 90 }));
+        assert_eq!(parse_ktx2(&data[..data.len() - 2]), Err(ContainerError::Truncated { expected: data.len(), actual: data.len() - 2 }));
+    }
+
+    #[test]
+    fn dds_four_cc_mip_chain_is_parsed() {
+        let mut data = dds(8, 8, 2, DDS_PIXEL_FORMAT_FOURCC, b"DXT1");
+        data.extend(bytes(32, 1));
+        data.extend(bytes(8, 2));
+        let parsed = parse_dds(&data).unwrap();
+        assert_eq!(parsed.target, TextureTarget::Texture2D);
+        assert_eq!(parsed.format, BC1);
+        assert_eq!(parsed.levels, vec![bytes(32, 1), bytes(8, 2)]);
+        assert_eq!(parse(&data), Ok(parsed));
+    }
+
+    #[test]
+    fn dds_array_levels_collect_each_layer() {
+        let mut data = dds(8, 8, 2, DDS_PIXEL_FORMAT_FOURCC, b"DX10");
+        for &value in [71, 3, 0, 2, 0].iter() {
+            push_u32(&mut data, value, false);
+        }
+        for layer in 0..2 {
+            data.extend(bytes(32, layer));
+            data.extend(bytes(8, 10 + layer));
+        }
+        let parsed = parse_dds(&data).unwrap();
+        assert_eq!(parsed.target, TextureTarget::Texture2DArray);
+        assert_eq!(parsed.format, CompressedFormat::Bc1 { alpha: true, srgb: false });
+        assert_eq!(parsed.size, Size::new(8, 8, 2));
+        assert_eq!(parsed.levels[0], [bytes(32, 0), bytes(32, 1)].concat());
+        assert_eq!(parsed.levels[1], [bytes(8, 10), bytes(8, 11)].concat());
+    }
+
+    #[test]
+    fn malformed_dds_is_rejected() {
+        let mut data = dds(4, 4, 1, DDS_PIXEL_FORMAT_FOURCC, b"DXT1");
+        data[4] = 100;
+        assert_eq!(parse_dds(&data), Err(ContainerError::InvalidHeader("invalid DDS header size")));
+
+        assert_eq!(parse_dds(&dds(4, 4, 1, 0x40, b"\0\0\0\0")), Err(ContainerError::NotCompressed));
+        assert_eq!(
+            parse_dds(&dds(4, 4, 1, DDS_PIXEL_FORMAT_FOURCC, b"ABCD")),
+            Err(ContainerError::UnsupportedFormat { container: "DDS", format: four_cc_value(b"ABCD") })
+        );
+        assert_eq!(parse_dds(&dds(4, 4, 1, DDS_PIXEL_FORMAT_FOURCC, b"DXT1")), Err(ContainerError::Truncated { expected: 136, actual: 128 }));
+        assert_eq!(parse_dds(&data[..64]), Err(ContainerError::Truncated { expected: 68, actual: 64 }));
+        assert_eq!(parse(b"GIF89a"), Err(ContainerError::UnknownContainer));
+    }
+}
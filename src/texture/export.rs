@@ -0,0 +1,218 @@
+//! Image file encoders for texture readback.
+//!
+//! PNG output uses stored (uncompressed) deflate blocks, so no compression
+//! library is needed. Input rows are ordered top to bottom.
+
+use std::fmt;
+use std::io;
+use std::io::Write;
+
+use super::format::{ PixelFormat, PixelType };
+
+/// Write 8 or 16 bit gray, gray-alpha, RGB or RGBA pixels as PNG.
+pub fn write_png<W: Write>(writer: &mut W, width: u32, height: u32, format: PixelFormat, pixel_type: PixelType, data: &[u8]) -> Result<(), ExportError> {
+    let color_type: u8 = match format {
+        PixelFormat::Red => 0,
+        PixelFormat::RG => 4,
+        PixelFormat::RGB => 2,
+        PixelFormat::RGBA => 6,
+        _ => return Err(ExportError::Unsupported { format: format, pixel_type: pixel_type }),
+    };
+    let bit_depth: u8 = match pixel_type {
+        PixelType::UnsignedByte => 8,
+        PixelType::UnsignedShort => 16,
+        _ => return Err(ExportError::Unsupported { format: format, pixel_type: pixel_type }),
+    };
+
+    let row = width as usize * format.components() * pixel_type.size();
+
+    let mut header = Vec::with_capacity(13);
+    push_u32_be(&mut header, width);
+    push_u32_be(&mut header, height);
+    header.push(bit_depth);
+    header.push(color_type);
+    header.extend([0u8, 0, 0].iter().cloned());
+
+    // Each scanline starts with filter type 0 and stores samples big-endian.
+    let mut raw = Vec::with_capacity((row + 1) * height as usize);
+    for line in data.chunks(row).take(height as usize) {
+        raw.push(0);
+        if bit_depth == 16 && cfg!(target_endian = "little") {
+            for sample in line.chunks(2) {
+                raw.push(sample[1]);
+                raw.push(sample[0]);
+            }
+        } else {
+            raw.extend(line.iter().cloned());
+        }
+    }
+
+    let result = writer.write_all(b"\x89PNG\r\n\x1a\n")
+        .and_then(|_| write_chunk(writer, b"IHDR", &header))
+        .and_then(|_| write_chunk(writer, b"IDAT", &zlib_stored(&raw)))
+        .and_then(|_| write_chunk(writer, b"IEND", &[]));
+
+    match result {
+        Ok(()) => Ok(()),
+        Err(err) => Err(ExportError::Io(err)),
+    }
+}
+
+/// Write 8 or 16 bit gray (`P5`) or RGB (`P6`) pixels as binary PPM/PGM.
+///
+/// Alpha of RGBA pixels is dropped.
+pub fn write_ppm<W: Write>(writer: &mut W, width: u32, height: u32, format: PixelFormat, pixel_type: PixelType, data: &[u8]) -> Result<(), ExportError> {
+    let (magic, channels) = match format {
+        PixelFormat::Red => ("P5", 1),
+        PixelFormat::RGB => ("P6", 3),
+        PixelFormat::RGBA => ("P6", 3),
+        _ => return Err(ExportError::Unsupported { format: format, pixel_type: pixel_type }),
+    };
+    let max_value = match pixel_type {
+        PixelType::UnsignedByte => 255,
+        PixelType::UnsignedShort => 65535,
+        _ => return Err(ExportError::Unsupported { format: format, pixel_type: pixel_type }),
+    };
+
+    let sample = pixel_type.size();
+    let pixel = format.components() * sample;
+
+    let mut output = format!("{}\n{} {}\n{}\n", magic, width, height, max_value).into_bytes();
+    for source in data.chunks(pixel).take(width as usize * height as usize) {
+        for channel in 0..channels {
+            let value = &source[channel * sample..(channel + 1) * sample];
+            if sample == 2 && cfg!(target_endian = "little") {
+                output.push(value[1]);
+                output.push(value[0]);
+            } else {
+                output.extend(value.iter().cloned());
+            }
+        }
+    }
+
+    match writer.write_all(&output) {
+        Ok(()) => Ok(()),
+        Err(err) => Err(ExportError::Io(err)),
+    }
+}
+
+/// Write float gray (`Pf`) or RGB (`PF`) pixels as PFM.
+///
+/// PFM stores rows bottom to top in little-endian order. Alpha of RGBA
+/// pixels is dropped.
+pub fn write_pfm<W: Write>(writer: &mut W, width: u32, height: u32, format: PixelFormat, pixel_type: PixelType, data: &[u8]) -> Result<(), ExportError> {
+    let (magic, channels) = match (format, pixel_type) {
+        (PixelFormat::Red, PixelType::Float) => ("Pf", 1),
+        (PixelFormat::RGB, PixelType::Float) => ("PF", 3),
+        (PixelFormat::RGBA, PixelType::Float) => ("PF", 3),
+        _ => return Err(ExportError::Unsupported { format: format, pixel_type: pixel_type }),
+    };
+
+    let pixel = format.components() * 4;
+    let row = width as usize * pixel;
+
+    let mut output = format!("{}\n{} {}\n-1.0\n", magic, width, height).into_bytes();
+    for line in data.chunks(row).take(height as usize).collect::<Vec<_>>().into_iter().rev() {
+        for source in line.chunks(pixel) {
+            for channel in 0..channels {
+                let value = &source[channel * 4..(channel + 1) * 4];
+                if cfg!(target_endian = "little") {
+                    output.extend(value.iter().cloned());
+                } else {
+                    output.extend(value.iter().rev().cloned());
+                }
+            }
+        }
+    }
+
+    match writer.write_all(&output) {
+        Ok(()) => Ok(()),
+        Err(err) => Err(ExportError::Io(err)),
+    }
+}
+
+fn push_u32_be(output: &mut Vec<u8>, value: u32) {
+    output.push((value >> 24) as u8);
+    output.push((value >> 16) as u8);
+    output.push((value >> 8) as u8);
+    output.push(value as u8);
+}
+
+fn write_chunk<W: Write>(writer: &mut W, kind: &[u8], data: &[u8]) -> io::Result<()> {
+    let mut chunk = Vec::with_capacity(data.len() + 12);
+    push_u32_be(&mut chunk, data.len() as u32);
+    chunk.extend(kind.iter().cloned());
+    chunk.extend(data.iter().cloned());
+    let crc = crc32(&chunk[4..]);
+    push_u32_be(&mut chunk, crc);
+    writer.write_all(&chunk)
+}
+
+/// Wrap `data` in a zlib stream of stored deflate blocks.
+fn zlib_stored(data: &[u8]) -> Vec<u8> {
+    const MAX_BLOCK: usize = 65535;
+
+    let mut output = Vec::with_capacity(data.len() + data.len() / MAX_BLOCK * 5 + 11);
+    output.push(0x78);
+    output.push(0x01);
+
+    let mut blocks = data.chunks(MAX_BLOCK).peekable();
+    if blocks.peek().is_none() {
+        output.extend([1u8, 0, 0, 0xff, 0xff].iter().cloned());
+    }
+    while let Some(block) = blocks.next() {
+        let last = blocks.peek().is_none();
+        let len = block.len() as u16;
+        output.push(if last { 1 } else { 0 });
+        output.push(len as u8);
+        output.push((len >> 8) as u8);
+        output.push(!len as u8);
+        output.push((!len >> 8) as u8);
+        output.extend(block.iter().cloned());
+    }
+
+    push_u32_be(&mut output, adler32(data));
+    output
+}
+
+fn crc32(data: &[u8]) -> u32 {
+    let mut table = [0u32; 256];
+    for n in 0..256 {
+        let mut c = n as u32;
+        for _ in 0..8 {
+            c = if c & 1 != 0 { 0xedb88320 ^ (c >> 1) } else { c >> 1 };
+        }
+        table[n] = c;
+    }
+
+    let mut crc = 0xffffffffu32;
+    for &byte in data {
+        crc = table[((crc ^ byte as u32) & 0xff) as usize] ^ (crc >> 8);
+    }
+    crc ^ 0xffffffff
+}
+
+fn adler32(data: &[u8]) -> u32 {
+    let mut a = 1u32;
+    let mut b = 0u32;
+    for &byte in data {
+        a = (a + byte as u32) % 65521;
+        b = (b + a) % 65521;
+    }
+    (b << 16) | a
+}
+
+#[derive(Debug)]
+pub enum ExportError {
+    Unsupported { format: PixelFormat, pixel_type: PixelType },
+    Io(io::Error),
+}
+
+impl fmt::Display for ExportError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            &ExportError::Unsupported { format, pixel_type } => write!(f, "Pixels of {:?} format and {:?} type can not be written to this file type.", format, pixel_type),
+            &ExportError::Io(ref err) => err.fmt(f),
+        }
+    }
+}
@@ -0,0 +1,847 @@
+use gl;
+use gl::types::*;
+
+use std::fmt;
+
+use super::compressed::CompressedFormat;
+
+/// How texel values of an internal format are stored and read in shaders.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum FormatKind {
+    /// Unsigned normalized, read as float in `[0, 1]`.
+    UnsignedNormalized,
+    /// Signed normalized, read as float in `[-1, 1]`.
+    SignedNormalized,
+    Float,
+    UnsignedInteger,
+    SignedInteger,
+    Depth,
+    Stencil,
+    DepthStencil,
+}
+
+/// Sized internal format of texture storage.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum InternalFormat {
+    R8,
+    RG8,
+    RGB8,
+    RGBA8,
+    R8Snorm,
+    RG8Snorm,
+    RGB8Snorm,
+    RGBA8Snorm,
+    R16,
+    RG16,
+    RGB16,
+    RGBA16,
+    RGB10A2,
+    Srgb8,
+    Srgb8Alpha8,
+    R16F,
+    RG16F,
+    RGB16F,
+    RGBA16F,
+    R32F,
+    RG32F,
+    RGB32F,
+    RGBA32F,
+    R11FG11FB10F,
+    RGB9E5,
+    R8UI,
+    RG8UI,
+    RGB8UI,
+    RGBA8UI,
+    R8I,
+    RG8I,
+    RGB8I,
+    RGBA8I,
+    R16UI,
+    RG16UI,
+    RGB16UI,
+    RGBA16UI,
+    R16I,
+    RG16I,
+    RGB16I,
+    RGBA16I,
+    R32UI,
+    RG32UI,
+    RGB32UI,
+    RGBA32UI,
+    R32I,
+    RG32I,
+    RGB32I,
+    RGBA32I,
+    DepthComponent16,
+    DepthComponent24,
+    DepthComponent32F,
+    Depth24Stencil8,
+    Depth32FStencil8,
+    StencilIndex8,
+}
+
+static INTERNAL_FORMATS: [InternalFormat; 55] = [
+    InternalFormat::R8, InternalFormat::RG8, InternalFormat::RGB8, InternalFormat::RGBA8,
+    InternalFormat::R8Snorm, InternalFormat::RG8Snorm, InternalFormat::RGB8Snorm, InternalFormat::RGBA8Snorm,
+    InternalFormat::R16, InternalFormat::RG16, InternalFormat::RGB16, InternalFormat::RGBA16,
+    InternalFormat::RGB10A2, InternalFormat::Srgb8, InternalFormat::Srgb8Alpha8,
+    InternalFormat::R16F, InternalFormat::RG16F, InternalFormat::RGB16F, InternalFormat::RGBA16F,
+    InternalFormat::R32F, InternalFormat::RG32F, InternalFormat::RGB32F, InternalFormat::RGBA32F,
+    InternalFormat::R11FG11FB10F, InternalFormat::RGB9E5,
+    InternalFormat::R8UI, InternalFormat::RG8UI, InternalFormat::RGB8UI, InternalFormat::RGBA8UI,
+    InternalFormat::R8I, InternalFormat::RG8I, InternalFormat::RGB8I, InternalFormat::RGBA8I,
+    InternalFormat::R16UI, InternalFormat::RG16UI, InternalFormat::RGB16UI, InternalFormat::RGBA16UI,
+    InternalFormat::R16I, InternalFormat::RG16I, InternalFormat::RGB16I, InternalFormat::RGBA16I,
+    InternalFormat::R32UI, InternalFormat::RG32UI, InternalFormat::RGB32UI, InternalFormat::RGBA32UI,
+    InternalFormat::R32I, InternalFormat::RG32I, InternalFormat::RGB32I, InternalFormat::RGBA32I,
+    InternalFormat::DepthComponent16, InternalFormat::DepthComponent24, InternalFormat::DepthComponent32F,
+    InternalFormat::Depth24Stencil8, InternalFormat::Depth32FStencil8, InternalFormat::StencilIndex8,
+];
+
+impl InternalFormat {
+    pub fn to_gl(&self) -> GLenum {
+        match *self {
+            InternalFormat::R8 => gl::R8,
+            InternalFormat::RG8 => gl::RG8,
+            InternalFormat::RGB8 => gl::RGB8,
+            InternalFormat::RGBA8 => gl::RGBA8,
+            InternalFormat::R8Snorm => gl::R8_SNORM,
+            InternalFormat::RG8Snorm => gl::RG8_SNORM,
+            InternalFormat::RGB8Snorm => gl::RGB8_SNORM,
+            InternalFormat::RGBA8Snorm => gl::RGBA8_SNORM,
+            InternalFormat::R16 => gl::R16,
+            InternalFormat::RG16 => gl::RG16,
+            InternalFormat::RGB16 => gl::RGB16,
+            InternalFormat::RGBA16 => gl::RGBA16,
+            InternalFormat::RGB10A2 => gl::RGB10_A2,
+            InternalFormat::Srgb8 => gl::SRGB8,
+            InternalFormat::Srgb8Alpha8 => gl::SRGB8_ALPHA8,
+            InternalFormat::R16F => gl::R16F,
+            InternalFormat::RG16F => gl::RG16F,
+            InternalFormat::RGB16F => gl::RGB16F,
+            InternalFormat::RGBA16F => gl::RGBA16F,
+            InternalFormat::R32F => gl::R32F,
+            InternalFormat::RG32F => gl::RG32F,
+            InternalFormat::RGB32F => gl::RGB32F,
+            InternalFormat::RGBA32F => gl::RGBA32F,
+            InternalFormat::R11FG11FB10F => gl::R11F_G11F_B10F,
+            InternalFormat::RGB9E5 => gl::RGB9_E5,
+            InternalFormat::R8UI => gl::R8UI,
+            InternalFormat::RG8UI => gl::RG8UI,
+            InternalFormat::RGB8UI => gl::RGB8UI,
+            InternalFormat::RGBA8UI => gl::RGBA8UI,
+            InternalFormat::R8I => gl::R8I,
+            InternalFormat::RG8I => gl::RG8I,
+            InternalFormat::RGB8I => gl::RGB8I,
+            InternalFormat::RGBA8I => gl::RGBA8I,
+            InternalFormat::R16UI => gl::R16UI,
+            InternalFormat::RG16UI => gl::RG16UI,
+            InternalFormat::RGB16UI => gl::RGB16UI,
+            InternalFormat::RGBA16UI => gl::RGBA16UI,
+            InternalFormat::R16I => gl::R16I,
+            InternalFormat::RG16I => gl::RG16I,
+            InternalFormat::RGB16I => gl::RGB16I,
+            InternalFormat::RGBA16I => gl::RGBA16I,
+            InternalFormat::R32UI => gl::R32UI,
+            InternalFormat::RG32UI => gl::RG32UI,
+            InternalFormat::RGB32UI => gl::RGB32UI,
+            InternalFormat::RGBA32UI => gl::RGBA32UI,
+            InternalFormat::R32I => gl::R32I,
+            InternalFormat::RG32I => gl::RG32I,
+            InternalFormat::RGB32I => gl::RGB32I,
+            InternalFormat::RGBA32I => gl::RGBA32I,
+            InternalFormat::DepthComponent16 => gl::DEPTH_COMPONENT16,
+            InternalFormat::DepthComponent24 => gl::DEPTH_COMPONENT24,
+            InternalFormat::DepthComponent32F => gl::DEPTH_COMPONENT32F,
+            InternalFormat::Depth24Stencil8 => gl::DEPTH24_STENCIL8,
+            InternalFormat::Depth32FStencil8 => gl::DEPTH32F_STENCIL8,
+            InternalFormat::StencilIndex8 => gl::STENCIL_INDEX8,
+        }
+    }
+
+    pub fn from_gl(value: GLenum) -> Option<InternalFormat> {
+        INTERNAL_FORMATS.iter()
+            .find(|format| format.to_gl() == value)
+            .cloned()
+    }
+
+    pub fn kind(&self) -> FormatKind {
+        match *self {
+            InternalFormat::R8
+            | InternalFormat::RG8
+            | InternalFormat::RGB8
+            | InternalFormat::RGBA8
+            | InternalFormat::R16
+            | InternalFormat::RG16
+            | InternalFormat::RGB16
+            | InternalFormat::RGBA16
+            | InternalFormat::RGB10A2
+            | InternalFormat::Srgb8
+            | InternalFormat::Srgb8Alpha8 => FormatKind::UnsignedNormalized,
+            InternalFormat::R8Snorm
+            | InternalFormat::RG8Snorm
+            | InternalFormat::RGB8Snorm
+            | InternalFormat::RGBA8Snorm => FormatKind::SignedNormalized,
+            InternalFormat::R16F
+            | InternalFormat::RG16F
+            | InternalFormat::RGB16F
+            | InternalFormat::RGBA16F
+            | InternalFormat::R32F
+            | InternalFormat::RG32F
+            | InternalFormat::RGB32F
+            | InternalFormat::RGBA32F
+            | InternalFormat::R11FG11FB10F
+            | InternalFormat::RGB9E5 => FormatKind::Float,
+            InternalFormat::R8UI
+            | InternalFormat::RG8UI
+            | InternalFormat::RGB8UI
+            | InternalFormat::RGBA8UI
+            | InternalFormat::R16UI
+            | InternalFormat::RG16UI
+            | InternalFormat::RGB16UI
+            | InternalFormat::RGBA16UI
+            | InternalFormat::R32UI
+            | InternalFormat::RG32UI
+            | InternalFormat::RGB32UI
+            | InternalFormat::RGBA32UI => FormatKind::UnsignedInteger,
+            InternalFormat::R8I
+            | InternalFormat::RG8I
+            | InternalFormat::RGB8I
+            | InternalFormat::RGBA8I
+            | InternalFormat::R16I
+            | InternalFormat::RG16I
+            | InternalFormat::RGB16I
+            | InternalFormat::RGBA16I
+            | InternalFormat::R32I
+            | InternalFormat::RG32I
+            | InternalFormat::RGB32I
+            | InternalFormat::RGBA32I => FormatKind::SignedInteger,
+            InternalFormat::DepthComponent16
+            | InternalFormat::DepthComponent24
+            | InternalFormat::DepthComponent32F => FormatKind::Depth,
+            InternalFormat::Depth24Stencil8
+            | InternalFormat::Depth32FStencil8 => FormatKind::DepthStencil,
+            InternalFormat::StencilIndex8 => FormatKind::Stencil,
+        }
+    }
+
+    /// Returns true if color values are stored sRGB encoded.
+    pub fn is_srgb(&self) -> bool {
+        match *self {
+            InternalFormat::Srgb8 | InternalFormat::Srgb8Alpha8 => true,
+            _ => false,
+        }
+    }
+
+    /// Returns true if texels are read as integers and cannot be filtered.
+    pub fn is_integer(&self) -> bool {
+        match self.kind() {
+            FormatKind::UnsignedInteger | FormatKind::SignedInteger => true,
+            _ => false,
+        }
+    }
+
+    /// Returns true if the format supports linear filtering and mipmap generation.
+    ///
+    /// Integer and stencil formats are never filterable. 32-bit float formats
+    /// are filterable on desktop OpenGL, OpenGL ES needs `OES_texture_float_linear`.
+    pub fn is_filterable(&self) -> bool {
+        match self.kind() {
+            FormatKind::UnsignedInteger
+            | FormatKind::SignedInteger
+            | FormatKind::Stencil
+            | FormatKind::DepthStencil => false,
+            _ => true,
+        }
+    }
+
+    /// Returns true if the format can be used for image load and store.
+    ///
+    /// Three-component, sRGB, depth and stencil formats can not be bound to
+    /// image units.
+    pub fn is_image_format(&self) -> bool {
+        match *self {
+            InternalFormat::RGBA32F | InternalFormat::RGBA16F | InternalFormat::RG32F | InternalFormat::RG16F
+            | InternalFormat::R11FG11FB10F | InternalFormat::R32F | InternalFormat::R16F
+            | InternalFormat::RGBA32UI | InternalFormat::RGBA16UI | InternalFormat::RGBA8UI
+            | InternalFormat::RG32UI | InternalFormat::RG16UI | InternalFormat::RG8UI
+            | InternalFormat::R32UI | InternalFormat::R16UI | InternalFormat::R8UI
+            | InternalFormat::RGBA32I | InternalFormat::RGBA16I | InternalFormat::RGBA8I
+            | InternalFormat::RG32I | InternalFormat::RG16I | InternalFormat::RG8I
+            | InternalFormat::R32I | InternalFormat::R16I | InternalFormat::R8I
+            | InternalFormat::RGBA16 | InternalFormat::RGB10A2 | InternalFormat::RGBA8
+            | InternalFormat::RG16 | InternalFormat::RG8 | InternalFormat::R16 | InternalFormat::R8
+            | InternalFormat::RGBA8Snorm | InternalFormat::RG8Snorm | InternalFormat::R8Snorm => true,
+            _ => false,
+        }
+    }
+
+    /// Returns true if the format can be used for image load and store on OpenGL ES 3.1.
+    pub fn is_es_image_format(&self) -> bool {
+        match *self {
+            InternalFormat::RGBA32F | InternalFormat::RGBA16F | InternalFormat::R32F
+            | InternalFormat::RGBA32UI | InternalFormat::RGBA16UI | InternalFormat::RGBA8UI | InternalFormat::R32UI
+            | InternalFormat::RGBA32I | InternalFormat::RGBA16I | InternalFormat::RGBA8I | InternalFormat::R32I
+            | InternalFormat::RGBA8 | InternalFormat::RGBA8Snorm => true,
+            _ => false,
+        }
+    }
+
+    /// Size of one texel in bytes.
+    pub fn texel_size(&self) -> usize {
+        match *self {
+            InternalFormat::R8
+            | InternalFormat::R8Snorm
+            | InternalFormat::R8UI
+            | InternalFormat::R8I
+            | InternalFormat::StencilIndex8 => 1,
+            InternalFormat::RG8
+            | InternalFormat::RG8Snorm
+            | InternalFormat::RG8UI
+            | InternalFormat::RG8I
+            | InternalFormat::R16
+            | InternalFormat::R16F
+            | InternalFormat::R16UI
+            | InternalFormat::R16I
+            | InternalFormat::DepthComponent16 => 2,
+            InternalFormat::RGB8
+            | InternalFormat::RGB8Snorm
+            | InternalFormat::Srgb8
+            | InternalFormat::RGB8UI
+            | InternalFormat::RGB8I
+            | InternalFormat::DepthComponent24 => 3,
+            InternalFormat::RGBA8
+            | InternalFormat::RGBA8Snorm
+            | InternalFormat::Srgb8Alpha8
+            | InternalFormat::RGBA8UI
+            | InternalFormat::RGBA8I
+            | InternalFormat::RG16
+            | InternalFormat::RG16F
+            | InternalFormat::RG16UI
+            | InternalFormat::RG16I
+            | InternalFormat::RGB10A2
+            | InternalFormat::R32F
+            | InternalFormat::R32UI
+            | InternalFormat::R32I
+            | InternalFormat::R11FG11FB10F
+            | InternalFormat::RGB9E5
+            | InternalFormat::DepthComponent32F
+            | InternalFormat::Depth24Stencil8 => 4,
+            InternalFormat::RGB16
+            | InternalFormat::RGB16F
+            | InternalFormat::RGB16UI
+            | InternalFormat::RGB16I => 6,
+            InternalFormat::RGBA16
+            | InternalFormat::RGBA16F
+            | InternalFormat::RGBA16UI
+            | InternalFormat::RGBA16I
+            | InternalFormat::RG32F
+            | InternalFormat::RG32UI
+            | InternalFormat::RG32I
+            | InternalFormat::Depth32FStencil8 => 8,
+            InternalFormat::RGB32F
+            | InternalFormat::RGB32UI
+            | InternalFormat::RGB32I => 12,
+            InternalFormat::RGBA32F
+            | InternalFormat::RGBA32UI
+            | InternalFormat::RGBA32I => 16,
+        }
+    }
+
+    /// Pixel formats and types that may be used to transfer data to this format.
+    ///
+    /// Follows the OpenGL ES 3.0 table of valid combinations, which is also
+    /// valid on desktop OpenGL, plus `BGR`/`BGRA` orders for 8-bit color
+    /// formats, which are desktop only.
+    pub fn transfer_types(&self) -> (&'static [PixelFormat], &'static [PixelType]) {
+        static RED: [PixelFormat; 1] = [PixelFormat::Red];
+        static RG: [PixelFormat; 1] = [PixelFormat::RG];
+        static RGB: [PixelFormat; 1] = [PixelFormat::RGB];
+        static RGBA: [PixelFormat; 1] = [PixelFormat::RGBA];
+        static RGB_BGR: [PixelFormat; 2] = [PixelFormat::RGB, PixelFormat::BGR];
+        static RGBA_BGRA: [PixelFormat; 2] = [PixelFormat::RGBA, PixelFormat::BGRA];
+        static RED_INTEGER: [PixelFormat; 1] = [PixelFormat::RedInteger];
+        static RG_INTEGER: [PixelFormat; 1] = [PixelFormat::RGInteger];
+        static RGB_INTEGER: [PixelFormat; 1] = [PixelFormat::RGBInteger];
+        static RGBA_INTEGER: [PixelFormat; 1] = [PixelFormat::RGBAInteger];
+        static DEPTH: [PixelFormat; 1] = [PixelFormat::DepthComponent];
+        static DEPTH_STENCIL: [PixelFormat; 1] = [PixelFormat::DepthStencil];
+        static STENCIL: [PixelFormat; 1] = [PixelFormat::StencilIndex];
+
+        static UNSIGNED_BYTE: [PixelType; 1] = [PixelType::UnsignedByte];
+        static BYTE: [PixelType; 1] = [PixelType::Byte];
+        static UNSIGNED_SHORT: [PixelType; 1] = [PixelType::UnsignedShort];
+        static SHORT: [PixelType; 1] = [PixelType::Short];
+        static UNSIGNED_INT: [PixelType; 1] = [PixelType::UnsignedInt];
+        static INT: [PixelType; 1] = [PixelType::Int];
+        static FLOAT: [PixelType; 1] = [PixelType::Float];
+        static HALF_OR_FLOAT: [PixelType; 2] = [PixelType::HalfFloat, PixelType::Float];
+        static RGB10_A2: [PixelType; 1] = [PixelType::UnsignedInt2101010Rev];
+        static R11F_G11F_B10F: [PixelType; 3] = [PixelType::UnsignedInt10F11F11FRev, PixelType::HalfFloat, PixelType::Float];
+        static RGB9_E5: [PixelType; 3] = [PixelType::UnsignedInt5999Rev, PixelType::HalfFloat, PixelType::Float];
+        static DEPTH16: [PixelType; 2] = [PixelType::UnsignedShort, PixelType::UnsignedInt];
+        static DEPTH24_STENCIL8: [PixelType; 1] = [PixelType::UnsignedInt248];
+        static DEPTH32F_STENCIL8: [PixelType; 1] = [PixelType::Float32UnsignedInt248Rev];
+
+        match *self {
+            InternalFormat::R8 => (&RED, &UNSIGNED_BYTE),
+            InternalFormat::RG8 => (&RG, &UNSIGNED_BYTE),
+            InternalFormat::RGB8 | InternalFormat::Srgb8 => (&RGB_BGR, &UNSIGNED_BYTE),
+            InternalFormat::RGBA8 | InternalFormat::Srgb8Alpha8 => (&RGBA_BGRA, &UNSIGNED_BYTE),
+            InternalFormat::R8Snorm => (&RED, &BYTE),
+            InternalFormat::RG8Snorm => (&RG, &BYTE),
+            InternalFormat::RGB8Snorm => (&RGB, &BYTE),
+            InternalFormat::RGBA8Snorm => (&RGBA, &BYTE),
+            InternalFormat::R16 => (&RED, &UNSIGNED_SHORT),
+            InternalFormat::RG16 => (&RG, &UNSIGNED_SHORT),
+            InternalFormat::RGB16 => (&RGB, &UNSIGNED_SHORT),
+            InternalFormat::RGBA16 => (&RGBA, &UNSIGNED_SHORT),
+            InternalFormat::RGB10A2 => (&RGBA, &RGB10_A2),
+            InternalFormat::R16F => (&RED, &HALF_OR_FLOAT),
+            InternalFormat::RG16F => (&RG, &HALF_OR_FLOAT),
+            InternalFormat::RGB16F => (&RGB, &HALF_OR_FLOAT),
+            InternalFormat::RGBA16F => (&RGBA, &HALF_OR_FLOAT),
+            InternalFormat::R32F => (&RED, &FLOAT),
+            InternalFormat::RG32F => (&RG, &FLOAT),
+            InternalFormat::RGB32F => (&RGB, &FLOAT),
+            InternalFormat::RGBA32F => (&RGBA, &FLOAT),
+            InternalFormat::R11FG11FB10F => (&RGB, &R11F_G11F_B10F),
+            InternalFormat::RGB9E5 => (&RGB, &RGB9_E5),
+            InternalFormat::R8UI => (&RED_INTEGER, &UNSIGNED_BYTE),
+            InternalFormat::RG8UI => (&RG_INTEGER, &UNSIGNED_BYTE),
+            InternalFormat::RGB8UI => (&RGB_INTEGER, &UNSIGNED_BYTE),
+            InternalFormat::RGBA8UI => (&RGBA_INTEGER, &UNSIGNED_BYTE),
+            InternalFormat::R8I => (&RED_INTEGER, &BYTE),
+            InternalFormat::RG8I => (&RG_INTEGER, &BYTE),
+            InternalFormat::RGB8I => (&RGB_INTEGER, &BYTE),
+            InternalFormat::RGBA8I => (&RGBA_INTEGER, &BYTE),
+            InternalFormat::R16UI => (&RED_INTEGER, &UNSIGNED_SHORT),
+            InternalFormat::RG16UI => (&RG_INTEGER, &UNSIGNED_SHORT),
+            InternalFormat::RGB16UI => (&RGB_INTEGER, &UNSIGNED_SHORT),
+            InternalFormat::RGBA16UI => (&RGBA_INTEGER, &UNSIGNED_SHORT),
+            InternalFormat::R16I => (&RED_INTEGER, &SHORT),
+            InternalFormat::RG16I => (&RG_INTEGER, &SHORT),
+            InternalFormat::RGB16I => (&RGB_INTEGER, &SHORT),
+            InternalFormat::RGBA16I => (&RGBA_INTEGER, &SHORT),
+            InternalFormat::R32UI => (&RED_INTEGER, &UNSIGNED_INT),
+            InternalFormat::RG32UI => (&RG_INTEGER, &UNSIGNED_INT),
+            InternalFormat::RGB32UI => (&RGB_INTEGER, &UNSIGNED_INT),
+            InternalFormat::RGBA32UI => (&RGBA_INTEGER, &UNSIGNED_INT),
+            InternalFormat::R32I => (&RED_INTEGER, &INT),
+            InternalFormat::RG32I => (&RG_INTEGER, &INT),
+            InternalFormat::RGB32I => (&RGB_INTEGER, &INT),
+            InternalFormat::RGBA32I => (&RGBA_INTEGER, &INT),
+            InternalFormat::DepthComponent16 => (&DEPTH, &DEPTH16),
+            InternalFormat::DepthComponent24 => (&DEPTH, &UNSIGNED_INT),
+            InternalFormat::DepthComponent32F => (&DEPTH, &FLOAT),
+            InternalFormat::Depth24Stencil8 => (&DEPTH_STENCIL, &DEPTH24_STENCIL8),
+            InternalFormat::Depth32FStencil8 => (&DEPTH_STENCIL, &DEPTH32F_STENCIL8),
+            InternalFormat::StencilIndex8 => (&STENCIL, &UNSIGNED_BYTE),
+        }
+    }
+
+    /// Check that data in `format` and `pixel_type` can be transferred to this format.
+    pub fn check_transfer(&self, format: PixelFormat, pixel_type: PixelType) -> Result<(), FormatError> {
+        let (formats, types) = self.transfer_types();
+        if formats.contains(&format) && types.contains(&pixel_type) {
+            return Ok(());
+        }
+        Err(FormatError::IllegalCombination {
+            internal_format: *self,
+            format: format,
+            pixel_type: pixel_type,
+        })
+    }
+
+    /// Check that this format can be read back as `format` and `pixel_type`.
+    ///
+    /// Readback converts between normalized and float values, so only the
+    /// class of the pixel format has to match.
+    pub fn check_readback(&self, format: PixelFormat, pixel_type: PixelType) -> Result<(), FormatError> {
+        let float_type = match pixel_type {
+            PixelType::HalfFloat | PixelType::Float | PixelType::UnsignedInt10F11F11FRev | PixelType::UnsignedInt5999Rev => true,
+            _ => false,
+        };
+        let valid = match self.kind() {
+            FormatKind::UnsignedNormalized
+            | FormatKind::SignedNormalized
+            | FormatKind::Float => match format {
+                PixelFormat::Red
+                | PixelFormat::RG
+                | PixelFormat::RGB
+                | PixelFormat::BGR
+                | PixelFormat::RGBA
+                | PixelFormat::BGRA => pixel_type != PixelType::UnsignedInt248 && pixel_type != PixelType::Float32UnsignedInt248Rev,
+                _ => false,
+            },
+            FormatKind::UnsignedInteger
+            | FormatKind::SignedInteger => match format {
+                PixelFormat::RedInteger
+                | PixelFormat::RGInteger
+                | PixelFormat::RGBInteger
+                | PixelFormat::RGBAInteger => !float_type && !pixel_type.is_packed(),
+                _ => false,
+            },
+            FormatKind::Depth => format == PixelFormat::DepthComponent && !pixel_type.is_packed(),
+            FormatKind::Stencil => format == PixelFormat::StencilIndex && !float_type && !pixel_type.is_packed(),
+            FormatKind::DepthStencil => match format {
+                PixelFormat::DepthStencil => self.transfer_types().1.contains(&pixel_type),
+                PixelFormat::DepthComponent => !pixel_type.is_packed(),
+                PixelFormat::StencilIndex => !float_type && !pixel_type.is_packed(),
+                _ => false,
+            },
+        };
+        if valid {
+            return Ok(());
+        }
+        Err(FormatError::IllegalCombination {
+            internal_format: *self,
+            format: format,
+            pixel_type: pixel_type,
+        })
+    }
+}
+
+/// Internal format of allocated texture images, either uncompressed or block-compressed.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum TextureFormat {
+    Uncompressed(InternalFormat),
+    Compressed(CompressedFormat),
+}
+
+impl TextureFormat {
+    pub fn to_gl(&self) -> GLenum {
+        match *self {
+            TextureFormat::Uncompressed(format) => format.to_gl(),
+            TextureFormat::Compressed(format) => format.to_gl(),
+        }
+    }
+
+    pub fn is_compressed(&self) -> bool {
+        match *self {
+            TextureFormat::Compressed(_) => true,
+            TextureFormat::Uncompressed(_) => false,
+        }
+    }
+
+    pub fn is_srgb(&self) -> bool {
+        match *self {
+            TextureFormat::Uncompressed(format) => format.is_srgb(),
+            TextureFormat::Compressed(format) => format.is_srgb(),
+        }
+    }
+
+    /// Compatibility class used by texture views.
+    pub fn view_class(&self) -> FormatClass {
+        match *self {
+            TextureFormat::Uncompressed(format) => match format.kind() {
+                FormatKind::Depth
+                | FormatKind::Stencil
+                | FormatKind::DepthStencil => FormatClass::Exact(format),
+                _ => FormatClass::Bits(format.texel_size() as u32 * 8),
+            },
+            TextureFormat::Compressed(format) => FormatClass::Compressed(format.base_format()),
+        }
+    }
+
+    /// Returns true if a view of this format can be created with `other` format.
+    pub fn is_view_compatible(&self, other: TextureFormat) -> bool {
+        self.view_class() == other.view_class()
+    }
+
+    /// Returns true if images of this format can be copied to images of `other` format.
+    ///
+    /// In addition to view compatible formats, uncompressed formats can be
+    /// copied to and from compressed formats whose block has the same size as
+    /// their texel.
+    pub fn is_copy_compatible(&self, other: TextureFormat) -> bool {
+        match (self.view_class(), other.view_class()) {
+            (FormatClass::Bits(bits), FormatClass::Compressed(format))
+            | (FormatClass::Compressed(format), FormatClass::Bits(bits)) => format.block_bytes() as u32 * 8 == bits,
+            (class, other_class) => class == other_class,
+        }
+    }
+}
+
+/// Class of internal formats which can reinterpret each other's texel data.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum FormatClass {
+    /// Uncompressed color formats with the same number of bits per texel.
+    Bits(u32),
+    /// Depth and stencil formats, only compatible with themselves.
+    Exact(InternalFormat),
+    /// Compressed formats differing only in sRGB encoding or signedness.
+    Compressed(CompressedFormat),
+}
+
+/// Layout of components in client pixel data.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum PixelFormat {
+    Red,
+    RG,
+    RGB,
+    BGR,
+    RGBA,
+    BGRA,
+    RedInteger,
+    RGInteger,
+    RGBInteger,
+    RGBAInteger,
+    DepthComponent,
+    StencilIndex,
+    DepthStencil,
+}
+
+impl PixelFormat {
+    pub fn to_gl(&self) -> GLenum {
+        match *self {
+            PixelFormat::Red => gl::RED,
+            PixelFormat::RG => gl::RG,
+            PixelFormat::RGB => gl::RGB,
+            PixelFormat::BGR => gl::BGR,
+            PixelFormat::RGBA => gl::RGBA,
+            PixelFormat::BGRA => gl::BGRA,
+            PixelFormat::RedInteger => gl::RED_INTEGER,
+            PixelFormat::RGInteger => gl::RG_INTEGER,
+            PixelFormat::RGBInteger => gl::RGB_INTEGER,
+            PixelFormat::RGBAInteger => gl::RGBA_INTEGER,
+            PixelFormat::DepthComponent => gl::DEPTH_COMPONENT,
+            PixelFormat::StencilIndex => gl::STENCIL_INDEX,
+            PixelFormat::DepthStencil => gl::DEPTH_STENCIL,
+        }
+    }
+
+    /// Number of components per pixel.
+    pub fn components(&self) -> usize {
+        match *self {
+            PixelFormat::Red
+            | PixelFormat::RedInteger
+            | PixelFormat::DepthComponent
+            | PixelFormat::StencilIndex => 1,
+            PixelFormat::RG
+            | PixelFormat::RGInteger
+            | PixelFormat::DepthStencil => 2,
+            PixelFormat::RGB
+            | PixelFormat::BGR
+            | PixelFormat::RGBInteger => 3,
+            PixelFormat::RGBA
+            | PixelFormat::BGRA
+            | PixelFormat::RGBAInteger => 4,
+        }
+    }
+}
+
+/// Data type of client pixel components.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum PixelType {
+    UnsignedByte,
+    Byte,
+    UnsignedShort,
+    Short,
+    UnsignedInt,
+    Int,
+    HalfFloat,
+    Float,
+    UnsignedInt2101010Rev,
+    UnsignedInt10F11F11FRev,
+    UnsignedInt5999Rev,
+    UnsignedInt248,
+    Float32UnsignedInt248Rev,
+}
+
+impl PixelType {
+    pub fn to_gl(&self) -> GLenum {
+        match *self {
+            PixelType::UnsignedByte => gl::UNSIGNED_BYTE,
+            PixelType::Byte => gl::BYTE,
+            PixelType::UnsignedShort => gl::UNSIGNED_SHORT,
+            PixelType::Short => gl::SHORT,
+            PixelType::UnsignedInt => gl::UNSIGNED_INT,
+            PixelType::Int => gl::INT,
+            PixelType::HalfFloat => gl::HALF_FLOAT,
+            PixelType::Float => gl::FLOAT,
+            PixelType::UnsignedInt2101010Rev => gl::UNSIGNED_INT_2_10_10_10_REV,
+            PixelType::UnsignedInt10F11F11FRev => gl::UNSIGNED_INT_10F_11F_11F_REV,
+            PixelType::UnsignedInt5999Rev => gl::UNSIGNED_INT_5_9_9_9_REV,
+            PixelType::UnsignedInt248 => gl::UNSIGNED_INT_24_8,
+            PixelType::Float32UnsignedInt248Rev => gl::FLOAT_32_UNSIGNED_INT_24_8_REV,
+        }
+    }
+
+    /// Returns true if a single value holds all components of a pixel.
+    pub fn is_packed(&self) -> bool {
+        match *self {
+            PixelType::UnsignedInt2101010Rev
+            | PixelType::UnsignedInt10F11F11FRev
+            | PixelType::UnsignedInt5999Rev
+            | PixelType::UnsignedInt248
+            | PixelType::Float32UnsignedInt248Rev => true,
+            _ => false,
+        }
+    }
+
+    /// Size in bytes of one component, or of the whole pixel if packed.
+    pub fn size(&self) -> usize {
+        match *self {
+            PixelType::UnsignedByte | PixelType::Byte => 1,
+            PixelType::UnsignedShort | PixelType::Short | PixelType::HalfFloat => 2,
+            PixelType::UnsignedInt
+            | PixelType::Int
+            | PixelType::Float
+            | PixelType::UnsignedInt2101010Rev
+            | PixelType::UnsignedInt10F11F11FRev
+            | PixelType::UnsignedInt5999Rev
+            | PixelType::UnsignedInt248 => 4,
+            PixelType::Float32UnsignedInt248Rev => 8,
+        }
+    }
+}
+
+/// Size in bytes of one pixel of client data.
+pub fn pixel_size(format: PixelFormat, pixel_type: PixelType) -> usize {
+    if pixel_type.is_packed() {
+        pixel_type.size()
+    } else {
+        format.components() * pixel_type.size()
+    }
+}
+
+/// Size in bytes of one row, padded to `alignment` as set by `GL_UNPACK_ALIGNMENT`.
+pub fn row_stride(format: PixelFormat, pixel_type: PixelType, width: usize, alignment: usize) -> usize {
+    let row = width * pixel_size(format, pixel_type);
+    (row + alignment - 1) / alignment * alignment
+}
+
+/// Number of bytes OpenGL reads for a `width` x `height` x `depth` upload.
+///
+/// Every row except the last one is padded to `alignment`.
+pub fn transfer_size(format: PixelFormat, pixel_type: PixelType, width: usize, height: usize, depth: usize, alignment: usize) -> usize {
+    let rows = height * depth;
+    if rows == 0 || width == 0 {
+        return 0;
+    }
+    (rows - 1) * row_stride(format, pixel_type, width, alignment) + width * pixel_size(format, pixel_type)
+}
+
+/// Check that `len` bytes of client data are enough for an upload.
+pub fn check_transfer_size(format: PixelFormat, pixel_type: PixelType, width: usize, height: usize, depth: usize, alignment: usize, len: usize) -> Result<usize, FormatError> {
+    let expected = transfer_size(format, pixel_type, width, height, depth, alignment);
+    if len < expected {
+        return Err(FormatError::DataTooSmall { expected: expected, actual: len });
+    }
+    Ok(expected)
+}
+
+/// Client pixel type with a known pixel format and type.
+///
+/// `u8` and `u16` based pixels map to normalized formats, `u32` and `i32`
+/// based pixels map to integer formats.
+pub trait Pixel: Copy {
+    fn format() -> PixelFormat;
+    fn pixel_type() -> PixelType;
+}
+
+macro_rules! impl_pixel {
+    ($t:ty, $format:ident, $pixel_type:ident) => {
+        impl Pixel for $t {
+            fn format() -> PixelFormat { PixelFormat::$format }
+            fn pixel_type() -> PixelType { PixelType::$pixel_type }
+        }
+    };
+    ($t:ty, $red:ident, $rg:ident, $rgb:ident, $rgba:ident, $pixel_type:ident) => {
+        impl_pixel!($t, $red, $pixel_type);
+        impl_pixel!([$t; 1], $red, $pixel_type);
+        impl_pixel!([$t; 2], $rg, $pixel_type);
+        impl_pixel!([$t; 3], $rgb, $pixel_type);
+        impl_pixel!([$t; 4], $rgba, $pixel_type);
+    };
+}
+
+impl_pixel!(u8, Red, RG, RGB, RGBA, UnsignedByte);
+impl_pixel!(i8, Red, RG, RGB, RGBA, Byte);
+impl_pixel!(u16, Red, RG, RGB, RGBA, UnsignedShort);
+impl_pixel!(i16, Red, RG, RGB, RGBA, Short);
+impl_pixel!(u32, RedInteger, RGInteger, RGBInteger, RGBAInteger, UnsignedInt);
+impl_pixel!(i32, RedInteger, RGInteger, RGBInteger, RGBAInteger, Int);
+impl_pixel!(f32, Red, RG, RGB, RGBA, Float);
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum FormatError {
+    IllegalCombination { internal_format: InternalFormat, format: PixelFormat, pixel_type: PixelType },
+    DataTooSmall { expected: usize, actual: usize },
+}
+
+impl fmt::Display for FormatError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            &FormatError::IllegalCombination { internal_format, format, pixel_type } => write!(f, "Pixel data of {:?} format and {:?} type can not be transferred to {:?} texture.", format, pixel_type, internal_format),
+            &FormatError::DataTooSmall { expected, actual } => write!(f, "Pixel data has {} bytes, but {} bytes are required.", actual, expected),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matching_transfer_is_accepted() {
+        assert_eq!(InternalFormat::RGBA8.check_transfer(PixelFormat::RGBA, PixelType::UnsignedByte), Ok(()));
+        assert_eq!(InternalFormat::RGBA8.check_transfer(PixelFormat::BGRA, PixelType::UnsignedByte), Ok(()));
+        assert_eq!(InternalFormat::RGBA16F.check_transfer(PixelFormat::RGBA, PixelType::HalfFloat), Ok(()));
+        assert_eq!(InternalFormat::RGBA16F.check_transfer(PixelFormat::RGBA, PixelType::Float), Ok(()));
+        assert_eq!(InternalFormat::R32UI.check_transfer(PixelFormat::RedInteger, PixelType::UnsignedInt), Ok(()));
+        assert_eq!(InternalFormat::Depth24Stencil8.check_transfer(PixelFormat::DepthStencil, PixelType::UnsignedInt248), Ok(()));
+    }
+
+    #[test]
+    fn mismatched_transfer_is_rejected() {
+        assert_eq!(
+            InternalFormat::R32UI.check_transfer(PixelFormat::Red, PixelType::UnsignedInt),
+            Err(FormatError::IllegalCombination {
+                internal_format: InternalFormat::R32UI,
+                format: PixelFormat::Red,
+                pixel_type: PixelType::UnsignedInt,
+            })
+        );
+        assert!(InternalFormat::RGBA8.check_transfer(PixelFormat::RGBA, PixelType::Float).is_err());
+        assert!(InternalFormat::RGB8Snorm.check_transfer(PixelFormat::BGR, PixelType::Byte).is_err());
+        assert!(InternalFormat::DepthComponent24.check_transfer(PixelFormat::Red, PixelType::UnsignedInt).is_err());
+    }
+
+    #[test]
+    fn readback_matches_format_class() {
+        assert_eq!(InternalFormat::RGBA8.check_readback(PixelFormat::RGBA, PixelType::Float), Ok(()));
+        assert!(InternalFormat::RGBA8.check_readback(PixelFormat::RGBAInteger, PixelType::UnsignedByte).is_err());
+        assert_eq!(InternalFormat::RGBA32I.check_readback(PixelFormat::RGBAInteger, PixelType::Int), Ok(()));
+        assert!(InternalFormat::RGBA32I.check_readback(PixelFormat::RGBAInteger, PixelType::Float).is_err());
+    }
+
+    #[test]
+    fn pixel_size_counts_components_unless_packed() {
+        assert_eq!(pixel_size(PixelFormat::RGB, PixelType::UnsignedByte), 3);
+        assert_eq!(pixel_size(PixelFormat::RGBA, PixelType::Float), 16);
+        assert_eq!(pixel_size(PixelFormat::RGBA, PixelType::UnsignedInt2101010Rev), 4);
+        assert_eq!(pixel_size(PixelFormat::DepthStencil, PixelType::Float32UnsignedInt248Rev), 8);
+    }
+
+    #[test]
+    fn transfer_size_pads_all_rows_but_last() {
+        assert_eq!(row_stride(PixelFormat::RGB, PixelType::UnsignedByte, 3, 4), 12);
+        assert_eq!(transfer_size(PixelFormat::RGB, PixelType::UnsignedByte, 3, 2, 1, 4), 12 + 9);
+        assert_eq!(transfer_size(PixelFormat::RGB, PixelType::UnsignedByte, 3, 2, 1, 1), 18);
+        assert_eq!(transfer_size(PixelFormat::RGB, PixelType::UnsignedByte, 3, 2, 2, 4), 3 * 12 + 9);
+        assert_eq!(transfer_size(PixelFormat::RGBA, PixelType::UnsignedByte, 4, 4, 1, 8), 64);
+        assert_eq!(transfer_size(PixelFormat::RGBA, PixelType::UnsignedByte, 0, 4, 1, 4), 0);
+        assert_eq!(transfer_size(PixelFormat::RGBA, PixelType::UnsignedByte, 4, 0, 1, 4), 0);
+    }
+
+    #[test]
+    fn short_client_data_is_rejected() {
+        assert_eq!(check_transfer_size(PixelFormat::RGB, PixelType::UnsignedByte, 3, 2, 1, 4, 21), Ok(21));
+        assert_eq!(
+            check_transfer_size(PixelFormat::RGB, PixelType::UnsignedByte, 3, 2, 1, 4, 20),
+            Err(FormatError::DataTooSmall { expected: 21, actual: 20 })
+        );
+    }
+}
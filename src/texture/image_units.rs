@@ -0,0 +1,348 @@
+use gl;
+use gl::types::*;
+
+use std::fmt;
+
+use version::{ Api, GLVersion, has_extension };
+
+use super::Texture;
+use super::format::{ InternalFormat, TextureFormat, FormatKind };
+
+/// Shader access to an image bound to an image unit.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum ImageAccess {
+    ReadOnly,
+    WriteOnly,
+    ReadWrite,
+}
+
+impl ImageAccess {
+    pub fn to_gl(&self) -> GLenum {
+        match *self {
+            ImageAccess::ReadOnly => gl::READ_ONLY,
+            ImageAccess::WriteOnly => gl::WRITE_ONLY,
+            ImageAccess::ReadWrite => gl::READ_WRITE,
+        }
+    }
+
+    /// Returns true if shaders may store to the image.
+    pub fn is_write(&self) -> bool {
+        *self != ImageAccess::ReadOnly
+    }
+}
+
+/// Texture level bound to an image unit.
+#[derive(Clone)]
+pub struct ImageBinding {
+    texture: Texture,
+    level: u32,
+    layer: Option<u32>,
+    access: ImageAccess,
+    format: InternalFormat,
+}
+
+impl ImageBinding {
+    pub fn get_texture(&self) -> &Texture {
+        &self.texture
+    }
+
+    pub fn get_level(&self) -> u32 {
+        self.level
+    }
+
+    /// Get bound layer, or `None` if all layers are bound.
+    pub fn get_layer(&self) -> Option<u32> {
+        self.layer
+    }
+
+    pub fn get_access(&self) -> ImageAccess {
+        self.access
+    }
+
+    /// Get format used by shader loads and stores.
+    pub fn get_format(&self) -> InternalFormat {
+        self.format
+    }
+
+    fn is_same(&self, other: &ImageBinding) -> bool {
+        self.texture.get_id() == other.texture.get_id()
+            && self.level == other.level
+            && self.layer == other.layer
+            && self.access == other.access
+            && self.format == other.format
+    }
+}
+
+/// Tracks texture levels bound to image units for shader loads and stores.
+///
+/// Number of units is queried from `GL_MAX_IMAGE_UNITS` on first use.
+/// Redundant `glBindImageTexture` calls are skipped. Dispatches and draws with
+/// images bound for writing are remembered until a memory barrier makes the
+/// writes visible.
+pub struct ImageUnits {
+    units: Vec<Option<ImageBinding>>,
+    supported: Option<bool>,
+    /// OpenGL ES only binds textures with immutable storage, with the ES 3.1 image formats.
+    immutable_required: bool,
+    pending_writes: bool,
+}
+
+impl ImageUnits {
+    pub fn new() -> ImageUnits {
+        ImageUnits {
+            units: Vec::new(),
+            supported: None,
+            immutable_required: false,
+            pending_writes: false,
+        }
+    }
+
+    /// Returns true if image load and store is available.
+    ///
+    /// Requires OpenGL 4.2, OpenGL ES 3.1 or `ARB_shader_image_load_store`.
+    pub fn is_supported(&mut self) -> bool {
+        if let Some(supported) = self.supported {
+            return supported;
+        }
+
+        let supported = match GLVersion::current() {
+            Some(version) => {
+                self.immutable_required = version.api == Api::GlEs;
+                version.is_at_least(Api::Gl, 4, 2)
+                    || version.is_at_least(Api::GlEs, 3, 1)
+                    || has_extension("GL_ARB_shader_image_load_store")
+            },
+            None => false,
+        };
+
+        debug!("image load store supported = {}, immutable required = {}", supported, self.immutable_required);
+
+        self.supported = Some(supported);
+        supported
+    }
+
+    fn ensure_units(&mut self) {
+        if self.units.len() > 0 || !self.is_supported() {
+            return;
+        }
+
+        let mut count: GLint = 0;
+        unsafe { gl::GetIntegerv(gl::MAX_IMAGE_UNITS, &mut count) };
+
+        debug!("image units, count = {}", count);
+
+        self.units = (0..count).map(|_| None).collect();
+    }
+
+    /// Number of image units, 0 if image load and store is not supported.
+    pub fn count(&mut self) -> GLuint {
+        self.ensure_units();
+        self.units.len() as GLuint
+    }
+
+    fn check_unit(&mut self, unit: GLuint) -> Result<(), ImageUnitError> {
+        if !self.is_supported() {
+            return Err(ImageUnitError::Unsupported);
+        }
+
+        let count = self.count();
+        if unit >= count {
+            error!("image unit {} out of range, count = {}", unit, count);
+            return Err(ImageUnitError::UnitOutOfRange { unit: unit, count: count });
+        }
+        Ok(())
+    }
+
+    /// Bind mip `level` of texture to image unit.
+    ///
+    /// With `layer` of `None` all layers, faces or slices of layered and 3D
+    /// textures are bound, otherwise only the selected one. Shader loads and
+    /// stores use `format`, which must be an image format with the same texel
+    /// size as the texture's format. Textures with compressed, depth, stencil
+    /// or sRGB formats can not be bound. On OpenGL ES the texture must have
+    /// immutable storage and `format` must equal the texture's format.
+    ///
+    /// ## glBindImageTexture
+    ///
+    /// - OpenGL Version 4.2
+    /// - OpenGL ES Version 3.1
+    ///
+    pub fn bind(&mut self, unit: GLuint, texture: &Texture, level: u32, layer: Option<u32>, access: ImageAccess, format: InternalFormat) -> Result<(), ImageUnitError> {
+        if let Err(err) = self.check_unit(unit) {
+            return Err(err);
+        }
+
+        let storage = match texture.get_storage() {
+            Some(storage) => storage,
+            None => return Err(ImageUnitError::NoStorage(texture.get_id())),
+        };
+
+        if self.immutable_required && !storage.immutable {
+            error!("[{}]: image units require immutable storage", texture.get_id());
+            return Err(ImageUnitError::NotImmutable(texture.get_id()));
+        }
+
+        if level >= storage.levels {
+            return Err(ImageUnitError::LevelOutOfRange { level: level, levels: storage.levels });
+        }
+
+        let image_format = if self.immutable_required { format.is_es_image_format() } else { format.is_image_format() };
+        if !image_format {
+            error!("[{}]: {:?} can not be used for image load and store", texture.get_id(), format);
+            return Err(ImageUnitError::FormatUnsupported(format));
+        }
+
+        let texture_format = match storage.format {
+            TextureFormat::Uncompressed(texture_format) => match texture_format.kind() {
+                FormatKind::Depth | FormatKind::DepthStencil | FormatKind::Stencil => None,
+                _ if texture_format.is_srgb() => None,
+                _ => Some(texture_format),
+            },
+            TextureFormat::Compressed(_) => None,
+        };
+        let texture_format = match texture_format {
+            Some(texture_format) => texture_format,
+            None => {
+                error!("[{}]: texture format {:?} can not be bound to image unit", texture.get_id(), storage.format);
+                return Err(ImageUnitError::TextureFormatUnsupported(storage.format));
+            },
+        };
+
+        let compatible = if self.immutable_required {
+            texture_format == format
+        } else {
+            texture_format.texel_size() == format.texel_size()
+        };
+        if !compatible {
+            error!("[{}]: image format {:?} is not compatible with {:?}", texture.get_id(), format, storage.format);
+            return Err(ImageUnitError::FormatMismatch { texture: storage.format, image: format });
+        }
+
+        let layers = storage.level_size(level).depth;
+        if let Some(layer) = layer {
+            if layer >= layers {
+                return Err(ImageUnitError::LayerOutOfRange { layer: layer, layers: layers });
+            }
+        }
+
+        let binding = ImageBinding {
+            texture: texture.clone(),
+            level: level,
+            layer: layer,
+            access: access,
+            format: format,
+        };
+
+        let bound = match self.units[unit as usize] {
+            Some(ref bound) => bound.is_same(&binding),
+            None => false,
+        };
+        if bound {
+            trace!("[{}]: already bound to image unit {}", texture.get_id(), unit);
+            return Ok(());
+        }
+
+        debug!("[{}]: bind image unit {}, level = {}, layer = {:?}, access = {:?}, format = {:?}", texture.get_id(), unit, level, layer, access, format);
+
+        unsafe {
+            gl::BindImageTexture(unit, texture.get_id(), level as GLint,
+                if layer.is_none() { gl::TRUE } else { gl::FALSE }, layer.unwrap_or(0) as GLint,
+                access.to_gl(), format.to_gl())
+        };
+
+        self.units[unit as usize] = Some(binding);
+
+        Ok(())
+    }
+
+    /// Unbind texture from image unit.
+    ///
+    /// ## glBindImageTexture(0)
+    ///
+    /// - OpenGL Version 4.2
+    /// - OpenGL ES Version 3.1
+    ///
+    pub fn unbind(&mut self, unit: GLuint) -> Result<(), ImageUnitError> {
+        if self.get_bound_id(unit).is_none() {
+            return Ok(());
+        }
+
+        debug!("unbind image unit {}", unit);
+        unsafe { gl::BindImageTexture(unit, 0, 0, gl::FALSE, 0, gl::READ_ONLY, gl::R32UI) };
+
+        self.units[unit as usize] = None;
+
+        Ok(())
+    }
+
+    /// Get binding of image unit.
+    pub fn get_binding(&self, unit: GLuint) -> Option<&ImageBinding> {
+        self.units.get(unit as usize).and_then(|binding| binding.as_ref())
+    }
+
+    /// Get name of texture bound to image unit.
+    pub fn get_bound_id(&self, unit: GLuint) -> Option<GLuint> {
+        self.get_binding(unit).map(|binding| binding.texture.get_id())
+    }
+
+    /// List all bindings as unit and texture name.
+    pub fn bound_ids(&self) -> Vec<(GLuint, GLuint)> {
+        self.units.iter().enumerate()
+            .filter_map(|(unit, binding)| binding.as_ref().map(|binding| (unit as GLuint, binding.texture.get_id())))
+            .collect()
+    }
+
+    /// Returns true if any image is bound with write access.
+    pub fn has_writable(&self) -> bool {
+        self.units.iter().any(|binding| match *binding {
+            Some(ref binding) => binding.access.is_write(),
+            None => false,
+        })
+    }
+
+    /// Remember that shaders may have stored to writable images.
+    pub fn mark_written(&mut self) {
+        if self.has_writable() {
+            self.pending_writes = true;
+        }
+    }
+
+    /// Returns true if images were written since the last image barrier.
+    pub fn has_pending_writes(&self) -> bool {
+        self.pending_writes
+    }
+
+    /// Forget image writes, after a barrier made them visible.
+    pub fn clear_pending_writes(&mut self) {
+        self.pending_writes = false;
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ImageUnitError {
+    Unsupported,
+    UnitOutOfRange { unit: GLuint, count: GLuint },
+    NoStorage(GLuint),
+    NotImmutable(GLuint),
+    LevelOutOfRange { level: u32, levels: u32 },
+    LayerOutOfRange { layer: u32, layers: u32 },
+    FormatUnsupported(InternalFormat),
+    TextureFormatUnsupported(TextureFormat),
+    FormatMismatch { texture: TextureFormat, image: InternalFormat },
+}
+
+impl fmt::Display for ImageUnitError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            &ImageUnitError::Unsupported => "Image load and store requires OpenGL 4.2, OpenGL ES 3.1 or ARB_shader_image_load_store.".fmt(f),
+            &ImageUnitError::UnitOutOfRange { unit, count } => write!(f, "Tried to use image unit {}, but there are only {} units.", unit, count),
+            &ImageUnitError::NoStorage(id) => write!(f, "Tried to bind texture {} to image unit before allocating its images.", id),
+            &ImageUnitError::NotImmutable(id) => write!(f, "Tried to bind texture {} without immutable storage to image unit.", id),
+            &ImageUnitError::LevelOutOfRange { level, levels } => write!(f, "Tried to bind mip level {} of texture with {} levels to image unit.", level, levels),
+            &ImageUnitError::LayerOutOfRange { layer, layers } => write!(f, "Tried to bind layer {} of image with {} layers to image unit.", layer, layers),
+            &ImageUnitError::FormatUnsupported(format) => write!(f, "Format {:?} can not be used for image load and store.", format),
+            &ImageUnitError::TextureFormatUnsupported(format) => write!(f, "Textures with format {:?} can not be bound to image units.", format),
+            &ImageUnitError::FormatMismatch { texture, image } => write!(f, "Image format {:?} is not compatible with texture format {:?}.", image, texture),
+        }
+    }
+}
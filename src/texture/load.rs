@@ -0,0 +1,231 @@
+#[cfg(feature = "image")] use image;
+#[cfg(feature = "image")] use image::{ DynamicImage, ImageError };
+
+use std::fmt;
+use std::fs::File;
+use std::io;
+use std::io::Read;
+use std::path::Path;
+
+use role::Generator;
+
+use super::{ Texture, TextureError, TextureUnits };
+#[cfg(feature = "image")] use super::{ TextureTarget, Texture2D };
+use super::container::{ self, CompressedData, ContainerError };
+use super::storage::Region;
+#[cfg(feature = "image")] use super::format::{ InternalFormat, PixelFormat, PixelType };
+#[cfg(feature = "image")] use super::storage::Size;
+
+/// How 8-bit color values of loaded images are interpreted.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ColorSpace {
+    /// Values are sRGB encoded and decoded to linear when sampled.
+    Srgb,
+    /// Values are used as they are, for normal maps, masks and data.
+    Linear,
+}
+
+/// Options for loading image files into textures.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct LoadOptions {
+    pub color_space: ColorSpace,
+    /// Flip rows so that the first image row ends up at the bottom, as OpenGL expects.
+    pub flip_vertical: bool,
+    /// Allocate full mip chain and generate it from the loaded image.
+    pub mipmaps: bool,
+}
+
+impl LoadOptions {
+    /// sRGB color, flipped to OpenGL origin, with mipmaps.
+    pub fn new() -> LoadOptions {
+        LoadOptions {
+            color_space: ColorSpace::Srgb,
+            flip_vertical: true,
+            mipmaps: true,
+        }
+    }
+
+    pub fn color_space(mut self, color_space: ColorSpace) -> LoadOptions {
+        self.color_space = color_space;
+        self
+    }
+
+    pub fn flip_vertical(mut self, flip: bool) -> LoadOptions {
+        self.flip_vertical = flip;
+        self
+    }
+
+    pub fn mipmaps(mut self, mipmaps: bool) -> LoadOptions {
+        self.mipmaps = mipmaps;
+        self
+    }
+}
+
+/// Load PNG, JPEG, TGA, HDR or other image file supported by `image` crate into 2D texture.
+///
+/// Requires the `image` feature.
+#[cfg(feature = "image")]
+pub fn load_texture_2d<T: AsRef<Path>>(units: &mut TextureUnits, path: T, options: &LoadOptions) -> Result<Texture2D, LoadError> {
+    debug!("load texture {}", path.as_ref().display());
+
+    match image::open(path) {
+        Ok(decoded) => texture_from_image(units, decoded, options),
+        Err(err) => Err(LoadError::Decode(err)),
+    }
+}
+
+/// Load image file contents from memory into 2D texture, detecting file type from data.
+#[cfg(feature = "image")]
+pub fn load_texture_2d_from_memory(units: &mut TextureUnits, data: &[u8], options: &LoadOptions) -> Result<Texture2D, LoadError> {
+    match image::load_from_memory(data) {
+        Ok(decoded) => texture_from_image(units, decoded, options),
+        Err(err) => Err(LoadError::Decode(err)),
+    }
+}
+
+/// Choose internal and pixel format for channel count and bit depth of decoded image.
+///
+/// Only 8-bit RGB and RGBA have sRGB internal formats, so 8-bit grayscale
+/// images are expanded to them when sRGB is requested, and other images are
+/// always loaded as linear. Images with unusual layouts are converted to
+/// 8-bit RGBA first.
+#[cfg(feature = "image")]
+pub fn choose_format(decoded: DynamicImage, color_space: ColorSpace) -> (DynamicImage, InternalFormat, PixelFormat, PixelType) {
+    let srgb = color_space == ColorSpace::Srgb;
+    let (internal_format, format, pixel_type) = match decoded {
+        DynamicImage::ImageLuma8(_) if srgb => {
+            return choose_format(DynamicImage::ImageRgb8(decoded.to_rgb8()), color_space);
+        },
+        DynamicImage::ImageLumaA8(_) if srgb => {
+            return choose_format(DynamicImage::ImageRgba8(decoded.to_rgba8()), color_space);
+        },
+        DynamicImage::ImageLuma8(_) => (InternalFormat::R8, PixelFormat::Red, PixelType::UnsignedByte),
+        DynamicImage::ImageLumaA8(_) => (InternalFormat::RG8, PixelFormat::RG, PixelType::UnsignedByte),
+        DynamicImage::ImageRgb8(_) => (if srgb { InternalFormat::Srgb8 } else { InternalFormat::RGB8 }, PixelFormat::RGB, PixelType::UnsignedByte),
+        DynamicImage::ImageRgba8(_) => (if srgb { InternalFormat::Srgb8Alpha8 } else { InternalFormat::RGBA8 }, PixelFormat::RGBA, PixelType::UnsignedByte),
+        DynamicImage::ImageLuma16(_) => (InternalFormat::R16, PixelFormat::Red, PixelType::UnsignedShort),
+        DynamicImage::ImageLumaA16(_) => (InternalFormat::RG16, PixelFormat::RG, PixelType::UnsignedShort),
+        DynamicImage::ImageRgb16(_) => (InternalFormat::RGB16, PixelFormat::RGB, PixelType::UnsignedShort),
+        DynamicImage::ImageRgba16(_) => (InternalFormat::RGBA16, PixelFormat::RGBA, PixelType::UnsignedShort),
+        DynamicImage::ImageRgb32F(_) => (InternalFormat::RGB32F, PixelFormat::RGB, PixelType::Float),
+        DynamicImage::ImageRgba32F(_) => (InternalFormat::RGBA32F, PixelFormat::RGBA, PixelType::Float),
+        other => {
+            debug!("convert {:?} image to rgba8", other.color());
+            return choose_format(DynamicImage::ImageRgba8(other.to_rgba8()), color_space);
+        },
+    };
+    (decoded, internal_format, format, pixel_type)
+}
+
+/// Create 2D texture from decoded image.
+#[cfg(feature = "image")]
+pub fn texture_from_image(units: &mut TextureUnits, decoded: DynamicImage, options: &LoadOptions) -> Result<Texture2D, LoadError> {
+    let decoded = if options.flip_vertical { decoded.flipv() } else { decoded };
+    let (decoded, internal_format, format, pixel_type) = choose_format(decoded, options.color_space);
+
+    let size = Size::new_2d(decoded.width(), decoded.height());
+    let levels = if options.mipmaps { TextureTarget::Texture2D.mip_count(size) } else { 1 };
+
+    debug!("texture from {}x{} image, format = {:?}, levels = {}", size.width, size.height, internal_format, levels);
+
+    let texture = match Texture2D::new(units, levels, internal_format, size.width, size.height) {
+        Ok(texture) => texture,
+        Err(err) => return Err(LoadError::Texture(err)),
+    };
+
+    let result = match units.bind_for_update(TextureTarget::Texture2D, texture.get_texture()) {
+        Ok(mut binding) => binding.sub_image_raw(0, Region::whole(size), format, pixel_type, decoded.as_bytes()),
+        Err(err) => Err(err),
+    };
+    if let Err(err) = result {
+        return Err(LoadError::Texture(err));
+    }
+
+    if levels > 1 {
+        if let Err(err) = texture.generate_mipmap(units) {
+            return Err(LoadError::Texture(err));
+        }
+    }
+
+    Ok(texture)
+}
+
+/// Load KTX, KTX2 or DDS file with block-compressed mip chain.
+///
+/// Texture target is chosen from the container layout, query it with
+/// `Texture::get_target` before binding.
+pub fn load_compressed<T: AsRef<Path>>(units: &mut TextureUnits, path: T) -> Result<Texture, LoadError> {
+    debug!("load compressed texture {}", path.as_ref().display());
+
+    let mut data = Vec::new();
+    if let Err(err) = File::open(path).and_then(|mut file| file.read_to_end(&mut data)) {
+        return Err(LoadError::Io(err));
+    }
+
+    load_compressed_from_memory(units, &data)
+}
+
+/// Load KTX, KTX2 or DDS file contents from memory, detecting container from data.
+pub fn load_compressed_from_memory(units: &mut TextureUnits, data: &[u8]) -> Result<Texture, LoadError> {
+    let compressed = match container::parse(data) {
+        Ok(compressed) => compressed,
+        Err(err) => return Err(LoadError::Container(err)),
+    };
+
+    match texture_from_compressed(units, &compressed) {
+        Ok(texture) => Ok(texture),
+        Err(err) => Err(LoadError::Texture(err)),
+    }
+}
+
+/// Create texture from parsed compressed mip chain.
+///
+/// Fails with `TextureError::CompressedUnsupported` if the format is not
+/// listed in `GL_COMPRESSED_TEXTURE_FORMATS`.
+pub fn texture_from_compressed(units: &mut TextureUnits, data: &CompressedData) -> Result<Texture, TextureError> {
+    let texture = units.gen_one();
+    let levels = data.levels.len() as u32;
+
+    debug!("[{}]: texture from compressed {:?}, target = {:?}, size = {:?}, levels = {}",
+        texture.get_id(), data.format, data.target, data.size, levels);
+
+    let mut binding = match units.bind_for_update(data.target, &texture) {
+        Ok(binding) => binding,
+        Err(err) => return Err(err),
+    };
+
+    if let Err(err) = binding.allocate_compressed(levels, data.format, data.size) {
+        error!("[{}]: failed to create compressed texture, {}", texture.get_id(), err);
+        return Err(err);
+    }
+
+    for (level, level_data) in data.levels.iter().enumerate() {
+        let region = Region::whole(data.level_size(level as u32));
+        if let Err(err) = binding.compressed_sub_image(level as u32, region, level_data) {
+            return Err(err);
+        }
+    }
+
+    Ok(texture)
+}
+
+#[derive(Debug)]
+pub enum LoadError {
+    #[cfg(feature = "image")]
+    Decode(ImageError),
+    Texture(TextureError),
+    Container(ContainerError),
+    Io(io::Error),
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            #[cfg(feature = "image")]
+            &LoadError::Decode(ref err) => write!(f, "Failed to load image, {}", err),
+            &LoadError::Texture(ref err) => err.fmt(f),
+            &LoadError::Container(ref err) => err.fmt(f),
+            &LoadError::Io(ref err) => err.fmt(f),
+        }
+    }
+}
@@ -0,0 +1,396 @@
+use gl;
+use gl::types::*;
+use std::rc::Rc;
+use std::cell::Cell;
+use std::cmp;
+use std::fmt;
+
+use gli;
+use role;
+
+pub use self::units::TextureUnits;
+pub use self::format::{ InternalFormat, TextureFormat, FormatClass, FormatKind, PixelFormat, PixelType, Pixel, FormatError };
+pub use self::compressed::CompressedFormat;
+pub use self::container::{ CompressedData, ContainerError };
+pub use self::storage::{ Size, Region, TextureStorage, TextureBinding };
+pub use self::readback::PixelBuffer;
+pub use self::export::ExportError;
+pub use self::load::{ ColorSpace, LoadOptions, LoadError, load_compressed, load_compressed_from_memory };
+#[cfg(feature = "image")]
+pub use self::load::{ load_texture_2d, load_texture_2d_from_memory };
+pub use self::image_units::{ ImageUnits, ImageAccess, ImageBinding, ImageUnitError };
+pub use self::view::{ ViewRange, CopyImage, create_view, copy_image };
+pub use self::typed::{ Texture2D, Texture3D, Texture2DArray, TextureCube, TextureCubeArray, TextureRectangle };
+
+mod units;
+mod storage;
+mod typed;
+mod readback;
+mod load;
+mod view;
+mod image_units;
+pub mod export;
+pub mod format;
+pub mod compressed;
+pub mod container;
+
+impl role::Generator for TextureUnits {
+    type Object = Texture;
+}
+
+impl gli::Generate for TextureUnits {
+    fn gl_gen(size: usize) -> Vec<GLuint> {
+        let mut ids: Vec<GLuint> = vec![0; size];
+        unsafe { gl::GenTextures(size as GLsizei, ids.as_mut_ptr()) };
+        ids
+    }
+}
+
+impl gli::IntoObject<Texture> for Texture {
+    fn new_object(id: GLuint) -> Texture {
+        Texture::from_raw(id)
+    }
+}
+
+/// Texture binding target.
+///
+/// Images of layered targets store layers in `Size::depth`. Cube maps store
+/// their six faces the same way, and cube map arrays store `layers * 6`
+/// layer-faces.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum TextureTarget {
+    Texture1D,
+    Texture2D,
+    Texture3D,
+    Texture2DArray,
+    CubeMap,
+    CubeMapArray,
+    Rectangle,
+}
+
+impl TextureTarget {
+    pub fn to_gl(&self) -> GLenum {
+        match *self {
+            TextureTarget::Texture1D => gl::TEXTURE_1D,
+            TextureTarget::Texture2D => gl::TEXTURE_2D,
+            TextureTarget::Texture3D => gl::TEXTURE_3D,
+            TextureTarget::Texture2DArray => gl::TEXTURE_2D_ARRAY,
+            TextureTarget::CubeMap => gl::TEXTURE_CUBE_MAP,
+            TextureTarget::CubeMapArray => gl::TEXTURE_CUBE_MAP_ARRAY,
+            TextureTarget::Rectangle => gl::TEXTURE_RECTANGLE,
+        }
+    }
+
+    /// Number of dimensions used by `glTexStorage*` and `glTexImage*` calls for this target.
+    pub fn dimensions(&self) -> u32 {
+        match *self {
+            TextureTarget::Texture1D => 1,
+            TextureTarget::Texture2D
+            | TextureTarget::CubeMap
+            | TextureTarget::Rectangle => 2,
+            TextureTarget::Texture3D
+            | TextureTarget::Texture2DArray
+            | TextureTarget::CubeMapArray => 3,
+        }
+    }
+
+    /// Returns true if `Size::depth` counts layers or faces, which are not reduced by mipmapping.
+    pub fn is_layered(&self) -> bool {
+        match *self {
+            TextureTarget::Texture2DArray
+            | TextureTarget::CubeMap
+            | TextureTarget::CubeMapArray => true,
+            _ => false,
+        }
+    }
+
+    /// Returns true if faces are addressed with a separate target for each face.
+    pub fn is_cube_map(&self) -> bool {
+        *self == TextureTarget::CubeMap
+    }
+
+    /// Returns true if images may have more than one mip level.
+    pub fn has_mipmaps(&self) -> bool {
+        *self != TextureTarget::Rectangle
+    }
+
+    /// Returns true if `size` is valid for this target.
+    pub fn is_valid_size(&self, size: Size) -> bool {
+        if size.width == 0 || size.height == 0 || size.depth == 0 {
+            return false;
+        }
+        match *self {
+            TextureTarget::Texture1D => size.height == 1 && size.depth == 1,
+            TextureTarget::Texture2D
+            | TextureTarget::Rectangle => size.depth == 1,
+            TextureTarget::Texture3D
+            | TextureTarget::Texture2DArray => true,
+            TextureTarget::CubeMap => size.width == size.height && size.depth == 6,
+            TextureTarget::CubeMapArray => size.width == size.height && size.depth % 6 == 0,
+        }
+    }
+
+    /// Size of mip `level` of an image of `size`.
+    pub fn mip_size(&self, size: Size, level: u32) -> Size {
+        let shrink = |value: u32| { let value = value >> level; if value == 0 { 1 } else { value } };
+        Size {
+            width: shrink(size.width),
+            height: shrink(size.height),
+            depth: if self.is_layered() { size.depth } else { shrink(size.depth) },
+        }
+    }
+
+    /// Number of levels in a full mip chain of an image of `size`.
+    pub fn mip_count(&self, size: Size) -> u32 {
+        if !self.has_mipmaps() {
+            return 1;
+        }
+        let depth = if self.is_layered() { 1 } else { size.depth };
+        let largest = cmp::max(size.width, cmp::max(size.height, depth));
+        32 - largest.leading_zeros()
+    }
+
+    /// Returns true if textures of this target can be viewed as `target`.
+    pub fn can_view_as(&self, target: TextureTarget) -> bool {
+        match *self {
+            TextureTarget::Texture1D => target == TextureTarget::Texture1D,
+            TextureTarget::Texture3D => target == TextureTarget::Texture3D,
+            TextureTarget::Rectangle => target == TextureTarget::Rectangle,
+            TextureTarget::Texture2D => target == TextureTarget::Texture2D || target == TextureTarget::Texture2DArray,
+            TextureTarget::Texture2DArray
+            | TextureTarget::CubeMap
+            | TextureTarget::CubeMapArray => match target {
+                TextureTarget::Texture2D
+                | TextureTarget::Texture2DArray
+                | TextureTarget::CubeMap
+                | TextureTarget::CubeMapArray => true,
+                _ => false,
+            },
+        }
+    }
+
+    /// Target passed to image calls for layer or face `z`.
+    fn image_target(&self, z: u32) -> GLenum {
+        if self.is_cube_map() {
+            gl::TEXTURE_CUBE_MAP_POSITIVE_X + z
+        } else {
+            self.to_gl()
+        }
+    }
+
+    /// Position of target in per-unit binding table.
+    fn index(&self) -> usize {
+        match *self {
+            TextureTarget::Texture1D => 0,
+            TextureTarget::Texture2D => 1,
+            TextureTarget::Texture3D => 2,
+            TextureTarget::Texture2DArray => 3,
+            TextureTarget::CubeMap => 4,
+            TextureTarget::CubeMapArray => 5,
+            TextureTarget::Rectangle => 6,
+        }
+    }
+
+    /// All targets, ordered by `index`.
+    fn all() -> &'static [TextureTarget] {
+        static ALL: [TextureTarget; 7] = [
+            TextureTarget::Texture1D,
+            TextureTarget::Texture2D,
+            TextureTarget::Texture3D,
+            TextureTarget::Texture2DArray,
+            TextureTarget::CubeMap,
+            TextureTarget::CubeMapArray,
+            TextureTarget::Rectangle,
+        ];
+        &ALL
+    }
+}
+
+/// Face of a cube map, in the order of layer-faces.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum CubeFace {
+    PositiveX,
+    NegativeX,
+    PositiveY,
+    NegativeY,
+    PositiveZ,
+    NegativeZ,
+}
+
+impl CubeFace {
+    pub fn to_gl(&self) -> GLenum {
+        gl::TEXTURE_CUBE_MAP_POSITIVE_X + self.index()
+    }
+
+    /// Index of face within a cube, used as layer-face offset.
+    pub fn index(&self) -> u32 {
+        match *self {
+            CubeFace::PositiveX => 0,
+            CubeFace::NegativeX => 1,
+            CubeFace::PositiveY => 2,
+            CubeFace::NegativeY => 3,
+            CubeFace::PositiveZ => 4,
+            CubeFace::NegativeZ => 5,
+        }
+    }
+
+    pub fn all() -> &'static [CubeFace] {
+        static ALL: [CubeFace; 6] = [
+            CubeFace::PositiveX,
+            CubeFace::NegativeX,
+            CubeFace::PositiveY,
+            CubeFace::NegativeY,
+            CubeFace::PositiveZ,
+            CubeFace::NegativeZ,
+        ];
+        &ALL
+    }
+}
+
+/// Raw texture object wrapper to hide RAII mechanism.
+struct Raw {
+    id: GLuint,
+    /// Target the texture was first bound to, which fixes its type.
+    target: Cell<Option<TextureTarget>>,
+    /// Format and size of allocated images.
+    storage: Cell<Option<TextureStorage>>,
+}
+
+impl Drop for Raw {
+
+    /// Delete texture objects.
+    ///
+    /// ## glDeleteTextures
+    ///
+    /// - OpenGL Version 2.0
+    /// - OpenGL ES Version 2.0
+    ///
+    fn drop(&mut self) {
+        debug!("[{}]: cleanup && delete", self.id);
+        unsafe { gl::DeleteTextures(1, &mut self.id) };
+    }
+}
+
+/// Manipulates OpenGL texture object.
+#[derive(Clone)]
+pub struct Texture {
+    raw: Rc<Raw>,
+}
+
+impl Texture {
+
+    /// Create from raw name.
+    pub fn from_raw(id: GLuint) -> Texture {
+        Texture { raw: Rc::new(Raw { id: id, target: Cell::new(None), storage: Cell::new(None) }) }
+    }
+
+    /// Get raw name.
+    pub fn get_id(&self) -> GLuint {
+        self.raw.id
+    }
+
+    /// Get target the texture was bound to first, if it was bound.
+    pub fn get_target(&self) -> Option<TextureTarget> {
+        self.raw.target.get()
+    }
+
+    /// Get format and size of allocated images, if they were allocated.
+    pub fn get_storage(&self) -> Option<TextureStorage> {
+        self.raw.storage.get()
+    }
+
+    fn set_storage(&self, storage: TextureStorage) {
+        self.raw.storage.set(Some(storage));
+    }
+
+    /// Remember `target` as the texture type, or check that it matches the known one.
+    fn claim_target(&self, target: TextureTarget) -> Result<(), TextureError> {
+        match self.raw.target.get() {
+            None => {
+                self.raw.target.set(Some(target));
+                Ok(())
+            },
+            Some(existing) if existing == target => Ok(()),
+            Some(existing) => Err(TextureError::TargetMismatch {
+                id: self.get_id(),
+                existing: existing,
+                requested: target,
+            }),
+        }
+    }
+
+    /// Determine if a name corresponds to a texture object.
+    ///
+    /// Returns true if contains correct texture object and it
+    /// was bound at least once.
+    ///
+    /// ## glIsTexture
+    ///
+    /// - OpenGL Version 2.0
+    /// - OpenGL ES Version 2.0
+    ///
+    pub fn is_texture(&self) -> bool {
+        unsafe { gl::IsTexture(self.get_id()) == gl::TRUE }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TextureError {
+    UnitOutOfRange { unit: GLuint, count: GLuint },
+    TargetMismatch { id: GLuint, existing: TextureTarget, requested: TextureTarget },
+    StorageUnsupported,
+    StorageImmutable(GLuint),
+    NoStorage(GLuint),
+    FormatMismatch { existing: TextureFormat, requested: TextureFormat },
+    InvalidSize { target: TextureTarget, size: Size },
+    InvalidLevelCount { levels: u32, max: u32 },
+    LevelOutOfRange { level: u32, levels: u32 },
+    RegionOutOfBounds { level: u32, region: Region, size: Size },
+    NotFilterable(InternalFormat),
+    NoMipmaps(TextureTarget),
+    Format(FormatError),
+    CompressedUnsupported(CompressedFormat),
+    InvalidCompressedFormat(CompressedFormat),
+    NotUncompressed(CompressedFormat),
+    NotCompressed(InternalFormat),
+    CompressedDataSize { expected: usize, actual: usize },
+    RegionNotBlockAligned { region: Region, block: (u32, u32) },
+    ViewUnsupported,
+    CopyImageUnsupported,
+    NotImmutable(GLuint),
+    ViewTargetIncompatible { original: TextureTarget, view: TextureTarget },
+    FormatIncompatible { source: TextureFormat, destination: TextureFormat },
+    LayerOutOfRange { target: TextureTarget, min_layer: u32, layers: u32, available: u32 },
+}
+
+impl fmt::Display for TextureError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            &TextureError::UnitOutOfRange { unit, count } => write!(f, "Tried to use texture unit {}, but there are only {} units.", unit, count),
+            &TextureError::TargetMismatch { id, existing, requested } => write!(f, "Tried to bind texture {} of {:?} target to {:?} target.", id, existing, requested),
+            &TextureError::StorageUnsupported => "Immutable texture storage requires OpenGL 4.2, OpenGL ES 3.0 or ARB_texture_storage.".fmt(f),
+            &TextureError::StorageImmutable(id) => write!(f, "Tried to redefine images of texture {} with immutable storage.", id),
+            &TextureError::NoStorage(id) => write!(f, "Tried to use images of texture {} before allocating them.", id),
+            &TextureError::FormatMismatch { existing, requested } => write!(f, "Tried to define {:?} mip level of texture with {:?} format.", requested, existing),
+            &TextureError::InvalidSize { target, size } => write!(f, "Size {}x{}x{} is not valid for {:?} texture.", size.width, size.height, size.depth, target),
+            &TextureError::InvalidLevelCount { levels, max } => write!(f, "Tried to allocate {} mip levels, but the size allows between 1 and {}.", levels, max),
+            &TextureError::LevelOutOfRange { level, levels } => write!(f, "Tried to use mip level {} of texture with {} levels.", level, levels),
+            &TextureError::RegionOutOfBounds { level, region, size } => write!(f, "Region at {},{},{} of size {}x{}x{} is outside of level {} of size {}x{}x{}.", region.x, region.y, region.z, region.size.width, region.size.height, region.size.depth, level, size.width, size.height, size.depth),
+            &TextureError::NotFilterable(format) => write!(f, "Mipmaps can not be generated for {:?} format which is not filterable.", format),
+            &TextureError::NoMipmaps(target) => write!(f, "Textures of {:?} target can not have mipmaps.", target),
+            &TextureError::Format(ref err) => err.fmt(f),
+            &TextureError::CompressedUnsupported(format) => write!(f, "Compressed format {:?} is not listed in GL_COMPRESSED_TEXTURE_FORMATS of this context.", format),
+            &TextureError::InvalidCompressedFormat(format) => write!(f, "Compressed format {:?} does not exist.", format),
+            &TextureError::NotUncompressed(format) => write!(f, "Tried to transfer pixels of texture with compressed {:?} format.", format),
+            &TextureError::NotCompressed(format) => write!(f, "Tried to upload compressed data to texture with uncompressed {:?} format.", format),
+            &TextureError::CompressedDataSize { expected, actual } => write!(f, "Compressed image requires {} bytes of data, but {} were given.", expected, actual),
+            &TextureError::RegionNotBlockAligned { region, block } => write!(f, "Region at {},{} of size {}x{} is not aligned to {}x{} compressed blocks.", region.x, region.y, region.size.width, region.size.height, block.0, block.1),
+            &TextureError::ViewUnsupported => "Texture views require OpenGL 4.3 or ARB_texture_view.".fmt(f),
+            &TextureError::CopyImageUnsupported => "Copying images requires OpenGL 4.3, OpenGL ES 3.2 or ARB_copy_image.".fmt(f),
+            &TextureError::NotImmutable(id) => write!(f, "Tried to create view of texture {} without immutable storage.", id),
+            &TextureError::ViewTargetIncompatible { original, view } => write!(f, "Textures of {:?} target can not be viewed as {:?} target.", original, view),
+            &TextureError::FormatIncompatible { source, destination } => write!(f, "Format {:?} is not in the same compatibility class as {:?}.", destination, source),
+            &TextureError::LayerOutOfRange { target, min_layer, layers, available } => write!(f, "Tried to use {} layers from layer {} for {:?} view of texture with {} layers.", layers, min_layer, target, available),
+        }
+    }
+}
@@ -0,0 +1,123 @@
+use std::fs::File;
+use std::io::Write;
+use std::mem;
+use std::path::Path;
+use std::ptr;
+use std::slice;
+
+use super::format::Pixel;
+use super::export::{ self, ExportError };
+
+/// Two-dimensional image of typed pixels, with rows ordered top to bottom.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PixelBuffer<P> {
+    width: u32,
+    height: u32,
+    pixels: Vec<P>,
+}
+
+impl<P: Pixel> PixelBuffer<P> {
+
+    /// Create from pixels ordered top to bottom.
+    ///
+    /// Returns `None` if number of pixels does not match size.
+    pub fn new(width: u32, height: u32, pixels: Vec<P>) -> Option<PixelBuffer<P>> {
+        if pixels.len() != width as usize * height as usize {
+            return None;
+        }
+        Some(PixelBuffer { width: width, height: height, pixels: pixels })
+    }
+
+    /// Create from OpenGL rows, which start at the bottom and are `stride` bytes apart.
+    pub fn from_gl_rows(width: u32, height: u32, data: &[u8], stride: usize) -> PixelBuffer<P> {
+        let row = width as usize * mem::size_of::<P>();
+        let count = width as usize * height as usize;
+
+        let mut pixels: Vec<P> = Vec::with_capacity(count);
+        unsafe {
+            let target = pixels.as_mut_ptr() as *mut u8;
+            for y in 0..height as usize {
+                let source = &data[(height as usize - 1 - y) * stride..][..row];
+                ptr::copy_nonoverlapping(source.as_ptr(), target.offset((y * row) as isize), row);
+            }
+            pixels.set_len(count);
+        }
+
+        PixelBuffer { width: width, height: height, pixels: pixels }
+    }
+
+    pub fn get_width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn get_height(&self) -> u32 {
+        self.height
+    }
+
+    /// Get pixels ordered top to bottom.
+    pub fn get_pixels(&self) -> &[P] {
+        &self.pixels
+    }
+
+    /// Get pixel at column `x` of row `y`, counted from the top.
+    pub fn get(&self, x: u32, y: u32) -> Option<P> {
+        if x >= self.width || y >= self.height {
+            return None;
+        }
+        Some(self.pixels[(y * self.width + x) as usize])
+    }
+
+    /// Flip rows, converting between top-left and bottom-left origin.
+    pub fn flip_vertical(&mut self) {
+        let width = self.width as usize;
+        let height = self.height as usize;
+        for y in 0..height / 2 {
+            for x in 0..width {
+                self.pixels.swap(y * width + x, (height - 1 - y) * width + x);
+            }
+        }
+    }
+
+    fn as_bytes(&self) -> &[u8] {
+        unsafe { slice::from_raw_parts(self.pixels.as_ptr() as *const u8, self.pixels.len() * mem::size_of::<P>()) }
+    }
+
+    /// Write as PNG, for 8 and 16 bit normalized pixels.
+    pub fn write_png<W: Write>(&self, writer: &mut W) -> Result<(), ExportError> {
+        export::write_png(writer, self.width, self.height, P::format(), P::pixel_type(), self.as_bytes())
+    }
+
+    /// Write as binary PPM, or PGM for single channel pixels.
+    pub fn write_ppm<W: Write>(&self, writer: &mut W) -> Result<(), ExportError> {
+        export::write_ppm(writer, self.width, self.height, P::format(), P::pixel_type(), self.as_bytes())
+    }
+
+    /// Write as PFM, for float pixels.
+    pub fn write_pfm<W: Write>(&self, writer: &mut W) -> Result<(), ExportError> {
+        export::write_pfm(writer, self.width, self.height, P::format(), P::pixel_type(), self.as_bytes())
+    }
+
+    /// Save as PNG file.
+    pub fn save_png<T: AsRef<Path>>(&self, path: T) -> Result<(), ExportError> {
+        match File::create(path) {
+            Ok(mut file) => self.write_png(&mut file),
+            Err(err) => Err(ExportError::Io(err)),
+        }
+    }
+
+    /// Save as PPM file.
+    pub fn save_ppm<T: AsRef<Path>>(&self, path: T) -> Result<(), ExportError> {
+        match File::create(path) {
+            Ok(mut file) => self.write_ppm(&mut file),
+            Err(err) => Err(ExportError::Io(err)),
+        }
+    }
+
+    /// Save as PFM file.
+    pub fn save_pfm<T: AsRef<Path>>(&self, path: T) -> Result<(), ExportError> {
+        match File::create(path) {
+            Ok(mut file) => self.write_pfm(&mut file),
+            Err(err) => Err(ExportError::Io(err)),
+        }
+    }
+}
@@ -0,0 +1,789 @@
+use gl;
+use gl::types::*;
+
+use std::cmp;
+use std::mem;
+use std::ptr;
+use std::slice;
+
+use super::{ Texture, TextureTarget, TextureError, TextureUnits };
+use super::format::{ self, InternalFormat, TextureFormat, FormatKind, PixelFormat, PixelType, Pixel };
+use super::compressed::CompressedFormat;
+use super::readback::PixelBuffer;
+
+/// Size of a texture image in texels.
+///
+/// Unused dimensions are 1, such as `depth` of 2D images.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct Size {
+    pub width: u32,
+    pub height: u32,
+    pub depth: u32,
+}
+
+impl Size {
+    pub fn new(width: u32, height: u32, depth: u32) -> Size {
+        Size { width: width, height: height, depth: depth }
+    }
+
+    pub fn new_1d(width: u32) -> Size {
+        Size::new(width, 1, 1)
+    }
+
+    pub fn new_2d(width: u32, height: u32) -> Size {
+        Size::new(width, height, 1)
+    }
+
+    /// Number of texels.
+    pub fn texels(&self) -> usize {
+        self.width as usize * self.height as usize * self.depth as usize
+    }
+}
+
+/// Box of texels within a texture image.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct Region {
+    pub x: u32,
+    pub y: u32,
+    pub z: u32,
+    pub size: Size,
+}
+
+impl Region {
+    pub fn new(x: u32, y: u32, z: u32, size: Size) -> Region {
+        Region { x: x, y: y, z: z, size: size }
+    }
+
+    /// Rectangle at `x`, `y` of a two-dimensional image.
+    pub fn new_2d(x: u32, y: u32, width: u32, height: u32) -> Region {
+        Region::new(x, y, 0, Size::new_2d(width, height))
+    }
+
+    /// Region covering a whole image of `size`.
+    pub fn whole(size: Size) -> Region {
+        Region::new(0, 0, 0, size)
+    }
+
+    /// Returns true if region fits into image of `size`.
+    pub fn fits(&self, size: Size) -> bool {
+        self.x as u64 + self.size.width as u64 <= size.width as u64
+            && self.y as u64 + self.size.height as u64 <= size.height as u64
+            && self.z as u64 + self.size.depth as u64 <= size.depth as u64
+    }
+}
+
+/// Format and size of allocated texture images.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct TextureStorage {
+    pub target: TextureTarget,
+    pub format: TextureFormat,
+    /// Size of the base level.
+    pub size: Size,
+    /// Number of allocated mip levels.
+    pub levels: u32,
+    /// True if allocated with `glTexStorage*` and can not be redefined.
+    pub immutable: bool,
+}
+
+impl TextureStorage {
+    /// Size of mip `level`.
+    pub fn level_size(&self, level: u32) -> Size {
+        self.target.mip_size(self.size, level)
+    }
+
+    fn check_level(&self, level: u32) -> Result<(), TextureError> {
+        if level >= self.levels {
+            return Err(TextureError::LevelOutOfRange { level: level, levels: self.levels });
+        }
+        Ok(())
+    }
+
+    /// Get format for pixel transfers, failing for compressed formats.
+    fn uncompressed_format(&self) -> Result<InternalFormat, TextureError> {
+        match self.format {
+            TextureFormat::Uncompressed(format) => Ok(format),
+            TextureFormat::Compressed(format) => Err(TextureError::NotUncompressed(format)),
+        }
+    }
+
+    /// Get format for compressed uploads, failing for uncompressed formats.
+    fn compressed_format(&self) -> Result<CompressedFormat, TextureError> {
+        match self.format {
+            TextureFormat::Compressed(format) => Ok(format),
+            TextureFormat::Uncompressed(format) => Err(TextureError::NotCompressed(format)),
+        }
+    }
+}
+
+/// Allocates and uploads texture images while texture is bound to the active unit.
+pub struct TextureBinding<'a> {
+    units: &'a mut TextureUnits,
+    target: TextureTarget,
+    texture: Texture,
+}
+
+/// Create binding for texture already bound to the active unit.
+pub fn new_binding<'a>(units: &'a mut TextureUnits, target: TextureTarget, texture: Texture) -> TextureBinding<'a> {
+    TextureBinding {
+        units: units,
+        target: target,
+        texture: texture,
+    }
+}
+
+impl<'a> TextureBinding<'a> {
+    pub fn get_texture(&self) -> &Texture {
+        &self.texture
+    }
+
+    pub fn get_target(&self) -> TextureTarget {
+        self.target
+    }
+
+    fn check_size(&self, size: Size) -> Result<(), TextureError> {
+        if !self.target.is_valid_size(size) {
+            return Err(TextureError::InvalidSize { target: self.target, size: size });
+        }
+        Ok(())
+    }
+
+    fn check_mutable(&self) -> Result<Option<TextureStorage>, TextureError> {
+        match self.texture.get_storage() {
+            Some(storage) if storage.immutable => Err(TextureError::StorageImmutable(self.texture.get_id())),
+            storage => Ok(storage),
+        }
+    }
+
+    fn get_storage(&self) -> Result<TextureStorage, TextureError> {
+        match self.texture.get_storage() {
+            Some(storage) => Ok(storage),
+            None => Err(TextureError::NoStorage(self.texture.get_id())),
+        }
+    }
+
+    /// Allocate immutable storage for `levels` mip levels.
+    ///
+    /// ## glTexStorage1D, glTexStorage2D, glTexStorage3D
+    ///
+    /// - OpenGL Version 4.2
+    /// - OpenGL ES Version 3.0
+    ///
+    pub fn storage(&mut self, levels: u32, internal_format: InternalFormat, size: Size) -> Result<(), TextureError> {
+        self.storage_format(levels, TextureFormat::Uncompressed(internal_format), size)
+    }
+
+    /// Allocate immutable storage for `levels` mip levels of compressed format.
+    ///
+    /// ## glTexStorage1D, glTexStorage2D, glTexStorage3D
+    ///
+    /// - OpenGL Version 4.2
+    /// - OpenGL ES Version 3.0
+    ///
+    pub fn storage_compressed(&mut self, levels: u32, compressed_format: CompressedFormat, size: Size) -> Result<(), TextureError> {
+        if let Err(err) = self.check_compressed_supported(compressed_format) {
+            return Err(err);
+        }
+        self.storage_format(levels, TextureFormat::Compressed(compressed_format), size)
+    }
+
+    fn storage_format(&mut self, levels: u32, internal_format: TextureFormat, size: Size) -> Result<(), TextureError> {
+        if !self.units.is_storage_supported() {
+            return Err(TextureError::StorageUnsupported);
+        }
+
+        if let Err(err) = self.check_mutable() {
+            return Err(err);
+        }
+
+        if let Err(err) = self.check_size(size) {
+            return Err(err);
+        }
+
+        let max = self.target.mip_count(size);
+        if levels == 0 || levels > max {
+            return Err(TextureError::InvalidLevelCount { levels: levels, max: max });
+        }
+
+        debug!("[{}]: storage, levels = {}, format = {:?}, size = {:?}", self.texture.get_id(), levels, internal_format, size);
+
+        unsafe {
+            match self.target.dimensions() {
+                1 => gl::TexStorage1D(self.target.to_gl(), levels as GLsizei, internal_format.to_gl(),
+                    size.width as GLsizei),
+                2 => gl::TexStorage2D(self.target.to_gl(), levels as GLsizei, internal_format.to_gl(),
+                    size.width as GLsizei, size.height as GLsizei),
+                _ => gl::TexStorage3D(self.target.to_gl(), levels as GLsizei, internal_format.to_gl(),
+                    size.width as GLsizei, size.height as GLsizei, size.depth as GLsizei),
+            }
+        };
+
+        self.texture.set_storage(TextureStorage {
+            target: self.target,
+            format: internal_format,
+            size: size,
+            levels: levels,
+            immutable: true,
+        });
+
+        Ok(())
+    }
+
+    /// Allocate `levels` mip levels, with immutable storage if it is available.
+    ///
+    /// Falls back to defining each level with `glTexImage*` on OpenGL versions
+    /// below 4.2.
+    pub fn allocate(&mut self, levels: u32, internal_format: InternalFormat, size: Size) -> Result<(), TextureError> {
+        if self.units.is_storage_supported() {
+            return self.storage(levels, internal_format, size);
+        }
+
+        if let Err(err) = self.check_size(size) {
+            return Err(err);
+        }
+
+        let max = self.target.mip_count(size);
+        if levels == 0 || levels > max {
+            return Err(TextureError::InvalidLevelCount { levels: levels, max: max });
+        }
+
+        for level in 0..levels {
+            let level_size = self.target.mip_size(size, level);
+            if let Err(err) = self.image::<u8>(level, internal_format, level_size, None) {
+                return Err(err);
+            }
+        }
+
+        debug!("[{}]: max level {}", self.texture.get_id(), levels - 1);
+        unsafe { gl::TexParameteri(self.target.to_gl(), gl::TEXTURE_MAX_LEVEL, (levels - 1) as GLint) };
+
+        Ok(())
+    }
+
+    /// Allocate `levels` mip levels of compressed format, with immutable storage if it is available.
+    ///
+    /// Falls back to defining each level with `glCompressedTexImage*` on
+    /// OpenGL versions below 4.2.
+    pub fn allocate_compressed(&mut self, levels: u32, compressed_format: CompressedFormat, size: Size) -> Result<(), TextureError> {
+        if self.units.is_storage_supported() {
+            return self.storage_compressed(levels, compressed_format, size);
+        }
+
+        if let Err(err) = self.check_size(size) {
+            return Err(err);
+        }
+
+        let max = self.target.mip_count(size);
+        if levels == 0 || levels > max {
+            return Err(TextureError::InvalidLevelCount { levels: levels, max: max });
+        }
+
+        for level in 0..levels {
+            let level_size = self.target.mip_size(size, level);
+            if let Err(err) = self.compressed_image(level, compressed_format, level_size, None) {
+                return Err(err);
+            }
+        }
+
+        debug!("[{}]: max level {}", self.texture.get_id(), levels - 1);
+        unsafe { gl::TexParameteri(self.target.to_gl(), gl::TEXTURE_MAX_LEVEL, (levels - 1) as GLint) };
+
+        Ok(())
+    }
+
+    /// Define mutable image of mip `level`, optionally uploading `data`.
+    ///
+    /// Level 0 defines format and base size of the texture, other levels
+    /// must match them.
+    ///
+    /// ## glTexImage1D, glTexImage2D, glTexImage3D
+    ///
+    /// - OpenGL Version 2.0
+    /// - OpenGL ES Version 2.0
+    ///
+    pub fn image<P: Pixel>(&mut self, level: u32, internal_format: InternalFormat, size: Size, data: Option<&[P]>) -> Result<(), TextureError> {
+        let storage = match self.check_mutable() {
+            Ok(storage) => storage,
+            Err(err) => return Err(err),
+        };
+
+        if let Err(err) = self.check_size(size) {
+            return Err(err);
+        }
+
+        let storage = match self.define_level(storage, level, TextureFormat::Uncompressed(internal_format), size) {
+            Ok(storage) => storage,
+            Err(err) => return Err(err),
+        };
+
+        let (pixel_format, pixel_type, pixels) = match data {
+            Some(data) => {
+                let bytes = as_bytes(data);
+                match self.prepare_unpack(internal_format, P::format(), P::pixel_type(), size, bytes.len()) {
+                    Ok(()) => (P::format(), P::pixel_type(), bytes.as_ptr()),
+                    Err(err) => return Err(err),
+                }
+            },
+            None => {
+                let (formats, types) = internal_format.transfer_types();
+                (formats[0], types[0], ptr::null())
+            },
+        };
+
+        debug!("[{}]: image, level = {}, format = {:?}, size = {:?}", self.texture.get_id(), level, internal_format, size);
+
+        unsafe {
+            match self.target.dimensions() {
+                1 => gl::TexImage1D(self.target.to_gl(), level as GLint, internal_format.to_gl() as GLint,
+                    size.width as GLsizei, 0,
+                    pixel_format.to_gl(), pixel_type.to_gl(), pixels as *const _),
+                2 if self.target.is_cube_map() => {
+                    let face_stride = self.face_stride(pixel_format, pixel_type, size);
+                    for face in 0..size.depth {
+                        let face_pixels = if pixels.is_null() { pixels } else { pixels.offset((face as usize * face_stride) as isize) };
+                        gl::TexImage2D(self.target.image_target(face), level as GLint, internal_format.to_gl() as GLint,
+                            size.width as GLsizei, size.height as GLsizei, 0,
+                            pixel_format.to_gl(), pixel_type.to_gl(), face_pixels as *const _);
+                    }
+                },
+                2 => gl::TexImage2D(self.target.to_gl(), level as GLint, internal_format.to_gl() as GLint,
+                    size.width as GLsizei, size.height as GLsizei, 0,
+                    pixel_format.to_gl(), pixel_type.to_gl(), pixels as *const _),
+                _ => gl::TexImage3D(self.target.to_gl(), level as GLint, internal_format.to_gl() as GLint,
+                    size.width as GLsizei, size.height as GLsizei, size.depth as GLsizei, 0,
+                    pixel_format.to_gl(), pixel_type.to_gl(), pixels as *const _),
+            }
+        };
+
+        self.texture.set_storage(storage);
+
+        Ok(())
+    }
+
+    /// Storage after defining mutable mip `level` of `internal_format` and `size`.
+    fn define_level(&self, storage: Option<TextureStorage>, level: u32, internal_format: TextureFormat, size: Size) -> Result<TextureStorage, TextureError> {
+        match storage {
+            Some(storage) if level > 0 => {
+                if storage.format != internal_format {
+                    return Err(TextureError::FormatMismatch { existing: storage.format, requested: internal_format });
+                }
+                if storage.level_size(level) != size {
+                    return Err(TextureError::InvalidSize { target: self.target, size: size });
+                }
+                Ok(TextureStorage {
+                    levels: if level + 1 > storage.levels { level + 1 } else { storage.levels },
+                    .. storage
+                })
+            },
+            _ if level > 0 => Err(TextureError::NoStorage(self.texture.get_id())),
+            _ => Ok(TextureStorage {
+                target: self.target,
+                format: internal_format,
+                size: size,
+                levels: 1,
+                immutable: false,
+            }),
+        }
+    }
+
+    /// Upload tightly packed `data` to `region` of mip `level`.
+    ///
+    /// For layered targets `region.z` selects the first layer, or layer-face of
+    /// cube maps and cube map arrays.
+    ///
+    /// ## glTexSubImage1D, glTexSubImage2D, glTexSubImage3D
+    ///
+    /// - OpenGL Version 2.0
+    /// - OpenGL ES Version 2.0
+    ///
+    pub fn sub_image<P: Pixel>(&mut self, level: u32, region: Region, data: &[P]) -> Result<(), TextureError> {
+        self.sub_image_raw(level, region, P::format(), P::pixel_type(), as_bytes(data))
+    }
+
+    /// Upload tightly packed bytes in `pixel_format` and `pixel_type` to `region` of mip `level`.
+    ///
+    /// ## glTexSubImage1D, glTexSubImage2D, glTexSubImage3D
+    ///
+    /// - OpenGL Version 2.0
+    /// - OpenGL ES Version 2.0
+    ///
+    pub fn sub_image_raw(&mut self, level: u32, region: Region, pixel_format: PixelFormat, pixel_type: PixelType, data: &[u8]) -> Result<(), TextureError> {
+        let storage = match self.get_storage() {
+            Ok(storage) => storage,
+            Err(err) => return Err(err),
+        };
+
+        if let Err(err) = storage.check_level(level) {
+            return Err(err);
+        }
+
+        let level_size = storage.level_size(level);
+        if !region.fits(level_size) {
+            return Err(TextureError::RegionOutOfBounds { level: level, region: region, size: level_size });
+        }
+
+        let internal_format = match storage.uncompressed_format() {
+            Ok(internal_format) => internal_format,
+            Err(err) => return Err(err),
+        };
+
+        if let Err(err) = self.prepare_unpack(internal_format, pixel_format, pixel_type, region.size, data.len()) {
+            return Err(err);
+        }
+
+        debug!("[{}]: sub image, level = {}, region = {:?}", self.texture.get_id(), level, region);
+
+        unsafe {
+            match self.target.dimensions() {
+                1 => gl::TexSubImage1D(self.target.to_gl(), level as GLint,
+                    region.x as GLint,
+                    region.size.width as GLsizei,
+                    pixel_format.to_gl(), pixel_type.to_gl(), data.as_ptr() as *const _),
+                2 if self.target.is_cube_map() => {
+                    let face_stride = self.face_stride(pixel_format, pixel_type, region.size);
+                    for face in 0..region.size.depth {
+                        gl::TexSubImage2D(self.target.image_target(region.z + face), level as GLint,
+                            region.x as GLint, region.y as GLint,
+                            region.size.width as GLsizei, region.size.height as GLsizei,
+                            pixel_format.to_gl(), pixel_type.to_gl(), data[face as usize * face_stride..].as_ptr() as *const _);
+                    }
+                },
+                2 => gl::TexSubImage2D(self.target.to_gl(), level as GLint,
+                    region.x as GLint, region.y as GLint,
+                    region.size.width as GLsizei, region.size.height as GLsizei,
+                    pixel_format.to_gl(), pixel_type.to_gl(), data.as_ptr() as *const _),
+                _ => gl::TexSubImage3D(self.target.to_gl(), level as GLint,
+                    region.x as GLint, region.y as GLint, region.z as GLint,
+                    region.size.width as GLsizei, region.size.height as GLsizei, region.size.depth as GLsizei,
+                    pixel_format.to_gl(), pixel_type.to_gl(), data.as_ptr() as *const _),
+            }
+        };
+
+        Ok(())
+    }
+
+    fn check_compressed_supported(&mut self, compressed_format: CompressedFormat) -> Result<(), TextureError> {
+        if !compressed_format.is_valid() {
+            error!("[{}]: compressed format {:?} is not valid", self.texture.get_id(), compressed_format);
+            return Err(TextureError::InvalidCompressedFormat(compressed_format));
+        }
+        if !self.units.is_compressed_format_supported(compressed_format) {
+            error!("[{}]: compressed format {:?} is not supported", self.texture.get_id(), compressed_format);
+            return Err(TextureError::CompressedUnsupported(compressed_format));
+        }
+        Ok(())
+    }
+
+    fn check_compressed_size(&self, expected: usize, actual: usize) -> Result<(), TextureError> {
+        if expected != actual {
+            error!("[{}]: compressed upload error, expected {} bytes, got {}", self.texture.get_id(), expected, actual);
+            return Err(TextureError::CompressedDataSize { expected: expected, actual: actual });
+        }
+        Ok(())
+    }
+
+    /// Define mutable compressed image of mip `level`, optionally uploading `data`.
+    ///
+    /// Level 0 defines format and base size of the texture, other levels
+    /// must match them. Cube map `data` stores faces one after another.
+    ///
+    /// ## glCompressedTexImage1D, glCompressedTexImage2D, glCompressedTexImage3D
+    ///
+    /// - OpenGL Version 2.0
+    /// - OpenGL ES Version 2.0
+    ///
+    pub fn compressed_image(&mut self, level: u32, compressed_format: CompressedFormat, size: Size, data: Option<&[u8]>) -> Result<(), TextureError> {
+        if let Err(err) = self.check_compressed_supported(compressed_format) {
+            return Err(err);
+        }
+
+        let storage = match self.check_mutable() {
+            Ok(storage) => storage,
+            Err(err) => return Err(err),
+        };
+
+        if let Err(err) = self.check_size(size) {
+            return Err(err);
+        }
+
+        let storage = match self.define_level(storage, level, TextureFormat::Compressed(compressed_format), size) {
+            Ok(storage) => storage,
+            Err(err) => return Err(err),
+        };
+
+        let image_size = compressed_format.image_size(size);
+        let pixels = match data {
+            Some(data) => match self.check_compressed_size(image_size, data.len()) {
+                Ok(()) => data.as_ptr(),
+                Err(err) => return Err(err),
+            },
+            None => ptr::null(),
+        };
+
+        debug!("[{}]: compressed image, level = {}, format = {:?}, size = {:?}", self.texture.get_id(), level, compressed_format, size);
+
+        let gl_format = compressed_format.to_gl();
+        unsafe {
+            match self.target.dimensions() {
+                1 => gl::CompressedTexImage1D(self.target.to_gl(), level as GLint, gl_format,
+                    size.width as GLsizei, 0,
+                    image_size as GLsizei, pixels as *const _),
+                2 if self.target.is_cube_map() => {
+                    let face_size = image_size / size.depth as usize;
+                    for face in 0..size.depth {
+                        let face_pixels = if pixels.is_null() { pixels } else { pixels.offset((face as usize * face_size) as isize) };
+                        gl::CompressedTexImage2D(self.target.image_target(face), level as GLint, gl_format,
+                            size.width as GLsizei, size.height as GLsizei, 0,
+                            face_size as GLsizei, face_pixels as *const _);
+                    }
+                },
+                2 => gl::CompressedTexImage2D(self.target.to_gl(), level as GLint, gl_format,
+                    size.width as GLsizei, size.height as GLsizei, 0,
+                    image_size as GLsizei, pixels as *const _),
+                _ => gl::CompressedTexImage3D(self.target.to_gl(), level as GLint, gl_format,
+                    size.width as GLsizei, size.height as GLsizei, size.depth as GLsizei, 0,
+                    image_size as GLsizei, pixels as *const _),
+            }
+        };
+
+        self.texture.set_storage(storage);
+
+        Ok(())
+    }
+
+    /// Upload compressed `data` to `region` of mip `level`.
+    ///
+    /// Region must start at block boundaries and cover whole blocks, except
+    /// where it reaches the edge of the image. For layered targets `region.z`
+    /// selects the first layer or layer-face.
+    ///
+    /// ## glCompressedTexSubImage1D, glCompressedTexSubImage2D, glCompressedTexSubImage3D
+    ///
+    /// - OpenGL Version 2.0
+    /// - OpenGL ES Version 2.0
+    ///
+    pub fn compressed_sub_image(&mut self, level: u32, region: Region, data: &[u8]) -> Result<(), TextureError> {
+        let storage = match self.get_storage() {
+            Ok(storage) => storage,
+            Err(err) => return Err(err),
+        };
+
+        if let Err(err) = storage.check_level(level) {
+            return Err(err);
+        }
+
+        let compressed_format = match storage.compressed_format() {
+            Ok(compressed_format) => compressed_format,
+            Err(err) => return Err(err),
+        };
+
+        let level_size = storage.level_size(level);
+        if !region.fits(level_size) {
+            return Err(TextureError::RegionOutOfBounds { level: level, region: region, size: level_size });
+        }
+
+        let (block_width, block_height) = compressed_format.block_size();
+        let aligned = region.x % block_width == 0
+            && region.y % block_height == 0
+            && (region.size.width % block_width == 0 || region.x + region.size.width == level_size.width)
+            && (region.size.height % block_height == 0 || region.y + region.size.height == level_size.height);
+        if !aligned {
+            return Err(TextureError::RegionNotBlockAligned { region: region, block: (block_width, block_height) });
+        }
+
+        let image_size = compressed_format.image_size(region.size);
+        if let Err(err) = self.check_compressed_size(image_size, data.len()) {
+            return Err(err);
+        }
+
+        debug!("[{}]: compressed sub image, level = {}, region = {:?}", self.texture.get_id(), level, region);
+
+        let gl_format = compressed_format.to_gl();
+        unsafe {
+            match self.target.dimensions() {
+                1 => gl::CompressedTexSubImage1D(self.target.to_gl(), level as GLint,
+                    region.x as GLint,
+                    region.size.width as GLsizei,
+                    gl_format, image_size as GLsizei, data.as_ptr() as *const _),
+                2 if self.target.is_cube_map() => {
+                    let face_size = image_size / region.size.depth as usize;
+                    for face in 0..region.size.depth {
+                        gl::CompressedTexSubImage2D(self.target.image_target(region.z + face), level as GLint,
+                            region.x as GLint, region.y as GLint,
+                            region.size.width as GLsizei, region.size.height as GLsizei,
+                            gl_format, face_size as GLsizei, data[face as usize * face_size..].as_ptr() as *const _);
+                    }
+                },
+                2 => gl::CompressedTexSubImage2D(self.target.to_gl(), level as GLint,
+                    region.x as GLint, region.y as GLint,
+                    region.size.width as GLsizei, region.size.height as GLsizei,
+                    gl_format, image_size as GLsizei, data.as_ptr() as *const _),
+                _ => gl::CompressedTexSubImage3D(self.target.to_gl(), level as GLint,
+                    region.x as GLint, region.y as GLint, region.z as GLint,
+                    region.size.width as GLsizei, region.size.height as GLsizei, region.size.depth as GLsizei,
+                    gl_format, image_size as GLsizei, data.as_ptr() as *const _),
+            }
+        };
+
+        Ok(())
+    }
+
+    /// Bytes between faces of cube map data, with current unpack alignment.
+    fn face_stride(&self, pixel_format: PixelFormat, pixel_type: PixelType, size: Size) -> usize {
+        let alignment = self.units.get_unpack_alignment() as usize;
+        format::row_stride(pixel_format, pixel_type, size.width as usize, alignment) * size.height as usize
+    }
+
+    /// Validate pixel transfer and set unpack alignment so that `data_len` bytes are read as tightly packed rows.
+    fn prepare_unpack(&mut self, internal_format: InternalFormat, pixel_format: PixelFormat, pixel_type: PixelType, size: Size, data_len: usize) -> Result<(), TextureError> {
+        if let Err(err) = internal_format.check_transfer(pixel_format, pixel_type) {
+            error!("[{}]: upload error, {}", self.texture.get_id(), err);
+            return Err(TextureError::Format(err));
+        }
+
+        let row = size.width as usize * format::pixel_size(pixel_format, pixel_type);
+        if row % self.units.get_unpack_alignment() as usize != 0 {
+            self.units.set_unpack_alignment(1);
+        }
+
+        let alignment = self.units.get_unpack_alignment() as usize;
+        match format::check_transfer_size(pixel_format, pixel_type, size.width as usize, size.height as usize, size.depth as usize, alignment, data_len) {
+            Ok(_) => Ok(()),
+            Err(err) => {
+                error!("[{}]: upload error, {}", self.texture.get_id(), err);
+                Err(TextureError::Format(err))
+            },
+        }
+    }
+
+    /// Generate mip levels from the base level, up to `GL_TEXTURE_MAX_LEVEL`.
+    ///
+    /// Format must be filterable and not a depth or stencil format.
+    ///
+    /// ## glGenerateMipmap
+    ///
+    /// - OpenGL Version 3.0
+    /// - OpenGL ES Version 2.0
+    ///
+    pub fn generate_mipmap(&mut self) -> Result<(), TextureError> {
+        let storage = match self.get_storage() {
+            Ok(storage) => storage,
+            Err(err) => return Err(err),
+        };
+
+        if !self.target.has_mipmaps() {
+            return Err(TextureError::NoMipmaps(self.target));
+        }
+
+        let internal_format = match storage.uncompressed_format() {
+            Ok(internal_format) => internal_format,
+            Err(err) => return Err(err),
+        };
+
+        if !internal_format.is_filterable() || internal_format.kind() == FormatKind::Depth {
+            error!("[{}]: can not generate mipmap, {:?} is not filterable", self.texture.get_id(), internal_format);
+            return Err(TextureError::NotFilterable(internal_format));
+        }
+
+        debug!("[{}]: generate mipmap", self.texture.get_id());
+        unsafe { gl::GenerateMipmap(self.target.to_gl()) };
+
+        if !storage.immutable {
+            // Levels past `GL_TEXTURE_MAX_LEVEL`, which `allocate` lowers, are not generated.
+            let mut max_level: GLint = 0;
+            unsafe { gl::GetTexParameteriv(self.target.to_gl(), gl::TEXTURE_MAX_LEVEL, &mut max_level) };
+            let levels = cmp::min(self.target.mip_count(storage.size), max_level as u32 + 1);
+            self.texture.set_storage(TextureStorage {
+                levels: cmp::max(levels, storage.levels),
+                .. storage
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Read layer, face or slice `z` of mip `level` back to client memory.
+    ///
+    /// Uses `glGetTextureSubImage` when available, so only the requested
+    /// image is transferred. Otherwise the whole level is read with
+    /// `glGetTexImage`. Rows are returned top to bottom.
+    ///
+    /// ## glGetTextureSubImage
+    ///
+    /// - OpenGL Version 4.5
+    ///
+    /// ## glGetTexImage
+    ///
+    /// - OpenGL Version 2.0
+    ///
+    pub fn read_image<P: Pixel>(&mut self, level: u32, z: u32) -> Result<PixelBuffer<P>, TextureError> {
+        let storage = match self.texture.get_storage() {
+            Some(storage) => storage,
+            None => return Err(TextureError::NoStorage(self.texture.get_id())),
+        };
+
+        if level >= storage.levels {
+            return Err(TextureError::LevelOutOfRange { level: level, levels: storage.levels });
+        }
+
+        let size = storage.level_size(level);
+        if z >= size.depth {
+            return Err(TextureError::RegionOutOfBounds {
+                level: level,
+                region: Region::new(0, 0, z, Size::new_2d(size.width, size.height)),
+                size: size,
+            });
+        }
+
+        let internal_format = match storage.uncompressed_format() {
+            Ok(internal_format) => internal_format,
+            Err(err) => return Err(err),
+        };
+
+        if let Err(err) = internal_format.check_readback(P::format(), P::pixel_type()) {
+            error!("[{}]: readback error, {}", self.texture.get_id(), err);
+            return Err(TextureError::Format(err));
+        }
+
+        let id = self.texture.get_id();
+        let target = self.target;
+        let alignment = self.units.get_pack_alignment() as usize;
+        let stride = format::row_stride(P::format(), P::pixel_type(), size.width as usize, alignment);
+        let image_len = stride * size.height as usize;
+
+        debug!("[{}]: read image, level = {}, z = {}", id, level, z);
+
+        let (data, offset) = if self.units.is_sub_image_readback_supported() {
+            let len = format::transfer_size(P::format(), P::pixel_type(), size.width as usize, size.height as usize, 1, alignment);
+            let mut data = vec![0u8; len];
+            unsafe {
+                gl::GetTextureSubImage(id, level as GLint, 0, 0, z as GLint,
+                    size.width as GLsizei, size.height as GLsizei, 1,
+                    P::format().to_gl(), P::pixel_type().to_gl(), len as GLsizei, data.as_mut_ptr() as *mut _)
+            };
+            (data, 0)
+        } else if target.is_cube_map() {
+            let len = format::transfer_size(P::format(), P::pixel_type(), size.width as usize, size.height as usize, 1, alignment);
+            let mut data = vec![0u8; len];
+            unsafe {
+                gl::GetTexImage(target.image_target(z), level as GLint,
+                    P::format().to_gl(), P::pixel_type().to_gl(), data.as_mut_ptr() as *mut _)
+            };
+            (data, 0)
+        } else {
+            let len = format::transfer_size(P::format(), P::pixel_type(), size.width as usize, size.height as usize, size.depth as usize, alignment);
+            let mut data = vec![0u8; len];
+            unsafe {
+                gl::GetTexImage(target.to_gl(), level as GLint,
+                    P::format().to_gl(), P::pixel_type().to_gl(), data.as_mut_ptr() as *mut _)
+            };
+            (data, z as usize * image_len)
+        };
+
+        Ok(PixelBuffer::from_gl_rows(size.width, size.height, &data[offset..], stride))
+    }
+}
+
+/// View pixel slice as bytes.
+fn as_bytes<P: Pixel>(data: &[P]) -> &[u8] {
+    unsafe { slice::from_raw_parts(data.as_ptr() as *const u8, data.len() * mem::size_of::<P>()) }
+}
@@ -0,0 +1,286 @@
+use gl::types::*;
+
+use role::Generator;
+
+use super::{ Texture, TextureTarget, TextureError, TextureUnits, CubeFace };
+use super::format::{ InternalFormat, TextureFormat, Pixel };
+use super::storage::{ Size, Region, TextureStorage };
+use super::readback::PixelBuffer;
+
+/// Generate texture and allocate its images.
+fn create(units: &mut TextureUnits, target: TextureTarget, levels: u32, internal_format: InternalFormat, size: Size) -> Result<Texture, TextureError> {
+    let texture = units.gen_one();
+    let result = match units.bind_for_update(target, &texture) {
+        Ok(mut binding) => binding.allocate(levels, internal_format, size),
+        Err(err) => Err(err),
+    };
+    match result {
+        Ok(()) => Ok(texture),
+        Err(err) => {
+            error!("[{}]: failed to create {:?} texture, {}", texture.get_id(), target, err);
+            Err(err)
+        },
+    }
+}
+
+/// Upload `data` to `region` of mip `level`.
+fn upload<P: Pixel>(units: &mut TextureUnits, target: TextureTarget, texture: &Texture, level: u32, region: Region, data: &[P]) -> Result<(), TextureError> {
+    match units.bind_for_update(target, texture) {
+        Ok(mut binding) => binding.sub_image(level, region, data),
+        Err(err) => Err(err),
+    }
+}
+
+/// Upload `data` to rectangle `region` of layer or face `z` of mip `level`.
+///
+/// `region` must be two-dimensional, at z 0 with depth of 1.
+fn upload_flat<P: Pixel>(units: &mut TextureUnits, target: TextureTarget, texture: &Texture, level: u32, z: u32, region: Region, data: &[P]) -> Result<(), TextureError> {
+    if region.z != 0 || region.size.depth != 1 {
+        let size = match texture.get_storage() {
+            Some(storage) => storage.level_size(level),
+            None => Size::new_2d(0, 0),
+        };
+        error!("[{}]: upload error, region {:?} is not two-dimensional", texture.get_id(), region);
+        return Err(TextureError::RegionOutOfBounds { level: level, region: region, size: Size::new_2d(size.width, size.height) });
+    }
+
+    upload(units, target, texture, level, Region { z: z, .. region }, data)
+}
+
+macro_rules! impl_typed_texture {
+    ($name:ident, $target:expr) => {
+        impl $name {
+            /// Get underlying texture object.
+            pub fn get_texture(&self) -> &Texture {
+                &self.texture
+            }
+
+            /// Get raw name.
+            pub fn get_id(&self) -> GLuint {
+                self.texture.get_id()
+            }
+
+            /// Get format and size of allocated images.
+            pub fn get_storage(&self) -> TextureStorage {
+                self.texture.get_storage().unwrap()
+            }
+
+            pub fn get_format(&self) -> TextureFormat {
+                self.get_storage().format
+            }
+
+            /// Number of allocated mip levels.
+            pub fn get_levels(&self) -> u32 {
+                self.get_storage().levels
+            }
+
+            /// Bind texture to its target of texture `unit`.
+            pub fn bind(&self, units: &mut TextureUnits, unit: GLuint) -> Result<(), TextureError> {
+                units.bind(unit, $target, &self.texture)
+            }
+
+            /// Generate all mip levels from the base level.
+            pub fn generate_mipmap(&self, units: &mut TextureUnits) -> Result<(), TextureError> {
+                match units.bind_for_update($target, &self.texture) {
+                    Ok(mut binding) => binding.generate_mipmap(),
+                    Err(err) => Err(err),
+                }
+            }
+
+            /// Read layer, face or slice `z` of mip `level` back to client memory.
+            pub fn read_image<P: Pixel>(&self, units: &mut TextureUnits, level: u32, z: u32) -> Result<PixelBuffer<P>, TextureError> {
+                match units.bind_for_update($target, &self.texture) {
+                    Ok(mut binding) => binding.read_image(level, z),
+                    Err(err) => Err(err),
+                }
+            }
+        }
+    };
+}
+
+/// Texture of `GL_TEXTURE_2D` target.
+#[derive(Clone)]
+pub struct Texture2D {
+    texture: Texture,
+}
+
+impl_typed_texture!(Texture2D, TextureTarget::Texture2D);
+
+impl Texture2D {
+    pub fn new(units: &mut TextureUnits, levels: u32, internal_format: InternalFormat, width: u32, height: u32) -> Result<Texture2D, TextureError> {
+        create(units, TextureTarget::Texture2D, levels, internal_format, Size::new_2d(width, height))
+            .map(|texture| Texture2D { texture: texture })
+    }
+
+    pub fn get_width(&self) -> u32 {
+        self.get_storage().size.width
+    }
+
+    pub fn get_height(&self) -> u32 {
+        self.get_storage().size.height
+    }
+
+    /// Upload `data` to rectangle `region` of mip `level`, see `Region::new_2d`.
+    pub fn upload<P: Pixel>(&self, units: &mut TextureUnits, level: u32, region: Region, data: &[P]) -> Result<(), TextureError> {
+        upload_flat(units, TextureTarget::Texture2D, &self.texture, level, 0, region, data)
+    }
+}
+
+/// Texture of `GL_TEXTURE_3D` target.
+#[derive(Clone)]
+pub struct Texture3D {
+    texture: Texture,
+}
+
+impl_typed_texture!(Texture3D, TextureTarget::Texture3D);
+
+impl Texture3D {
+    pub fn new(units: &mut TextureUnits, levels: u32, internal_format: InternalFormat, width: u32, height: u32, depth: u32) -> Result<Texture3D, TextureError> {
+        create(units, TextureTarget::Texture3D, levels, internal_format, Size::new(width, height, depth))
+            .map(|texture| Texture3D { texture: texture })
+    }
+
+    pub fn get_size(&self) -> Size {
+        self.get_storage().size
+    }
+
+    /// Upload `data` to box of mip `level`.
+    pub fn upload<P: Pixel>(&self, units: &mut TextureUnits, level: u32, region: Region, data: &[P]) -> Result<(), TextureError> {
+        upload(units, TextureTarget::Texture3D, &self.texture, level, region, data)
+    }
+}
+
+/// Texture of `GL_TEXTURE_2D_ARRAY` target.
+#[derive(Clone)]
+pub struct Texture2DArray {
+    texture: Texture,
+}
+
+impl_typed_texture!(Texture2DArray, TextureTarget::Texture2DArray);
+
+impl Texture2DArray {
+    pub fn new(units: &mut TextureUnits, levels: u32, internal_format: InternalFormat, width: u32, height: u32, layers: u32) -> Result<Texture2DArray, TextureError> {
+        create(units, TextureTarget::Texture2DArray, levels, internal_format, Size::new(width, height, layers))
+            .map(|texture| Texture2DArray { texture: texture })
+    }
+
+    pub fn get_width(&self) -> u32 {
+        self.get_storage().size.width
+    }
+
+    pub fn get_height(&self) -> u32 {
+        self.get_storage().size.height
+    }
+
+    pub fn get_layers(&self) -> u32 {
+        self.get_storage().size.depth
+    }
+
+    /// Upload `data` to rectangle `region` of mip `level` of a single `layer`.
+    pub fn upload_layer<P: Pixel>(&self, units: &mut TextureUnits, layer: u32, level: u32, region: Region, data: &[P]) -> Result<(), TextureError> {
+        upload_flat(units, TextureTarget::Texture2DArray, &self.texture, level, layer, region, data)
+    }
+
+    /// Upload `data` to consecutive layers, starting at `region.z`.
+    pub fn upload_layers<P: Pixel>(&self, units: &mut TextureUnits, level: u32, region: Region, data: &[P]) -> Result<(), TextureError> {
+        upload(units, TextureTarget::Texture2DArray, &self.texture, level, region, data)
+    }
+}
+
+/// Texture of `GL_TEXTURE_CUBE_MAP` target.
+#[derive(Clone)]
+pub struct TextureCube {
+    texture: Texture,
+}
+
+impl_typed_texture!(TextureCube, TextureTarget::CubeMap);
+
+impl TextureCube {
+    /// Create cube map with square faces of `size`.
+    pub fn new(units: &mut TextureUnits, levels: u32, internal_format: InternalFormat, size: u32) -> Result<TextureCube, TextureError> {
+        create(units, TextureTarget::CubeMap, levels, internal_format, Size::new(size, size, 6))
+            .map(|texture| TextureCube { texture: texture })
+    }
+
+    /// Width and height of faces.
+    pub fn get_size(&self) -> u32 {
+        self.get_storage().size.width
+    }
+
+    /// Upload `data` to rectangle `region` of mip `level` of a single `face`.
+    pub fn upload_face<P: Pixel>(&self, units: &mut TextureUnits, face: CubeFace, level: u32, region: Region, data: &[P]) -> Result<(), TextureError> {
+        upload_flat(units, TextureTarget::CubeMap, &self.texture, level, face.index(), region, data)
+    }
+
+    /// Upload whole mip `level` of all faces, stored one after another in `CubeFace` order.
+    pub fn upload_faces<P: Pixel>(&self, units: &mut TextureUnits, level: u32, data: &[P]) -> Result<(), TextureError> {
+        let size = self.get_storage().level_size(level);
+        upload(units, TextureTarget::CubeMap, &self.texture, level, Region::whole(size), data)
+    }
+}
+
+/// Texture of `GL_TEXTURE_CUBE_MAP_ARRAY` target.
+#[derive(Clone)]
+pub struct TextureCubeArray {
+    texture: Texture,
+}
+
+impl_typed_texture!(TextureCubeArray, TextureTarget::CubeMapArray);
+
+impl TextureCubeArray {
+    /// Create array of `layers` cube maps with square faces of `size`.
+    pub fn new(units: &mut TextureUnits, levels: u32, internal_format: InternalFormat, size: u32, layers: u32) -> Result<TextureCubeArray, TextureError> {
+        create(units, TextureTarget::CubeMapArray, levels, internal_format, Size::new(size, size, layers * 6))
+            .map(|texture| TextureCubeArray { texture: texture })
+    }
+
+    /// Width and height of faces.
+    pub fn get_size(&self) -> u32 {
+        self.get_storage().size.width
+    }
+
+    /// Number of cube maps.
+    pub fn get_layers(&self) -> u32 {
+        self.get_storage().size.depth / 6
+    }
+
+    /// Upload `data` to rectangle `region` of mip `level` of a single `face` of cube map `layer`.
+    pub fn upload_face<P: Pixel>(&self, units: &mut TextureUnits, layer: u32, face: CubeFace, level: u32, region: Region, data: &[P]) -> Result<(), TextureError> {
+        upload_flat(units, TextureTarget::CubeMapArray, &self.texture, level, layer * 6 + face.index(), region, data)
+    }
+
+    /// Upload whole mip `level` of all faces of cube map `layer`.
+    pub fn upload_layer<P: Pixel>(&self, units: &mut TextureUnits, layer: u32, level: u32, data: &[P]) -> Result<(), TextureError> {
+        let size = self.get_storage().level_size(level);
+        upload(units, TextureTarget::CubeMapArray, &self.texture, level,
+            Region::new(0, 0, layer * 6, Size::new(size.width, size.height, 6)), data)
+    }
+}
+
+/// Texture of `GL_TEXTURE_RECTANGLE` target, without mipmaps and addressed by texel coordinates.
+#[derive(Clone)]
+pub struct TextureRectangle {
+    texture: Texture,
+}
+
+impl_typed_texture!(TextureRectangle, TextureTarget::Rectangle);
+
+impl TextureRectangle {
+    pub fn new(units: &mut TextureUnits, internal_format: InternalFormat, width: u32, height: u32) -> Result<TextureRectangle, TextureError> {
+        create(units, TextureTarget::Rectangle, 1, internal_format, Size::new_2d(width, height))
+            .map(|texture| TextureRectangle { texture: texture })
+    }
+
+    pub fn get_width(&self) -> u32 {
+        self.get_storage().size.width
+    }
+
+    pub fn get_height(&self) -> u32 {
+        self.get_storage().size.height
+    }
+
+    /// Upload `data` to rectangle `region` of the image, see `Region::new_2d`.
+    pub fn upload<P: Pixel>(&self, units: &mut TextureUnits, region: Region, data: &[P]) -> Result<(), TextureError> {
+        upload_flat(units, TextureTarget::Rectangle, &self.texture, 0, 0, region, data)
+    }
+}
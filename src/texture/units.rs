@@ -0,0 +1,344 @@
+use gl;
+use gl::types::*;
+
+use version::{ Api, GLVersion, has_extension };
+
+use super::{ Texture, TextureTarget, TextureError, TextureBinding };
+use super::compressed::CompressedFormat;
+use super::storage;
+
+/// Tracks active texture unit and textures bound to each target of each unit.
+///
+/// Number of units is queried from `GL_MAX_COMBINED_TEXTURE_IMAGE_UNITS` on
+/// first use. Redundant `glActiveTexture` and `glBindTexture` calls are skipped.
+pub struct TextureUnits {
+    active: GLuint,
+    units: Vec<Vec<Option<Texture>>>,
+    unpack_alignment: GLint,
+    pack_alignment: GLint,
+    storage_supported: Option<bool>,
+    sub_image_readback_supported: Option<bool>,
+    view_supported: Option<bool>,
+    copy_image_supported: Option<bool>,
+    compressed_formats: Option<Vec<GLenum>>,
+}
+
+impl TextureUnits {
+    pub fn new() -> TextureUnits {
+        TextureUnits {
+            active: 0,
+            units: Vec::new(),
+            unpack_alignment: 4,
+            pack_alignment: 4,
+            storage_supported: None,
+            sub_image_readback_supported: None,
+            view_supported: None,
+            copy_image_supported: None,
+            compressed_formats: None,
+        }
+    }
+
+    fn ensure_units(&mut self) {
+        if self.units.len() > 0 {
+            return;
+        }
+
+        let mut count: GLint = 0;
+        unsafe { gl::GetIntegerv(gl::MAX_COMBINED_TEXTURE_IMAGE_UNITS, &mut count) };
+
+        debug!("texture units, count = {}", count);
+
+        let targets = TextureTarget::all().len();
+        self.units = (0..count).map(|_| vec![None; targets]).collect();
+    }
+
+    /// Number of texture units.
+    pub fn count(&mut self) -> GLuint {
+        self.ensure_units();
+        self.units.len() as GLuint
+    }
+
+    fn check_unit(&mut self, unit: GLuint) -> Result<(), TextureError> {
+        let count = self.count();
+        if unit >= count {
+            error!("texture unit {} out of range, count = {}", unit, count);
+            return Err(TextureError::UnitOutOfRange { unit: unit, count: count });
+        }
+        Ok(())
+    }
+
+    /// Get active texture unit.
+    pub fn get_active(&self) -> GLuint {
+        self.active
+    }
+
+    /// Select active texture unit.
+    ///
+    /// ## glActiveTexture
+    ///
+    /// - OpenGL Version 2.0
+    /// - OpenGL ES Version 2.0
+    ///
+    pub fn set_active(&mut self, unit: GLuint) -> Result<(), TextureError> {
+        if let Err(err) = self.check_unit(unit) {
+            return Err(err);
+        }
+
+        if self.active != unit {
+            trace!("active texture unit {}", unit);
+            self.active = unit;
+            unsafe { gl::ActiveTexture(gl::TEXTURE0 + unit) };
+        }
+
+        Ok(())
+    }
+
+    /// Bind texture to target of texture unit.
+    ///
+    /// The first target a texture is bound to fixes its type, binding it to
+    /// other target later fails.
+    ///
+    /// ## glBindTexture
+    ///
+    /// - OpenGL Version 2.0
+    /// - OpenGL ES Version 2.0
+    ///
+    pub fn bind(&mut self, unit: GLuint, target: TextureTarget, texture: &Texture) -> Result<(), TextureError> {
+        if let Err(err) = texture.claim_target(target) {
+            error!("[{}]: bind error, {}", texture.get_id(), err);
+            return Err(err);
+        }
+
+        if let Err(err) = self.check_unit(unit) {
+            return Err(err);
+        }
+
+        if self.get_bound_id(unit, target) == Some(texture.get_id()) {
+            trace!("[{}]: already bound to {:?} of unit {}", texture.get_id(), target, unit);
+            return Ok(());
+        }
+
+        if let Err(err) = self.set_active(unit) {
+            return Err(err);
+        }
+
+        debug!("[{}]: bind {:?} of unit {}", texture.get_id(), target, unit);
+        unsafe { gl::BindTexture(target.to_gl(), texture.get_id()) };
+
+        self.units[unit as usize][target.index()] = Some(texture.clone());
+
+        Ok(())
+    }
+
+    /// Bind texture to target of the active unit for allocation and uploads.
+    ///
+    /// ## glBindTexture
+    ///
+    /// - OpenGL Version 2.0
+    /// - OpenGL ES Version 2.0
+    ///
+    pub fn bind_for_update<'a>(&'a mut self, target: TextureTarget, texture: &Texture) -> Result<TextureBinding<'a>, TextureError> {
+        let unit = self.active;
+        if let Err(err) = self.bind(unit, target, texture) {
+            return Err(err);
+        }
+        Ok(storage::new_binding(self, target, texture.clone()))
+    }
+
+    /// Returns true if immutable storage with `glTexStorage*` is available.
+    ///
+    /// Requires OpenGL 4.2, OpenGL ES 3.0 or `ARB_texture_storage`.
+    pub fn is_storage_supported(&mut self) -> bool {
+        if let Some(supported) = self.storage_supported {
+            return supported;
+        }
+
+        let supported = match GLVersion::current() {
+            Some(version) => version.is_at_least(Api::Gl, 4, 2)
+                || version.is_at_least(Api::GlEs, 3, 0)
+                || has_extension("GL_ARB_texture_storage"),
+            None => false,
+        };
+
+        debug!("immutable texture storage supported = {}", supported);
+
+        self.storage_supported = Some(supported);
+        supported
+    }
+
+    /// Returns true if `glGetTextureSubImage` is available.
+    ///
+    /// Requires OpenGL 4.5 or `ARB_get_texture_sub_image`.
+    pub fn is_sub_image_readback_supported(&mut self) -> bool {
+        if let Some(supported) = self.sub_image_readback_supported {
+            return supported;
+        }
+
+        let supported = match GLVersion::current() {
+            Some(version) => version.is_at_least(Api::Gl, 4, 5)
+                || has_extension("GL_ARB_get_texture_sub_image"),
+            None => false,
+        };
+
+        debug!("texture sub image readback supported = {}", supported);
+
+        self.sub_image_readback_supported = Some(supported);
+        supported
+    }
+
+    /// Returns true if `glTextureView` is available.
+    ///
+    /// Requires OpenGL 4.3 or `ARB_texture_view`. `OES_texture_view` is not
+    /// accepted, because the loader does not provide its entry point.
+    pub fn is_view_supported(&mut self) -> bool {
+        if let Some(supported) = self.view_supported {
+            return supported;
+        }
+
+        let supported = match GLVersion::current() {
+            Some(version) => version.is_at_least(Api::Gl, 4, 3)
+                || has_extension("GL_ARB_texture_view"),
+            None => false,
+        };
+
+        debug!("texture views supported = {}", supported);
+
+        self.view_supported = Some(supported);
+        supported
+    }
+
+    /// Returns true if `glCopyImageSubData` is available.
+    ///
+    /// Requires OpenGL 4.3, OpenGL ES 3.2 or `ARB_copy_image`.
+    pub fn is_copy_image_supported(&mut self) -> bool {
+        if let Some(supported) = self.copy_image_supported {
+            return supported;
+        }
+
+        let supported = match GLVersion::current() {
+            Some(version) => version.is_at_least(Api::Gl, 4, 3)
+                || version.is_at_least(Api::GlEs, 3, 2)
+                || has_extension("GL_ARB_copy_image"),
+            None => false,
+        };
+
+        debug!("copy image supported = {}", supported);
+
+        self.copy_image_supported = Some(supported);
+        supported
+    }
+
+    /// Get compressed internal formats supported by the context.
+    ///
+    /// ## glGetIntegerv(GL_COMPRESSED_TEXTURE_FORMATS)
+    ///
+    /// - OpenGL Version 2.0
+    /// - OpenGL ES Version 2.0
+    ///
+    pub fn get_compressed_formats(&mut self) -> &[GLenum] {
+        if self.compressed_formats.is_none() {
+            let mut count: GLint = 0;
+            unsafe { gl::GetIntegerv(gl::NUM_COMPRESSED_TEXTURE_FORMATS, &mut count) };
+
+            let mut values: Vec<GLint> = vec![0; count as usize];
+            if count > 0 {
+                unsafe { gl::GetIntegerv(gl::COMPRESSED_TEXTURE_FORMATS, values.as_mut_ptr()) };
+            }
+
+            debug!("compressed texture formats, count = {}", count);
+
+            self.compressed_formats = Some(values.into_iter().map(|value| value as GLenum).collect());
+        }
+        self.compressed_formats.as_ref().unwrap()
+    }
+
+    /// Returns true if `format` is listed in `GL_COMPRESSED_TEXTURE_FORMATS`.
+    pub fn is_compressed_format_supported(&mut self, format: CompressedFormat) -> bool {
+        let value = format.to_gl();
+        self.get_compressed_formats().contains(&value)
+    }
+
+    /// Get row alignment of client pixel data for readback.
+    pub fn get_pack_alignment(&self) -> GLint {
+        self.pack_alignment
+    }
+
+    /// Set row alignment of client pixel data for readback.
+    ///
+    /// ## glPixelStorei(GL_PACK_ALIGNMENT)
+    ///
+    /// - OpenGL Version 2.0
+    /// - OpenGL ES Version 2.0
+    ///
+    pub fn set_pack_alignment(&mut self, alignment: GLint) {
+        if self.pack_alignment != alignment {
+            trace!("pack alignment {}", alignment);
+            self.pack_alignment = alignment;
+            unsafe { gl::PixelStorei(gl::PACK_ALIGNMENT, alignment) };
+        }
+    }
+
+    /// Get row alignment of client pixel data for uploads.
+    pub fn get_unpack_alignment(&self) -> GLint {
+        self.unpack_alignment
+    }
+
+    /// Set row alignment of client pixel data for uploads.
+    ///
+    /// ## glPixelStorei(GL_UNPACK_ALIGNMENT)
+    ///
+    /// - OpenGL Version 2.0
+    /// - OpenGL ES Version 2.0
+    ///
+    pub fn set_unpack_alignment(&mut self, alignment: GLint) {
+        if self.unpack_alignment != alignment {
+            trace!("unpack alignment {}", alignment);
+            self.unpack_alignment = alignment;
+            unsafe { gl::PixelStorei(gl::UNPACK_ALIGNMENT, alignment) };
+        }
+    }
+
+    /// Unbind texture from target of texture unit.
+    ///
+    /// ## glBindTexture(0)
+    ///
+    /// - OpenGL Version 2.0
+    /// - OpenGL ES Version 2.0
+    ///
+    pub fn unbind(&mut self, unit: GLuint, target: TextureTarget) -> Result<(), TextureError> {
+        if self.get_bound_id(unit, target).is_none() {
+            return Ok(());
+        }
+
+        if let Err(err) = self.set_active(unit) {
+            return Err(err);
+        }
+
+        debug!("unbind {:?} of unit {}", target, unit);
+        unsafe { gl::BindTexture(target.to_gl(), 0) };
+
+        self.units[unit as usize][target.index()] = None;
+
+        Ok(())
+    }
+
+    /// Get name of texture bound to target of texture unit.
+    pub fn get_bound_id(&self, unit: GLuint, target: TextureTarget) -> Option<GLuint> {
+        self.units.get(unit as usize)
+            .and_then(|targets| targets[target.index()].as_ref())
+            .map(|texture| texture.get_id())
+    }
+
+    /// List all bindings as unit, target and texture name.
+    pub fn bound_ids(&self) -> Vec<(GLuint, TextureTarget, GLuint)> {
+        let mut bound = Vec::new();
+        for (unit, targets) in self.units.iter().enumerate() {
+            for (index, texture) in targets.iter().enumerate() {
+                if let &Some(ref texture) = texture {
+                    bound.push((unit as GLuint, TextureTarget::all()[index], texture.get_id()));
+                }
+            }
+        }
+        bound
+    }
+}
@@ -0,0 +1,242 @@
+use gl;
+use gl::types::*;
+
+use role::Generator;
+
+use super::{ Texture, TextureTarget, TextureError, TextureUnits };
+use super::format::{ InternalFormat, TextureFormat };
+use super::storage::{ Size, Region, TextureStorage };
+
+/// Mip levels and layers of the original texture covered by a view.
+///
+/// Layers of cube maps and cube map arrays are counted in layer-faces.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct ViewRange {
+    pub min_level: u32,
+    pub levels: u32,
+    pub min_layer: u32,
+    pub layers: u32,
+}
+
+impl ViewRange {
+    pub fn new(min_level: u32, levels: u32, min_layer: u32, layers: u32) -> ViewRange {
+        ViewRange { min_level: min_level, levels: levels, min_layer: min_layer, layers: layers }
+    }
+
+    /// All levels and layers of `storage`.
+    pub fn whole(storage: &TextureStorage) -> ViewRange {
+        let layers = if storage.target.is_layered() { storage.size.depth } else { 1 };
+        ViewRange::new(0, storage.levels, 0, layers)
+    }
+}
+
+/// Create texture sharing `range` of `original` texture's images, reinterpreted as `target` and `format`.
+///
+/// Original texture must have immutable storage, and `format` must be in
+/// the same compatibility class as its format. The view has immutable
+/// storage of its own, starting at `range.min_level`.
+///
+/// ## glTextureView
+///
+/// - OpenGL Version 4.3
+///
+pub fn create_view(units: &mut TextureUnits, original: &Texture, target: TextureTarget, format: TextureFormat, range: ViewRange) -> Result<Texture, TextureError> {
+    if !units.is_view_supported() {
+        return Err(TextureError::ViewUnsupported);
+    }
+
+    let storage = match original.get_storage() {
+        Some(storage) if storage.immutable => storage,
+        Some(_) => return Err(TextureError::NotImmutable(original.get_id())),
+        None => return Err(TextureError::NoStorage(original.get_id())),
+    };
+
+    if !storage.target.can_view_as(target) {
+        return Err(TextureError::ViewTargetIncompatible { original: storage.target, view: target });
+    }
+
+    if !storage.format.is_view_compatible(format) {
+        error!("[{}]: can not view {:?} as {:?}", original.get_id(), storage.format, format);
+        return Err(TextureError::FormatIncompatible { source: storage.format, destination: format });
+    }
+
+    let max = storage.levels.saturating_sub(range.min_level);
+    if range.levels == 0 || range.levels > max {
+        return Err(TextureError::InvalidLevelCount { levels: range.levels, max: max });
+    }
+
+    let available = if storage.target.is_layered() { storage.size.depth } else { 1 };
+    let layers_valid = match target {
+        TextureTarget::CubeMap => range.layers == 6,
+        TextureTarget::CubeMapArray => range.layers > 0 && range.layers % 6 == 0,
+        TextureTarget::Texture2DArray => range.layers > 0,
+        _ => range.layers == 1,
+    };
+    if !layers_valid || range.min_layer as u64 + range.layers as u64 > available as u64 {
+        return Err(TextureError::LayerOutOfRange { target: target, min_layer: range.min_layer, layers: range.layers, available: available });
+    }
+
+    let base = storage.level_size(range.min_level);
+    let size = if storage.target.is_layered() { Size::new(base.width, base.height, range.layers) } else { base };
+    if !target.is_valid_size(size) {
+        return Err(TextureError::InvalidSize { target: target, size: size });
+    }
+
+    let texture = units.gen_one();
+
+    debug!("[{}]: view of [{}], target = {:?}, format = {:?}, range = {:?}", texture.get_id(), original.get_id(), target, format, range);
+
+    unsafe {
+        gl::TextureView(texture.get_id(), target.to_gl(), original.get_id(), format.to_gl(),
+            range.min_level, range.levels, range.min_layer, range.layers)
+    };
+
+    if let Err(err) = texture.claim_target(target) {
+        return Err(err);
+    }
+
+    texture.set_storage(TextureStorage {
+        target: target,
+        format: format,
+        size: size,
+        levels: range.levels,
+        immutable: true,
+    });
+
+    Ok(texture)
+}
+
+/// Image used as source or destination of `copy_image`.
+#[derive(Copy, Clone)]
+pub enum CopyImage<'a> {
+    /// Mip `level` of a texture with allocated storage.
+    Texture { texture: &'a Texture, level: u32 },
+    /// Renderbuffer name, with format and size it was allocated with.
+    Renderbuffer { id: GLuint, format: InternalFormat, width: u32, height: u32 },
+}
+
+impl<'a> CopyImage<'a> {
+    /// Get name, target, level, format and level size passed to the copy.
+    fn resolve(&self) -> Result<(GLuint, GLenum, u32, TextureFormat, Size), TextureError> {
+        match *self {
+            CopyImage::Texture { texture, level } => {
+                let storage = match texture.get_storage() {
+                    Some(storage) => storage,
+                    None => return Err(TextureError::NoStorage(texture.get_id())),
+                };
+                if level >= storage.levels {
+                    return Err(TextureError::LevelOutOfRange { level: level, levels: storage.levels });
+                }
+                Ok((texture.get_id(), storage.target.to_gl(), level, storage.format, storage.level_size(level)))
+            },
+            CopyImage::Renderbuffer { id, format, width, height } => {
+                Ok((id, gl::RENDERBUFFER, 0, TextureFormat::Uncompressed(format), Size::new_2d(width, height)))
+            },
+        }
+    }
+}
+
+/// Check that `region` of compressed image of `size` starts at block boundaries and covers whole blocks, except at the edges.
+fn check_block_aligned(format: TextureFormat, region: Region, size: Size) -> Result<(), TextureError> {
+    let (block_width, block_height) = match format {
+        TextureFormat::Compressed(format) => format.block_size(),
+        TextureFormat::Uncompressed(_) => return Ok(()),
+    };
+    let aligned = region.x % block_width == 0
+        && region.y % block_height == 0
+        && (region.size.width % block_width == 0 || region.x + region.size.width == size.width)
+        && (region.size.height % block_height == 0 || region.y + region.size.height == size.height);
+    if !aligned {
+        return Err(TextureError::RegionNotBlockAligned { region: region, block: (block_width, block_height) });
+    }
+    Ok(())
+}
+
+/// Clamp `extent` of whole blocks at `offset` to image `size`, if only the last block is partial.
+fn clamp_edge_block(offset: u32, extent: u32, size: u32, block: u32) -> u32 {
+    let end = offset + extent;
+    if end > size && end < size + block {
+        size - offset
+    } else {
+        extent
+    }
+}
+
+/// Copy `region` of `source` image to `destination` image at `x`, `y`, `z`, without pixel conversion.
+///
+/// Formats must be compatible: both in the same view class, or an
+/// uncompressed format with texels of the same size as compressed blocks.
+/// `region` is measured in source texels. For layered targets `z` selects
+/// the layer or layer-face.
+///
+/// ## glCopyImageSubData
+///
+/// - OpenGL Version 4.3
+/// - OpenGL ES Version 3.2
+///
+pub fn copy_image(units: &mut TextureUnits, source: CopyImage, region: Region, destination: CopyImage, x: u32, y: u32, z: u32) -> Result<(), TextureError> {
+    if !units.is_copy_image_supported() {
+        return Err(TextureError::CopyImageUnsupported);
+    }
+
+    let (source_id, source_target, source_level, source_format, source_size) = match source.resolve() {
+        Ok(image) => image,
+        Err(err) => return Err(err),
+    };
+    let (destination_id, destination_target, destination_level, destination_format, destination_size) = match destination.resolve() {
+        Ok(image) => image,
+        Err(err) => return Err(err),
+    };
+
+    if !source_format.is_copy_compatible(destination_format) {
+        error!("[{}]: can not copy {:?} to {:?}", source_id, source_format, destination_format);
+        return Err(TextureError::FormatIncompatible { source: source_format, destination: destination_format });
+    }
+
+    if !region.fits(source_size) {
+        return Err(TextureError::RegionOutOfBounds { level: source_level, region: region, size: source_size });
+    }
+
+    if let Err(err) = check_block_aligned(source_format, region, source_size) {
+        return Err(err);
+    }
+
+    // Between compressed and uncompressed formats one block corresponds to one texel.
+    let destination_extent = match (source_format, destination_format) {
+        (TextureFormat::Compressed(format), TextureFormat::Uncompressed(_)) => {
+            let (block_width, block_height) = format.block_size();
+            Size::new((region.size.width + block_width - 1) / block_width, (region.size.height + block_height - 1) / block_height, region.size.depth)
+        },
+        (TextureFormat::Uncompressed(_), TextureFormat::Compressed(format)) => {
+            let (block_width, block_height) = format.block_size();
+            Size::new(
+                clamp_edge_block(x, region.size.width * block_width, destination_size.width, block_width),
+                clamp_edge_block(y, region.size.height * block_height, destination_size.height, block_height),
+                region.size.depth)
+        },
+        _ => region.size,
+    };
+    let destination_region = Region::new(x, y, z, destination_extent);
+
+    if !destination_region.fits(destination_size) {
+        return Err(TextureError::RegionOutOfBounds { level: destination_level, region: destination_region, size: destination_size });
+    }
+
+    if let Err(err) = check_block_aligned(destination_format, destination_region, destination_size) {
+        return Err(err);
+    }
+
+    debug!("[{}]: copy image, level = {}, region = {:?} to [{}], level = {}, at {},{},{}",
+        source_id, source_level, region, destination_id, destination_level, x, y, z);
+
+    unsafe {
+        gl::CopyImageSubData(
+            source_id, source_target, source_level as GLint,
+            region.x as GLint, region.y as GLint, region.z as GLint,
+            destination_id, destination_target, destination_level as GLint,
+            x as GLint, y as GLint, z as GLint,
+            region.size.width as GLsizei, region.size.height as GLsizei, region.size.depth as GLsizei)
+    };
+
+    Ok(())
+}
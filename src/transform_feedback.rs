@@ -0,0 +1,563 @@
+use gl;
+use gl::types::*;
+use std::rc::Rc;
+use std::cell::Cell;
+use std::fmt;
+
+use buffer::Buffer;
+use program;
+use shader::ShaderKind;
+use version::{ Api, GLVersion, has_extension };
+use gli;
+use role;
+
+impl role::Generator for TransformFeedbackState {
+    type Object = TransformFeedback;
+}
+
+impl gli::Generate for TransformFeedbackState {
+    fn gl_gen(size: usize) -> Vec<GLuint> {
+        let mut ids: Vec<GLuint> = vec![0; size];
+        unsafe { gl::GenTransformFeedbacks(size as GLsizei, ids.as_mut_ptr()) };
+        ids
+    }
+}
+
+impl gli::IntoObject<TransformFeedback> for TransformFeedback {
+    fn new_object(id: GLuint) -> TransformFeedback {
+        TransformFeedback::from_raw(id)
+    }
+}
+
+/// How captured varyings are written to transform feedback buffers.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum BufferMode {
+    /// All varyings are written to a single buffer.
+    Interleaved,
+    /// Each varying is written to its own buffer binding.
+    Separate,
+}
+
+impl BufferMode {
+    pub fn to_gl(&self) -> GLenum {
+        match *self {
+            BufferMode::Interleaved => gl::INTERLEAVED_ATTRIBS,
+            BufferMode::Separate => gl::SEPARATE_ATTRIBS,
+        }
+    }
+
+    pub fn from_gl(value: GLenum) -> Option<BufferMode> {
+        match value {
+            gl::INTERLEAVED_ATTRIBS => Some(BufferMode::Interleaved),
+            gl::SEPARATE_ATTRIBS => Some(BufferMode::Separate),
+            _ => None,
+        }
+    }
+}
+
+/// Primitive type captured by transform feedback.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum PrimitiveMode {
+    Points,
+    Lines,
+    Triangles,
+}
+
+impl PrimitiveMode {
+    pub fn to_gl(&self) -> GLenum {
+        match *self {
+            PrimitiveMode::Points => gl::POINTS,
+            PrimitiveMode::Lines => gl::LINES,
+            PrimitiveMode::Triangles => gl::TRIANGLES,
+        }
+    }
+
+    /// Returns true if primitives drawn with `draw_mode` can be captured in this mode.
+    ///
+    /// Only applies to programs without geometry or tessellation stages,
+    /// so adjacency modes and patches are never accepted.
+    pub fn accepts_draw_mode(&self, draw_mode: GLenum) -> bool {
+        match *self {
+            PrimitiveMode::Points => draw_mode == gl::POINTS,
+            PrimitiveMode::Lines => match draw_mode {
+                gl::LINES | gl::LINE_LOOP | gl::LINE_STRIP => true,
+                _ => false,
+            },
+            PrimitiveMode::Triangles => match draw_mode {
+                gl::TRIANGLES | gl::TRIANGLE_STRIP | gl::TRIANGLE_FAN => true,
+                _ => false,
+            },
+        }
+    }
+
+    /// Get mode capturing primitives emitted by the last vertex processing
+    /// stage of linked program `id` with attached `stages`, or `None` if the
+    /// draw mode decides it.
+    ///
+    /// Geometry shader output type takes precedence over tessellation
+    /// primitive mode.
+    ///
+    /// ## glGetProgramiv(GL_GEOMETRY_OUTPUT_TYPE, GL_TESS_GEN_MODE, GL_TESS_GEN_POINT_MODE)
+    ///
+    /// - OpenGL Version 4.0
+    /// - OpenGL ES Version 3.2
+    ///
+    pub fn emitted_by_program(id: GLuint, stages: &[ShaderKind]) -> Option<PrimitiveMode> {
+        let query = |pname: GLenum| {
+            let mut value: GLint = 0;
+            unsafe { gl::GetProgramiv(id, pname, &mut value) };
+            value as GLenum
+        };
+
+        if stages.contains(&ShaderKind::Geometry) {
+            return match query(gl::GEOMETRY_OUTPUT_TYPE) {
+                gl::POINTS => Some(PrimitiveMode::Points),
+                gl::LINE_STRIP => Some(PrimitiveMode::Lines),
+                _ => Some(PrimitiveMode::Triangles),
+            };
+        }
+
+        if stages.contains(&ShaderKind::TessEvaluation) {
+            if query(gl::TESS_GEN_POINT_MODE) == gl::TRUE as GLenum {
+                return Some(PrimitiveMode::Points);
+            }
+            return match query(gl::TESS_GEN_MODE) {
+                gl::ISOLINES => Some(PrimitiveMode::Lines),
+                _ => Some(PrimitiveMode::Triangles),
+            };
+        }
+
+        None
+    }
+}
+
+/// Raw transform feedback object wrapper to hide RAII mechanism.
+struct Raw {
+    id: GLuint,
+    /// Capture begun on this object, kept while it is paused and unbound.
+    capture: Cell<Option<Capture>>,
+}
+
+impl Drop for Raw {
+
+    /// Delete transform feedback objects.
+    ///
+    /// ## glDeleteTransformFeedbacks
+    ///
+    /// - OpenGL Version 4.0
+    /// - OpenGL ES Version 3.0
+    ///
+    fn drop(&mut self) {
+        debug!("[{}]: cleanup && delete", self.id);
+        unsafe { gl::DeleteTransformFeedbacks(1, &mut self.id) };
+    }
+}
+
+/// Capture begun on a transform feedback object.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+struct Capture {
+    mode: PrimitiveMode,
+    paused: bool,
+}
+
+/// Generates, binds and unbinds transform feedback objects, and controls capture of the bound one.
+///
+/// Capture state belongs to each object, so an object with paused capture
+/// can be unbound, and capture resumed after binding it again.
+pub struct TransformFeedbackState {
+    binding: Option<TransformFeedback>,
+}
+
+impl TransformFeedbackState {
+    pub fn new() -> TransformFeedbackState {
+        TransformFeedbackState {
+            binding: None,
+        }
+    }
+
+    /// Bind transform feedback object and return bound object variant.
+    ///
+    /// ## glBindTransformFeedback
+    ///
+    /// - OpenGL Version 4.0
+    /// - OpenGL ES Version 3.0
+    ///
+    pub fn bind(&mut self, transform_feedback: &TransformFeedback) -> TransformFeedbackBinding {
+        match self.binding {
+            None => (),
+            Some(ref old) => {
+                error!("[{}]: can not bind {} when already bound", old.get_id(), transform_feedback.get_id());
+                panic!("Can not bind multiple TransformFeedback objects to OpenGL state.");
+            }
+        };
+
+        self.binding = Some(transform_feedback.clone());
+
+        TransformFeedbackBinding::new(transform_feedback.clone())
+    }
+
+    /// Unbind transform feedback object.
+    ///
+    /// Capture must be paused or ended before unbinding.
+    ///
+    /// ## glBindTransformFeedback(0)
+    ///
+    /// - OpenGL Version 4.0
+    /// - OpenGL ES Version 3.0
+    ///
+    pub fn unbind(&mut self, binding: TransformFeedbackBinding) {
+        if binding.tf.get_active_mode().is_some() {
+            error!("[{}]: can not unbind while capture is active", binding.tf.get_id());
+            panic!("Can not unbind TransformFeedback object while capture is active.");
+        }
+
+        self.binding = None;
+    }
+
+    /// Get name of the bound transform feedback object.
+    pub fn get_bound_id(&self) -> Option<GLuint> {
+        self.binding.as_ref().map(|tf| tf.get_id())
+    }
+
+    /// Get primitive mode of the bound object's capture, if it is not paused.
+    pub fn get_active_mode(&self) -> Option<PrimitiveMode> {
+        self.binding.as_ref().and_then(|tf| tf.get_active_mode())
+    }
+
+    /// Start capturing primitives of `mode` into bound buffers.
+    ///
+    /// ## glBeginTransformFeedback
+    ///
+    /// - OpenGL Version 3.0
+    /// - OpenGL ES Version 3.0
+    ///
+    pub fn begin(&mut self, mode: PrimitiveMode) -> Result<(), TransformFeedbackError> {
+        let tf = try_bound(&self.binding);
+
+        if tf.raw.capture.get().is_some() {
+            error!("[{}]: begin, {}", tf.get_id(), TransformFeedbackError::AlreadyActive);
+            return Err(TransformFeedbackError::AlreadyActive);
+        }
+
+        debug!("[{}]: begin, {:?}", tf.get_id(), mode);
+        unsafe { gl::BeginTransformFeedback(mode.to_gl()) };
+
+        match unsafe { gl::GetError() } {
+            gl::NO_ERROR => {
+                tf.raw.capture.set(Some(Capture { mode: mode, paused: false }));
+                Ok(())
+            },
+            error => {
+                error!("[{}]: begin error, {}", tf.get_id(), error);
+                Err(TransformFeedbackError::Other(error))
+            },
+        }
+    }
+
+    /// Pause capture without ending it.
+    ///
+    /// ## glPauseTransformFeedback
+    ///
+    /// - OpenGL Version 4.0
+    /// - OpenGL ES Version 3.0
+    ///
+    pub fn pause(&mut self) -> Result<(), TransformFeedbackError> {
+        let tf = try_bound(&self.binding);
+
+        match tf.raw.capture.get() {
+            None => Err(TransformFeedbackError::NotActive),
+            Some(Capture { paused: true, .. }) => Err(TransformFeedbackError::AlreadyPaused),
+            Some(capture) => {
+                debug!("[{}]: pause", tf.get_id());
+                unsafe { gl::PauseTransformFeedback() };
+                tf.raw.capture.set(Some(Capture { paused: true, .. capture }));
+                Ok(())
+            },
+        }
+    }
+
+    /// Resume paused capture.
+    ///
+    /// ## glResumeTransformFeedback
+    ///
+    /// - OpenGL Version 4.0
+    /// - OpenGL ES Version 3.0
+    ///
+    pub fn resume(&mut self) -> Result<(), TransformFeedbackError> {
+        let tf = try_bound(&self.binding);
+
+        match tf.raw.capture.get() {
+            None => Err(TransformFeedbackError::NotActive),
+            Some(Capture { paused: false, .. }) => Err(TransformFeedbackError::NotPaused),
+            Some(capture) => {
+                debug!("[{}]: resume", tf.get_id());
+                unsafe { gl::ResumeTransformFeedback() };
+                tf.raw.capture.set(Some(Capture { paused: false, .. capture }));
+                Ok(())
+            },
+        }
+    }
+
+    /// End capture.
+    ///
+    /// ## glEndTransformFeedback
+    ///
+    /// - OpenGL Version 3.0
+    /// - OpenGL ES Version 3.0
+    ///
+    pub fn end(&mut self) -> Result<(), TransformFeedbackError> {
+        let tf = try_bound(&self.binding);
+
+        match tf.raw.capture.get() {
+            None => Err(TransformFeedbackError::NotActive),
+            Some(_) => {
+                debug!("[{}]: end", tf.get_id());
+                unsafe { gl::EndTransformFeedback() };
+                tf.raw.capture.set(None);
+                Ok(())
+            },
+        }
+    }
+
+    /// Check that primitives drawn with `draw_mode` by program `program` can be captured by active capture.
+    ///
+    /// With geometry or tessellation stages the primitives they emit are
+    /// checked instead of `draw_mode`. Nothing is checked if shaders were
+    /// detached from the program after linking.
+    pub fn check_draw_mode(&self, draw_mode: GLenum, program: GLuint) -> Result<(), TransformFeedbackError> {
+        let mode = match self.get_active_mode() {
+            Some(mode) => mode,
+            None => return Ok(()),
+        };
+
+        let stages = program::get_attached_stages(program);
+        if stages.is_empty() {
+            trace!("[{}]: no attached shaders, skip draw mode check", program);
+            return Ok(());
+        }
+
+        match PrimitiveMode::emitted_by_program(program, &stages) {
+            Some(emitted) if emitted != mode => {
+                Err(TransformFeedbackError::IncompatibleProgramOutput { capture: mode, emitted: emitted })
+            },
+            Some(_) => Ok(()),
+            None if !mode.accepts_draw_mode(draw_mode) => {
+                Err(TransformFeedbackError::IncompatibleDrawMode { capture: mode, draw: draw_mode })
+            },
+            None => Ok(()),
+        }
+    }
+}
+
+fn try_bound(binding: &Option<TransformFeedback>) -> &TransformFeedback {
+    match *binding {
+        Some(ref tf) => tf,
+        None => {
+            error!("no transform feedback object bound");
+            panic!("TransformFeedback object must be bound before capture.");
+        }
+    }
+}
+
+/// Manipulates OpenGL transform feedback object.
+#[derive(Clone)]
+pub struct TransformFeedback {
+    raw: Rc<Raw>,
+}
+
+impl TransformFeedback {
+
+    /// Create from raw name.
+    pub fn from_raw(id: GLuint) -> TransformFeedback {
+        TransformFeedback { raw: Rc::new(Raw { id: id, capture: Cell::new(None) }) }
+    }
+
+    /// Get raw name.
+    pub fn get_id(&self) -> GLuint {
+        self.raw.id
+    }
+
+    /// Returns true if capture was begun on this object and not ended, whether paused or not.
+    pub fn is_capturing(&self) -> bool {
+        self.raw.capture.get().is_some()
+    }
+
+    /// Get primitive mode of capture on this object, if it is not paused.
+    pub fn get_active_mode(&self) -> Option<PrimitiveMode> {
+        match self.raw.capture.get() {
+            Some(Capture { mode, paused: false }) => Some(mode),
+            _ => None,
+        }
+    }
+
+    /// Determine if a name corresponds to a transform feedback object.
+    ///
+    /// ## glIsTransformFeedback
+    ///
+    /// - OpenGL Version 4.0
+    /// - OpenGL ES Version 3.0
+    ///
+    pub fn is_transform_feedback(&self) -> bool {
+        unsafe { gl::IsTransformFeedback(self.get_id()) == gl::TRUE }
+    }
+}
+
+/// Manipulates OpenGL transform feedback object when it is bound.
+pub struct TransformFeedbackBinding {
+    tf: TransformFeedback,
+    buffers: Vec<Option<Rc<Buffer>>>,
+}
+
+impl TransformFeedbackBinding {
+    fn new(tf: TransformFeedback) -> TransformFeedbackBinding {
+        let binding = TransformFeedbackBinding {
+            tf: tf,
+            buffers: Vec::new(),
+        };
+
+        let new_id = binding.tf.get_id();
+
+        debug!("[{}]: bind", new_id);
+        unsafe { gl::BindTransformFeedback(gl::TRANSFORM_FEEDBACK, new_id) };
+
+        binding
+    }
+
+    fn unbind(&mut self) {
+        debug!("[{}]: unbind", self.tf.get_id());
+        unsafe { gl::BindTransformFeedback(gl::TRANSFORM_FEEDBACK, 0) };
+    }
+
+    /// Check `index` against number of transform feedback binding points.
+    ///
+    /// `GL_MAX_TRANSFORM_FEEDBACK_BUFFERS` requires OpenGL 4.0 or
+    /// `ARB_transform_feedback3`, otherwise separate attribute limit applies.
+    fn check_index(&self, index: GLuint) -> Result<(), TransformFeedbackError> {
+        let buffers_limit = match GLVersion::current() {
+            Some(version) => version.is_at_least(Api::Gl, 4, 0) || has_extension("GL_ARB_transform_feedback3"),
+            None => false,
+        };
+        let pname = if buffers_limit { gl::MAX_TRANSFORM_FEEDBACK_BUFFERS } else { gl::MAX_TRANSFORM_FEEDBACK_SEPARATE_ATTRIBS };
+
+        let mut max: GLint = 0;
+        unsafe { gl::GetIntegerv(pname, &mut max) };
+
+        if index >= max as GLuint {
+            error!("[{}]: buffer index {} out of range, max = {}", self.tf.get_id(), index, max);
+            return Err(TransformFeedbackError::IndexOutOfRange { index: index, max: max as GLuint });
+        }
+
+        Ok(())
+    }
+
+    fn keep_buffer(&mut self, index: GLuint, buffer: &Rc<Buffer>) {
+        let index = index as usize;
+        if self.buffers.len() <= index {
+            self.buffers.resize(index + 1, None);
+        }
+        self.buffers[index] = Some(buffer.clone());
+    }
+
+    /// Bind whole buffer to indexed transform feedback target.
+    ///
+    /// ## glBindBufferBase(GL_TRANSFORM_FEEDBACK_BUFFER)
+    ///
+    /// - OpenGL Version 3.0
+    /// - OpenGL ES Version 3.0
+    ///
+    pub fn bind_buffer(&mut self, index: GLuint, buffer: &Rc<Buffer>) -> Result<(), TransformFeedbackError> {
+        if let Err(err) = self.check_index(index) {
+            return Err(err);
+        }
+
+        debug!("[{}]: bind buffer {}, index = {}", self.tf.get_id(), buffer.get_id(), index);
+        unsafe { gl::BindBufferBase(gl::TRANSFORM_FEEDBACK_BUFFER, index, buffer.get_id()) };
+
+        self.keep_buffer(index, buffer);
+        Ok(())
+    }
+
+    /// Bind range of buffer to indexed transform feedback target.
+    ///
+    /// Both `offset` and `size` are in bytes and must be multiples of 4.
+    ///
+    /// ## glBindBufferRange(GL_TRANSFORM_FEEDBACK_BUFFER)
+    ///
+    /// - OpenGL Version 3.0
+    /// - OpenGL ES Version 3.0
+    ///
+    pub fn bind_buffer_range(&mut self, index: GLuint, buffer: &Rc<Buffer>, offset: usize, size: usize) -> Result<(), TransformFeedbackError> {
+        if let Err(err) = self.check_index(index) {
+            return Err(err);
+        }
+
+        if offset % 4 != 0 || size % 4 != 0 {
+            error!("[{}]: unaligned buffer range, offset = {}, size = {}", self.tf.get_id(), offset, size);
+            return Err(TransformFeedbackError::UnalignedRange { offset: offset, size: size });
+        }
+
+        debug!(
+            "[{}]: bind buffer {} range, index = {}, offset = {}, size = {}",
+            self.tf.get_id(),
+            buffer.get_id(),
+            index,
+            offset,
+            size
+        );
+        unsafe {
+            gl::BindBufferRange(
+                gl::TRANSFORM_FEEDBACK_BUFFER,
+                index,
+                buffer.get_id(),
+                offset as GLintptr,
+                size as GLsizeiptr
+            )
+        };
+
+        self.keep_buffer(index, buffer);
+        Ok(())
+    }
+}
+
+impl Drop for TransformFeedbackBinding {
+
+    /// Cleanup state and unbind transform feedback object if it is still bound.
+    ///
+    /// ## glBindTransformFeedback(0)
+    ///
+    /// - OpenGL Version 4.0
+    /// - OpenGL ES Version 3.0
+    ///
+    fn drop(&mut self) {
+        self.unbind();
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TransformFeedbackError {
+    AlreadyActive,
+    NotActive,
+    AlreadyPaused,
+    NotPaused,
+    IncompatibleDrawMode { capture: PrimitiveMode, draw: GLenum },
+    IncompatibleProgramOutput { capture: PrimitiveMode, emitted: PrimitiveMode },
+    IndexOutOfRange { index: GLuint, max: GLuint },
+    UnalignedRange { offset: usize, size: usize },
+    Other(GLenum),
+}
+
+impl fmt::Display for TransformFeedbackError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            &TransformFeedbackError::AlreadyActive => "Tried to begin transform feedback while capture is already active.".fmt(f),
+            &TransformFeedbackError::NotActive => "Tried to change transform feedback capture which is not active.".fmt(f),
+            &TransformFeedbackError::AlreadyPaused => "Tried to pause transform feedback capture which is already paused.".fmt(f),
+            &TransformFeedbackError::NotPaused => "Tried to resume transform feedback capture which is not paused.".fmt(f),
+            &TransformFeedbackError::IncompatibleDrawMode { capture, draw } => write!(f, "Tried to draw primitive mode {} while capturing {:?}.", draw, capture),
+            &TransformFeedbackError::IncompatibleProgramOutput { capture, emitted } => write!(f, "Tried to draw with program emitting {:?} while capturing {:?}.", emitted, capture),
+            &TransformFeedbackError::IndexOutOfRange { index, max } => write!(f, "Tried to bind transform feedback buffer at index {}, but maximum is {}.", index, max),
+            &TransformFeedbackError::UnalignedRange { offset, size } => write!(f, "Tried to bind transform feedback buffer range with offset {} and size {} not aligned to 4 bytes.", offset, size),
+            &TransformFeedbackError::Other(error) => write!(f, "Unrecognised error {} in transform feedback.", error),
+        }
+    }
+}
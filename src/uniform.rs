@@ -0,0 +1,56 @@
+use gl;
+use gl::types::*;
+
+/// Value of a uniform variable, uploaded with `glProgramUniform*`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum UniformValue {
+    Float(GLfloat),
+    Vec2([GLfloat; 2]),
+    Vec3([GLfloat; 3]),
+    Vec4([GLfloat; 4]),
+    Int(GLint),
+    IVec2([GLint; 2]),
+    IVec3([GLint; 3]),
+    IVec4([GLint; 4]),
+    UInt(GLuint),
+    UVec2([GLuint; 2]),
+    UVec3([GLuint; 3]),
+    UVec4([GLuint; 4]),
+    Mat2([GLfloat; 4]),
+    Mat3([GLfloat; 9]),
+    Mat4([GLfloat; 16]),
+}
+
+impl UniformValue {
+
+    /// Upload value to uniform `location` of program `id` without putting it in use.
+    ///
+    /// ## glProgramUniform*
+    ///
+    /// - OpenGL Version 4.1
+    /// - OpenGL ES Version 3.1
+    ///
+    pub fn upload(&self, id: GLuint, location: GLint) {
+        trace!("[{}]: upload uniform {}, {:?}", id, location, self);
+
+        unsafe {
+            match self {
+                &UniformValue::Float(v) => gl::ProgramUniform1f(id, location, v),
+                &UniformValue::Vec2(ref v) => gl::ProgramUniform2fv(id, location, 1, v.as_ptr()),
+                &UniformValue::Vec3(ref v) => gl::ProgramUniform3fv(id, location, 1, v.as_ptr()),
+                &UniformValue::Vec4(ref v) => gl::ProgramUniform4fv(id, location, 1, v.as_ptr()),
+                &UniformValue::Int(v) => gl::ProgramUniform1i(id, location, v),
+                &UniformValue::IVec2(ref v) => gl::ProgramUniform2iv(id, location, 1, v.as_ptr()),
+                &UniformValue::IVec3(ref v) => gl::ProgramUniform3iv(id, location, 1, v.as_ptr()),
+                &UniformValue::IVec4(ref v) => gl::ProgramUniform4iv(id, location, 1, v.as_ptr()),
+                &UniformValue::UInt(v) => gl::ProgramUniform1ui(id, location, v),
+                &UniformValue::UVec2(ref v) => gl::ProgramUniform2uiv(id, location, 1, v.as_ptr()),
+                &UniformValue::UVec3(ref v) => gl::ProgramUniform3uiv(id, location, 1, v.as_ptr()),
+                &UniformValue::UVec4(ref v) => gl::ProgramUniform4uiv(id, location, 1, v.as_ptr()),
+                &UniformValue::Mat2(ref v) => gl::ProgramUniformMatrix2fv(id, location, 1, gl::FALSE, v.as_ptr()),
+                &UniformValue::Mat3(ref v) => gl::ProgramUniformMatrix3fv(id, location, 1, gl::FALSE, v.as_ptr()),
+                &UniformValue::Mat4(ref v) => gl::ProgramUniformMatrix4fv(id, location, 1, gl::FALSE, v.as_ptr()),
+            }
+        }
+    }
+}
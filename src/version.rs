@@ -0,0 +1,137 @@
+use gl;
+use gl::types::*;
+
+use std::ffi::CStr;
+use std::fmt;
+use std::str;
+
+/// OpenGL API flavour of the context.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Api {
+    Gl,
+    GlEs,
+}
+
+/// Version of the current OpenGL context.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct GLVersion {
+    pub api: Api,
+    pub major: u32,
+    pub minor: u32,
+}
+
+impl GLVersion {
+    pub fn new(api: Api, major: u32, minor: u32) -> GLVersion {
+        GLVersion { api: api, major: major, minor: minor }
+    }
+
+    /// Query version of the current context.
+    ///
+    /// ## glGetString(GL_VERSION)
+    ///
+    /// - OpenGL Version 2.0
+    /// - OpenGL ES Version 2.0
+    ///
+    pub fn current() -> Option<GLVersion> {
+        let version = match get_string(gl::VERSION) {
+            Some(version) => version,
+            None => {
+                error!("failed to get context version string");
+                return None;
+            }
+        };
+
+        let parsed = GLVersion::parse(&version);
+
+        if parsed.is_none() {
+            error!("failed to parse context version string, {}", version);
+        }
+
+        parsed
+    }
+
+    /// Parse `GL_VERSION` string such as "4.5.0 NVIDIA 367.27" or "OpenGL ES 3.2 Mesa 17.0".
+    pub fn parse(version: &str) -> Option<GLVersion> {
+        let (api, rest) = if version.starts_with("OpenGL ES") {
+            let rest = &version["OpenGL ES".len()..];
+            // Skip profile suffix of OpenGL ES 1.x strings, such as "-CM".
+            let rest = match rest.find(' ') {
+                Some(pos) => &rest[pos + 1..],
+                None => rest,
+            };
+            (Api::GlEs, rest)
+        } else {
+            (Api::Gl, version)
+        };
+
+        let number = rest.trim().split(' ').next().unwrap_or("");
+        let mut parts = number.split('.');
+
+        let major = parts.next().and_then(|part| part.parse::<u32>().ok());
+        let minor = parts.next().and_then(|part| part.parse::<u32>().ok());
+
+        match (major, minor) {
+            (Some(major), Some(minor)) => Some(GLVersion::new(api, major, minor)),
+            _ => None,
+        }
+    }
+
+    /// Returns true if this version is at least `major.minor` of the same api.
+    pub fn is_at_least(&self, api: Api, major: u32, minor: u32) -> bool {
+        self.api == api && (self.major, self.minor) >= (major, minor)
+    }
+}
+
+impl fmt::Display for GLVersion {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.api {
+            Api::Gl => write!(f, "OpenGL {}.{}", self.major, self.minor),
+            Api::GlEs => write!(f, "OpenGL ES {}.{}", self.major, self.minor),
+        }
+    }
+}
+
+/// Get a string describing the current context, such as `gl::VERSION` or `gl::RENDERER`.
+///
+/// ## glGetString
+///
+/// - OpenGL Version 2.0
+/// - OpenGL ES Version 2.0
+///
+pub fn get_string(name: GLenum) -> Option<String> {
+    unsafe {
+        let ptr = gl::GetString(name);
+        if ptr.is_null() {
+            return None;
+        }
+        str::from_utf8(CStr::from_ptr(ptr as *const _).to_bytes())
+            .ok()
+            .map(|s| s.to_string())
+    }
+}
+
+/// Returns true if the current context reports extension `name`.
+///
+/// ## glGetStringi(GL_EXTENSIONS)
+///
+/// - OpenGL Version 3.0
+/// - OpenGL ES Version 3.0
+///
+pub fn has_extension(name: &str) -> bool {
+    let mut count: GLint = 0;
+    unsafe { gl::GetIntegerv(gl::NUM_EXTENSIONS, &mut count) };
+
+    for index in 0..count as GLuint {
+        let found = unsafe {
+            let ptr = gl::GetStringi(gl::EXTENSIONS, index);
+            !ptr.is_null() && CStr::from_ptr(ptr as *const _).to_bytes() == name.as_bytes()
+        };
+        if found {
+            trace!("extension {} found", name);
+            return true;
+        }
+    }
+
+    trace!("extension {} not found", name);
+    false
+}
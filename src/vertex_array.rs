@@ -76,6 +76,11 @@ impl VertexArrayState {
         VertexArrayBinding::new(vertex_array.clone())
     }
 
+    /// Get name of the bound vertex array object.
+    pub fn get_bound_id(&self) -> Option<GLuint> {
+        self.binding.as_ref().map(|va| va.get_id())
+    }
+
     /// Unbind vertex array and return unbound object variant.
     ///
     /// ## glBindVertexArray(0)